@@ -0,0 +1,8 @@
+-- Your SQL goes here
+CREATE TABLE public.room_bans (
+    room_uuid VARCHAR NOT NULL,
+    user_uuid VARCHAR NOT NULL,
+    banned_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (room_uuid, user_uuid),
+    FOREIGN KEY (room_uuid) REFERENCES public.rooms(uuid)
+);
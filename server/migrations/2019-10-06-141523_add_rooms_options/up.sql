@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE public.rooms
+    ADD COLUMN options JSONB NOT NULL DEFAULT '{}';
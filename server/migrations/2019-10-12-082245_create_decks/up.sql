@@ -0,0 +1,12 @@
+-- Your SQL goes here
+CREATE TABLE public.decks (
+    uuid VARCHAR PRIMARY KEY,
+    owner_uuid VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    card_set TEXT[] NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    last_updated_at TIMESTAMP NOT NULL,
+    FOREIGN KEY (owner_uuid) REFERENCES public.users(uuid)
+);
+
+CREATE INDEX decks_owner_uuid_index ON public.decks (owner_uuid);
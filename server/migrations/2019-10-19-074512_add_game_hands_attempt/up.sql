@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE public.game_hands
+    ADD COLUMN attempt INTEGER NOT NULL DEFAULT 1;
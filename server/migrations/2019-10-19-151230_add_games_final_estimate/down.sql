@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE public.games
+    DROP COLUMN final_estimate,
+    DROP COLUMN final_estimate_reason;
@@ -0,0 +1,4 @@
+-- Your SQL goes here
+ALTER TABLE public.games
+    ADD COLUMN final_estimate VARCHAR NULL,
+    ADD COLUMN final_estimate_reason VARCHAR NULL;
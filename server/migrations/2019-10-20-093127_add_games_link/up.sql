@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE public.games
+    ADD COLUMN link VARCHAR NULL;
@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX public.games_room_uuid_external_id_idx;
+
+ALTER TABLE public.games
+    DROP COLUMN external_id,
+    DROP COLUMN pre_estimate;
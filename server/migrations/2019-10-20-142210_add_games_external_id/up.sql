@@ -0,0 +1,7 @@
+-- Your SQL goes here
+ALTER TABLE public.games
+    ADD COLUMN external_id VARCHAR NULL,
+    ADD COLUMN pre_estimate VARCHAR NULL;
+
+CREATE UNIQUE INDEX games_room_uuid_external_id_idx
+    ON public.games (room_uuid, external_id);
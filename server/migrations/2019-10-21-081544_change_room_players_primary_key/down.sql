@@ -0,0 +1,12 @@
+-- This file should undo anything in `up.sql`
+DELETE FROM public.room_players
+    WHERE (room_uuid, player_uuid) NOT IN (
+        SELECT DISTINCT ON (player_uuid) room_uuid, player_uuid
+        FROM public.room_players
+        ORDER BY player_uuid, joined_at DESC
+    );
+
+ALTER TABLE public.room_players
+    DROP CONSTRAINT room_players_pkey,
+    ALTER COLUMN room_uuid DROP NOT NULL,
+    ADD PRIMARY KEY (player_uuid);
@@ -0,0 +1,7 @@
+-- Your SQL goes here
+DELETE FROM public.room_players WHERE room_uuid IS NULL;
+
+ALTER TABLE public.room_players
+    DROP CONSTRAINT room_players_pkey,
+    ALTER COLUMN room_uuid SET NOT NULL,
+    ADD PRIMARY KEY (room_uuid, player_uuid);
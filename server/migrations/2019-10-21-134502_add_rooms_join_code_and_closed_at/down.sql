@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE public.rooms
+    DROP COLUMN closed_at,
+    DROP COLUMN join_code;
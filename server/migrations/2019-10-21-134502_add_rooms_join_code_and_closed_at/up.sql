@@ -0,0 +1,4 @@
+-- Your SQL goes here
+ALTER TABLE public.rooms
+    ADD COLUMN join_code VARCHAR NULL,
+    ADD COLUMN closed_at TIMESTAMP NULL;
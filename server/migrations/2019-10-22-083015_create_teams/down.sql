@@ -0,0 +1,6 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE public.decks
+    DROP COLUMN team_uuid;
+
+DROP TABLE public.team_members;
+DROP TABLE public.teams;
@@ -0,0 +1,23 @@
+-- Your SQL goes here
+CREATE TABLE public.teams (
+    uuid VARCHAR PRIMARY KEY,
+    name VARCHAR NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    last_updated_at TIMESTAMP NOT NULL
+);
+
+CREATE TABLE public.team_members (
+    team_uuid VARCHAR NOT NULL,
+    user_uuid VARCHAR NOT NULL,
+    joined_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (team_uuid, user_uuid),
+    FOREIGN KEY (team_uuid) REFERENCES public.teams(uuid),
+    FOREIGN KEY (user_uuid) REFERENCES public.users(uuid)
+);
+
+CREATE INDEX team_members_user_uuid_index ON public.team_members (user_uuid);
+
+ALTER TABLE public.decks
+    ADD COLUMN team_uuid VARCHAR NULL REFERENCES public.teams(uuid);
+
+CREATE INDEX decks_team_uuid_index ON public.decks (team_uuid);
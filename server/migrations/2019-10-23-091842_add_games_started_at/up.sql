@@ -0,0 +1,7 @@
+-- Your SQL goes here
+ALTER TABLE public.games
+    ADD COLUMN started_at TIMESTAMP NULL;
+
+UPDATE public.games
+    SET started_at = created_at
+    WHERE sequence IS NULL;
@@ -1,36 +1,36 @@
 use actix::prelude::*;
 
 use crate::common::error::{ContextExt, ErrorKind, Result as CommonResult};
-use crate::common::message::ResponseMessage;
+use crate::common::message::response::ResponseFrame;
 
 use super::ClientChannel;
 
 pub struct DefaultClientChannel {
-    addr: Recipient<ResponseMessage>,
+    addr: Recipient<ResponseFrame>,
 }
 
 impl ClientChannel for DefaultClientChannel {
-    fn do_send(&self, msg: ResponseMessage) -> CommonResult<()> {
-        self.addr.do_send(msg).context(|| {
+    fn do_send(&self, frame: ResponseFrame) -> CommonResult<()> {
+        self.addr.do_send(frame).context(|| {
             (
                 ErrorKind::SendMessageError,
-                "Error when sending response message to client channel",
+                "Error when sending response frame to client channel",
             )
         })
     }
 
-    fn try_send(&self, msg: ResponseMessage) -> CommonResult<()> {
-        self.addr.try_send(msg).context(|| {
+    fn try_send(&self, frame: ResponseFrame) -> CommonResult<()> {
+        self.addr.try_send(frame).context(|| {
             (
                 ErrorKind::SendMessageError,
-                "Error when trying to send response message to client channel",
+                "Error when trying to send response frame to client channel",
             )
         })
     }
 }
 
 impl DefaultClientChannel {
-    pub fn new(addr: Recipient<ResponseMessage>) -> Self {
+    pub fn new(addr: Recipient<ResponseFrame>) -> Self {
         DefaultClientChannel { addr }
     }
 }
@@ -2,20 +2,20 @@
 use mockall::automock;
 
 use crate::common::error::Result as CommonResult;
-use crate::common::message::ResponseMessage;
+use crate::common::message::response::ResponseFrame;
 
 mod default;
 
 pub use default::DefaultClientChannel;
 
+/// Channel to the websocket client. Sending does not wait for the delivery.
+/// Instead, room events carry their sequence numbers, with which the client
+/// finds out the events it has missed and has them replayed.
 #[cfg_attr(test, automock)]
 pub trait ClientChannel {
-    /// Send ResponseMessage unconditionally, ignoring any potential errors.
-    fn do_send(&self, msg: ResponseMessage) -> CommonResult<()>;
+    /// Send ResponseFrame unconditionally, ignoring any potential errors.
+    fn do_send(&self, frame: ResponseFrame) -> CommonResult<()>;
 
-    /// Tries to send ResponseMessage.
-    fn try_send(&self, msg: ResponseMessage) -> CommonResult<()>;
-
-    // TODO: Implement send method which provides mechanism to track for
-    // message delivery
+    /// Tries to send ResponseFrame.
+    fn try_send(&self, frame: ResponseFrame) -> CommonResult<()>;
 }
@@ -41,4 +41,8 @@ where
     fn contains_key(&self, id: &ClientId) -> bool {
         return self.store.contains_key(id);
     }
+
+    fn remove(&mut self, id: &ClientId) -> Option<Client<T>> {
+        self.store.remove(id)
+    }
 }
@@ -71,4 +71,7 @@ where
 
     /// Returns true if the store contains client for the specified client Id.
     fn contains_key(&self, id: &ClientId) -> bool;
+
+    /// Remove client by given client Id and return it if exists
+    fn remove(&mut self, id: &ClientId) -> Option<Client<T>>;
 }
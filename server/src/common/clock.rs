@@ -0,0 +1,70 @@
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+#[cfg(test)]
+use mockall::automock;
+
+/// Source of the current time, so that time-dependent logic can be tested
+/// without waiting for wall time to pass.
+#[cfg_attr(test, automock)]
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Format time as RFC 3339 timestamp in UTC.
+pub fn format_timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let secs_of_day = secs % 86400;
+
+    // Convert days since epoch to civil date, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = secs / 86400 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::time::Duration;
+
+    #[test]
+    fn format_timestamp_should_format_time_in_utc() {
+        let time = UNIX_EPOCH + Duration::from_secs(951_827_696);
+
+        assert_eq!(format_timestamp(time), "2000-02-29T12:34:56Z");
+    }
+}
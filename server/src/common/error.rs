@@ -1,5 +1,7 @@
 use std::fmt;
 
+use actix::MailboxError;
+
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Clone)]
@@ -81,6 +83,8 @@ pub enum ErrorKind {
     ActixRuntimeError,
     ConnectionPoolError,
     InsertionError,
+    UpdateError,
+    DeletionError,
     QueryError,
 
     InvalidOAuthConfig,
@@ -92,14 +96,151 @@ pub enum ErrorKind {
     RoomNotFound,
     AlreadyJoinedError,
     UnauthenticatedError,
+    ForbiddenError,
+    PlayerNotFound,
+    BannedError,
+    RoomLockedError,
+    GameInProgressError,
+    GameNotFound,
+    StoryNotFound,
+    InvalidCardSet,
+    DeckNotFound,
+    SessionNotFound,
 
     SendMessageError,
     DeserializationError,
+    UnsupportedProtocolVersion,
 
     InvalidParams,
     UnknownError,
 }
 
+impl ErrorKind {
+    /// Stable code of the error kind exposed to clients. Codes must not be
+    /// changed once released, even when the kind is renamed.
+    pub fn code(self) -> &'static str {
+        match self {
+            ErrorKind::ServerListenError => "server_listen_error",
+            ErrorKind::InternalServerError => "internal_server_error",
+            ErrorKind::RemoteServerError => "remote_server_error",
+            ErrorKind::BadRequest => "bad_request",
+            ErrorKind::MissingDatabaseUrl => "missing_database_url",
+            ErrorKind::ActixRuntimeError => "runtime_error",
+            ErrorKind::ConnectionPoolError => "connection_pool_error",
+            ErrorKind::InsertionError => "insertion_error",
+            ErrorKind::UpdateError => "update_error",
+            ErrorKind::DeletionError => "deletion_error",
+            ErrorKind::QueryError => "query_error",
+            ErrorKind::InvalidOAuthConfig => "invalid_oauth_config",
+            ErrorKind::OAuthClientNotBuild => "oauth_client_not_built",
+            ErrorKind::UnauthorizedError => "unauthorized",
+            ErrorKind::UnsupportedProviderError => "unsupported_provider",
+            ErrorKind::MissingClientError => "missing_client",
+            ErrorKind::RoomNotFound => "room_not_found",
+            ErrorKind::AlreadyJoinedError => "already_joined",
+            ErrorKind::UnauthenticatedError => "unauthenticated",
+            ErrorKind::ForbiddenError => "forbidden",
+            ErrorKind::PlayerNotFound => "player_not_found",
+            ErrorKind::BannedError => "banned",
+            ErrorKind::RoomLockedError => "room_locked",
+            ErrorKind::GameInProgressError => "game_in_progress",
+            ErrorKind::GameNotFound => "game_not_found",
+            ErrorKind::StoryNotFound => "story_not_found",
+            ErrorKind::InvalidCardSet => "invalid_card_set",
+            ErrorKind::DeckNotFound => "deck_not_found",
+            ErrorKind::SessionNotFound => "session_not_found",
+            ErrorKind::SendMessageError => "send_message_error",
+            ErrorKind::DeserializationError => "deserialization_error",
+            ErrorKind::UnsupportedProtocolVersion => "unsupported_protocol_version",
+            ErrorKind::InvalidParams => "invalid_params",
+            ErrorKind::UnknownError => "unknown_error",
+        }
+    }
+
+    /// Error kind of the code received from the server, or None when the code
+    /// is unknown to this version.
+    pub fn from_code(code: &str) -> Option<ErrorKind> {
+        match code {
+            "server_listen_error" => Some(ErrorKind::ServerListenError),
+            "internal_server_error" => Some(ErrorKind::InternalServerError),
+            "remote_server_error" => Some(ErrorKind::RemoteServerError),
+            "bad_request" => Some(ErrorKind::BadRequest),
+            "missing_database_url" => Some(ErrorKind::MissingDatabaseUrl),
+            "runtime_error" => Some(ErrorKind::ActixRuntimeError),
+            "connection_pool_error" => Some(ErrorKind::ConnectionPoolError),
+            "insertion_error" => Some(ErrorKind::InsertionError),
+            "update_error" => Some(ErrorKind::UpdateError),
+            "deletion_error" => Some(ErrorKind::DeletionError),
+            "query_error" => Some(ErrorKind::QueryError),
+            "invalid_oauth_config" => Some(ErrorKind::InvalidOAuthConfig),
+            "oauth_client_not_built" => Some(ErrorKind::OAuthClientNotBuild),
+            "unauthorized" => Some(ErrorKind::UnauthorizedError),
+            "unsupported_provider" => Some(ErrorKind::UnsupportedProviderError),
+            "missing_client" => Some(ErrorKind::MissingClientError),
+            "room_not_found" => Some(ErrorKind::RoomNotFound),
+            "already_joined" => Some(ErrorKind::AlreadyJoinedError),
+            "unauthenticated" => Some(ErrorKind::UnauthenticatedError),
+            "forbidden" => Some(ErrorKind::ForbiddenError),
+            "player_not_found" => Some(ErrorKind::PlayerNotFound),
+            "banned" => Some(ErrorKind::BannedError),
+            "room_locked" => Some(ErrorKind::RoomLockedError),
+            "game_in_progress" => Some(ErrorKind::GameInProgressError),
+            "game_not_found" => Some(ErrorKind::GameNotFound),
+            "story_not_found" => Some(ErrorKind::StoryNotFound),
+            "invalid_card_set" => Some(ErrorKind::InvalidCardSet),
+            "deck_not_found" => Some(ErrorKind::DeckNotFound),
+            "session_not_found" => Some(ErrorKind::SessionNotFound),
+            "send_message_error" => Some(ErrorKind::SendMessageError),
+            "deserialization_error" => Some(ErrorKind::DeserializationError),
+            "unsupported_protocol_version" => Some(ErrorKind::UnsupportedProtocolVersion),
+            "invalid_params" => Some(ErrorKind::InvalidParams),
+            "unknown_error" => Some(ErrorKind::UnknownError),
+            _ => None,
+        }
+    }
+
+    /// Whether the error is caused by the request of the client, in which
+    /// case its message can be shown to the client.
+    pub fn is_client_error(self) -> bool {
+        match self {
+            ErrorKind::BadRequest
+            | ErrorKind::UnauthorizedError
+            | ErrorKind::UnsupportedProviderError
+            | ErrorKind::RoomNotFound
+            | ErrorKind::AlreadyJoinedError
+            | ErrorKind::UnauthenticatedError
+            | ErrorKind::ForbiddenError
+            | ErrorKind::PlayerNotFound
+            | ErrorKind::BannedError
+            | ErrorKind::RoomLockedError
+            | ErrorKind::GameInProgressError
+            | ErrorKind::GameNotFound
+            | ErrorKind::StoryNotFound
+            | ErrorKind::InvalidCardSet
+            | ErrorKind::DeckNotFound
+            | ErrorKind::SessionNotFound
+            | ErrorKind::DeserializationError
+            | ErrorKind::UnsupportedProtocolVersion
+            | ErrorKind::InvalidParams => true,
+            ErrorKind::ServerListenError
+            | ErrorKind::InternalServerError
+            | ErrorKind::RemoteServerError
+            | ErrorKind::MissingDatabaseUrl
+            | ErrorKind::ActixRuntimeError
+            | ErrorKind::ConnectionPoolError
+            | ErrorKind::InsertionError
+            | ErrorKind::UpdateError
+            | ErrorKind::DeletionError
+            | ErrorKind::QueryError
+            | ErrorKind::InvalidOAuthConfig
+            | ErrorKind::OAuthClientNotBuild
+            | ErrorKind::MissingClientError
+            | ErrorKind::SendMessageError
+            | ErrorKind::UnknownError => false,
+        }
+    }
+}
+
 impl From<ErrorKind> for Error {
     fn from(kind: ErrorKind) -> Error {
         Error::new(kind, "")
@@ -179,3 +320,16 @@ impl<T> ErrorKindExt<T> for std::option::Option<T> {
         })
     }
 }
+
+/// Flatten the result of a message sent to an actor. Failing to deliver the
+/// message is a runtime error.
+pub fn flatten_mailbox_result<T>(
+    result: std::result::Result<Result<T>, MailboxError>,
+) -> Result<T> {
+    result.context(|| {
+        (
+            ErrorKind::ActixRuntimeError,
+            "Error when sending message to actor",
+        )
+    })?
+}
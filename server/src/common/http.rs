@@ -0,0 +1,35 @@
+use actix_web::error::BlockingError;
+use actix_web::HttpResponse;
+use log::error;
+
+use crate::common::error::{Error, ErrorKind};
+
+/// Map error of a blocking handler to HTTP response. Details of server errors
+/// are logged instead of being exposed to the client.
+pub fn error_response(err: BlockingError<Error>) -> HttpResponse {
+    let err = match err {
+        BlockingError::Canceled => return HttpResponse::InternalServerError().into(),
+        BlockingError::Error(err) => err,
+    };
+
+    let mut builder = match err.kind() {
+        ErrorKind::BadRequest | ErrorKind::InvalidParams | ErrorKind::InvalidCardSet => {
+            HttpResponse::BadRequest()
+        }
+        ErrorKind::UnauthorizedError | ErrorKind::UnauthenticatedError => {
+            HttpResponse::Unauthorized()
+        }
+        ErrorKind::ForbiddenError | ErrorKind::BannedError => HttpResponse::Forbidden(),
+        ErrorKind::RoomNotFound
+        | ErrorKind::PlayerNotFound
+        | ErrorKind::DeckNotFound
+        | ErrorKind::GameNotFound
+        | ErrorKind::StoryNotFound => HttpResponse::NotFound(),
+        _ => {
+            error!("Error when handling HTTP request: {}", err);
+            return HttpResponse::InternalServerError().into();
+        }
+    };
+
+    builder.body(err.to_string())
+}
@@ -1,5 +1,6 @@
 pub mod request;
 pub mod response;
+pub mod schema;
 
 pub use request::RequestMessage;
 pub use response::ResponseMessage;
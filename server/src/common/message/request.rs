@@ -1,23 +1,223 @@
 use actix::prelude::*;
-use serde::Deserialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize};
 
 use crate::common::model::Uuid;
+use crate::poker::deck::DeckPreset;
+use crate::poker::model::{CardLabel, RoomOptions};
 
-#[derive(Message, Debug, Deserialize)]
+/// Request of websocket client. The ID given by the client is echoed in the
+/// reply to the request, so that the client can match them.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct RequestFrame {
+    pub request_id: Option<String>,
+    #[serde(flatten)]
+    pub req: RequestMessage,
+}
+
+#[derive(Message, Debug, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "type")]
 pub enum RequestMessage {
     CreateRoom(CreateRoomParams),
     JoinRoom(JoinRoomParams),
+    KickPlayer(KickPlayerParams),
+    BanPlayer(BanPlayerParams),
+    UpdateRoomConfig(UpdateRoomConfigParams),
+    StartGame(StartGameParams),
+    PlayCard(PlayCardParams),
+    RevealCards,
+    ResetGame(ResetGameParams),
+    FinalizeGame(FinalizeGameParams),
+    AddStory(AddStoryParams),
+    ReorderBacklog(ReorderBacklogParams),
+    RemoveStory(RemoveStoryParams),
+    SkipStory,
+    NextStory(NextStoryParams),
+    ResumeSession(ResumeSessionParams),
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
 pub struct CreateRoomParams {
     pub passphrase: Option<String>, // TODO: Use SecStr
-    pub card_set: Vec<String>,
+    pub card_set: Option<Vec<CardLabel>>,
+    pub deck_preset: Option<DeckPreset>,
+    pub deck_uuid: Option<Uuid>,
+    #[serde(default)]
+    pub options: RoomOptions,
 }
 
-#[derive(Debug, Deserialize)]
+/// Room is joined either by its Uuid or its join code.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct JoinRoomParams {
-    pub room_uuid: Uuid,
+    pub room_uuid: Option<Uuid>,
+    pub join_code: Option<String>,
     pub passphrase: Option<String>, // TODO: Use SecStr
+    #[serde(default)]
+    pub spectator: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct KickPlayerParams {
+    pub player_uuid: Uuid,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct BanPlayerParams {
+    pub player_uuid: Uuid,
+}
+
+/// Fields which are absent are left unchanged. Passphrase can be removed by
+/// explicitly setting it to null.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct UpdateRoomConfigParams {
+    #[serde(
+        default,
+        deserialize_with = "deserialize_present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub passphrase: Option<Option<String>>, // TODO: Use SecStr
+    pub card_set: Option<Vec<CardLabel>>,
+    pub deck_preset: Option<DeckPreset>,
+    pub options: Option<RoomOptions>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct StartGameParams {
+    pub title: String,
+    pub description: Option<String>,
+    /// Reveal the cards automatically after the given number of seconds.
+    pub countdown_secs: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct PlayCardParams {
+    pub card: CardLabel,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ResetGameParams {
+    /// Reveal the cards of the new round automatically after the given number
+    /// of seconds.
+    pub countdown_secs: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct FinalizeGameParams {
+    pub estimate: CardLabel,
+    /// Required when the estimate overrides the cards of the card set.
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct AddStoryParams {
+    pub title: String,
+    pub description: Option<String>,
+    pub link: Option<String>,
+}
+
+/// The Uuids of all stories in the backlog in their new order.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ReorderBacklogParams {
+    pub story_uuids: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct RemoveStoryParams {
+    pub story_uuid: Uuid,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct NextStoryParams {
+    /// Reveal the cards automatically after the given number of seconds.
+    pub countdown_secs: Option<u64>,
+}
+
+/// Take back the seat of the session which is disconnected within the resume
+/// grace window, using the resume token given when the session is started.
+#[derive(Debug, Serialize, Deserialize, JsonSchema)]
+pub struct ResumeSessionParams {
+    pub resume_token: String,
+    /// Sequence number of the latest room event received. The events after
+    /// it are replayed when they are still buffered by the room, otherwise
+    /// a snapshot of the room is sent instead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_seq: Option<u64>,
+}
+
+/// Distinguish an explicit null from an absent field, which serde otherwise
+/// deserializes both as None.
+fn deserialize_present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    mod request_frame {
+        use super::*;
+
+        #[test]
+        fn should_deserialize_request_with_request_id() {
+            let frame: RequestFrame =
+                serde_json::from_str(r#"{"type":"PlayCard","request_id":"request-1","card":"3"}"#)
+                    .unwrap();
+
+            assert_eq!(frame.request_id, Some(String::from("request-1")));
+            match frame.req {
+                RequestMessage::PlayCard(params) => assert_eq!(params.card, "3"),
+                req => panic!("Unexpected request {:?}", req),
+            }
+        }
+
+        #[test]
+        fn should_deserialize_request_without_params_with_request_id() {
+            let frame: RequestFrame =
+                serde_json::from_str(r#"{"type":"RevealCards","request_id":"request-1"}"#).unwrap();
+
+            assert_eq!(frame.request_id, Some(String::from("request-1")));
+            match frame.req {
+                RequestMessage::RevealCards => (),
+                req => panic!("Unexpected request {:?}", req),
+            }
+        }
+
+        #[test]
+        fn should_deserialize_request_without_request_id() {
+            let frame: RequestFrame = serde_json::from_str(r#"{"type":"SkipStory"}"#).unwrap();
+
+            assert_eq!(frame.request_id, None);
+        }
+    }
+
+    mod update_room_config_params {
+        use super::*;
+
+        #[test]
+        fn should_deserialize_absent_passphrase_as_none() {
+            let params: UpdateRoomConfigParams = serde_json::from_str("{}").unwrap();
+
+            assert_eq!(params.passphrase, None);
+        }
+
+        #[test]
+        fn should_deserialize_null_passphrase_as_some_none() {
+            let params: UpdateRoomConfigParams =
+                serde_json::from_str(r#"{"passphrase":null}"#).unwrap();
+
+            assert_eq!(params.passphrase, Some(None));
+        }
+
+        #[test]
+        fn should_deserialize_provided_passphrase() {
+            let params: UpdateRoomConfigParams =
+                serde_json::from_str(r#"{"passphrase":"Passphrase"}"#).unwrap();
+
+            assert_eq!(params.passphrase, Some(Some(String::from("Passphrase"))));
+        }
+    }
 }
@@ -1,27 +1,252 @@
 use actix::prelude::Message;
-use serde::Serialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
+use crate::common::error::Error;
 use crate::common::model::Uuid;
-use crate::poker::model::Card;
+use crate::poker::card::{Card, CardStats};
+use crate::poker::game::Story;
+use crate::poker::model::{CardLabel, RoomOptions};
 
-#[derive(Message, Serialize, Clone)]
+/// Response to websocket client. Events of a room are numbered by the
+/// sequence of the room, so that the client can tell which events it has
+/// missed and have them replayed when it resumes its session.
+#[derive(Message, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct ResponseFrame {
+    /// Sequence number of the room event. Absent for replies and messages
+    /// sent to a single client.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seq: Option<u64>,
+    #[serde(flatten)]
+    pub msg: ResponseMessage,
+}
+
+impl ResponseFrame {
+    pub fn event(seq: u64, msg: ResponseMessage) -> Self {
+        ResponseFrame {
+            seq: Some(seq),
+            msg,
+        }
+    }
+}
+
+impl From<ResponseMessage> for ResponseFrame {
+    fn from(msg: ResponseMessage) -> Self {
+        ResponseFrame { seq: None, msg }
+    }
+}
+
+#[derive(Message, Serialize, Deserialize, Clone, JsonSchema)]
 pub enum ResponseMessage {
+    Error(RequestError),
+    Acknowledged(Acknowledgement),
     RoomCreated(CreatedRoom),
     RoomClosed(String),
     UserJoined(String),
-    Unauthorized(String),
     UserLeft(String),
+    Kicked(KickedFromRoom),
     TopicUpdated(String),
-    ConfigUpdated(String),
-    GameStarted(String),
+    ConfigUpdated(RoomConfig),
+    GameStarted(StartedGame),
     CardPlayed(String),
     CardPlayFailed(String),
+    CardsRevealed(RevealedCards),
+    CountdownTicked(CountdownTick),
+    GameReset(ResetRound),
+    GameFinalized(FinalizedGame),
+    BacklogUpdated(Vec<Story>),
     GameEnded(String),
+    SessionStarted(StartedSession),
+    SessionResumed(Box<RoomSnapshot>),
+}
+
+/// Error when handling a request. Details of server errors are not exposed to
+/// the client.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct RequestError {
+    /// Stable code derived from the error kind, for clients to handle the
+    /// error with.
+    pub code: String,
+    pub message: String,
+    /// ID of the request which caused the error, when it is given by the
+    /// client.
+    pub request_id: Option<String>,
+}
+
+impl RequestError {
+    pub fn new(err: &Error, request_id: Option<String>) -> Self {
+        let kind = err.kind();
+        let message = if !kind.is_client_error() {
+            String::from("Error when handling request")
+        } else if err.message().is_empty() {
+            kind.to_string()
+        } else {
+            String::from(err.message())
+        };
+
+        RequestError {
+            code: String::from(kind.code()),
+            message,
+            request_id,
+        }
+    }
+}
+
+/// Reply to a request which is handled successfully but has no other direct
+/// reply.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct Acknowledgement {
+    pub request_id: String,
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
 pub struct CreatedRoom {
     pub uuid: Uuid,
+    /// Short code to share with players instead of the room Uuid.
+    pub join_code: String,
     pub private: bool,
-    pub card_set: Vec<Card>,
+    pub card_set: Vec<CardLabel>,
+    pub cards: Vec<Card>,
+    pub options: RoomOptions,
+    /// ID of the request which created the room, when it is given by the
+    /// client.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct RoomConfig {
+    pub private: bool,
+    pub card_set: Vec<CardLabel>,
+    pub cards: Vec<Card>,
+    pub options: RoomOptions,
+}
+
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct KickedFromRoom {
+    pub room_uuid: Uuid,
+    pub banned: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct StartedGame {
+    pub title: String,
+    pub description: Option<String>,
+    pub link: Option<String>,
+    pub countdown_secs: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct ResetRound {
+    pub attempt: u32,
+    pub countdown_secs: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct FinalizedGame {
+    pub game_uuid: Uuid,
+    pub estimate: CardLabel,
+    pub reason: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct CountdownTick {
+    pub secs_left: u64,
+}
+
+/// Cards played in the game, sorted by their ordinals in the card set.
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct RevealedCards {
+    pub attempt: u32,
+    /// Empty in anonymous mode, so that cards cannot be mapped to players.
+    pub hands: Vec<PlayedHand>,
+    pub cards: Vec<CardLabel>,
+    pub stats: CardStats,
+    /// Uuids of players in the room who did not play a card.
+    pub abstained: Vec<Uuid>,
+}
+
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct PlayedHand {
+    pub player_uuid: Uuid,
+    pub card: CardLabel,
+}
+
+/// Sent once the connection is established. The token resumes the session
+/// when the connection drops, and is replaced by the token of the resuming
+/// connection once used.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+pub struct StartedSession {
+    pub resume_token: String,
+}
+
+/// State of the room for the player who resumes the session, in place of the
+/// messages missed while disconnected which are no longer buffered.
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct RoomSnapshot {
+    pub uuid: Uuid,
+    /// Sequence number of the latest room event reflected in the snapshot.
+    pub seq: u64,
+    pub config: RoomConfig,
+    /// Uuids of players in the room, including spectators.
+    pub players: Vec<Uuid>,
+    pub spectators: Vec<Uuid>,
+    pub game: Option<GameSnapshot>,
+    pub backlog: Vec<Story>,
+}
+
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct GameSnapshot {
+    pub uuid: Uuid,
+    pub title: String,
+    pub description: Option<String>,
+    pub link: Option<String>,
+    pub attempt: u32,
+    pub countdown_secs: Option<u64>,
+    /// Uuids of players who have played in the current round.
+    pub played: Vec<Uuid>,
+    /// Card played by the player who resumes the session.
+    pub own_card: Option<CardLabel>,
+    /// Present once the cards of the current round are revealed.
+    pub revealed_cards: Option<RevealedCards>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::common::error::ErrorKind;
+
+    mod request_error {
+        use super::*;
+
+        #[test]
+        fn should_expose_message_of_client_error() {
+            let err = Error::new(ErrorKind::BannedError, "Player is banned from the room");
+
+            let request_error = RequestError::new(&err, Some(String::from("request-1")));
+
+            assert_eq!(request_error.code, "banned");
+            assert_eq!(request_error.message, "Player is banned from the room");
+            assert_eq!(request_error.request_id, Some(String::from("request-1")));
+        }
+
+        #[test]
+        fn should_fall_back_to_error_kind_when_message_is_empty() {
+            let request_error = RequestError::new(&Error::from(ErrorKind::RoomNotFound), None);
+
+            assert_eq!(request_error.code, "room_not_found");
+            assert_eq!(request_error.message, "RoomNotFound");
+        }
+
+        #[test]
+        fn should_hide_details_of_server_error() {
+            let err = Error::new(ErrorKind::QueryError, "Error when querying room from DB");
+
+            let request_error = RequestError::new(&err, None);
+
+            assert_eq!(request_error.code, "query_error");
+            assert_eq!(request_error.message, "Error when handling request");
+        }
+    }
 }
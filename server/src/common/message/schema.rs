@@ -0,0 +1,339 @@
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use schemars::schema::{RootSchema, SchemaObject};
+use serde_json::{self, Map, Value};
+
+use crate::common::message::request::RequestFrame;
+use crate::common::message::response::{CreatedRoom, ResponseFrame};
+use crate::common::message::{RequestMessage, ResponseMessage};
+use crate::user::route::LoginUserReq;
+
+/// JSON Schema of the messages exchanged with clients, with every message
+/// type in its definitions.
+pub fn protocol_schema() -> RootSchema {
+    let mut gen = SchemaGenerator::new(SchemaSettings::draft07());
+    gen.subschema_for::<RequestFrame>();
+    gen.subschema_for::<RequestMessage>();
+    gen.subschema_for::<ResponseMessage>();
+    gen.subschema_for::<ResponseFrame>();
+    gen.subschema_for::<CreatedRoom>();
+    gen.subschema_for::<LoginUserReq>();
+
+    let mut schema = SchemaObject::default();
+    schema.metadata().title = Some(String::from("ScrumPokerProtocol"));
+
+    RootSchema {
+        meta_schema: gen.settings().meta_schema.clone(),
+        schema,
+        definitions: gen.take_definitions(),
+    }
+}
+
+pub fn protocol_schema_json() -> String {
+    serde_json::to_string_pretty(&protocol_schema()).expect("Error when serializing schema") + "\n"
+}
+
+/// TypeScript definitions of the messages, converted from their JSON Schema.
+pub fn protocol_typescript() -> String {
+    let schema = serde_json::to_value(protocol_schema()).expect("Error when serializing schema");
+    let mut lines = vec![
+        String::from("// Generated from the message types of the server. Do not edit."),
+        String::from(
+            "// Run `UPDATE_GENERATED_FILES=1 cargo test generated_files` in `server/` to update.",
+        ),
+    ];
+    if let Some(definitions) = schema.get("definitions").and_then(Value::as_object) {
+        for (name, definition) in definitions.iter() {
+            lines.push(String::new());
+            lines.push(ts_definition(name, definition));
+        }
+    }
+
+    lines.join("\n") + "\n"
+}
+
+fn ts_definition(name: &str, schema: &Value) -> String {
+    let mut definition = String::new();
+    if let Some(description) = schema.get("description").and_then(Value::as_str) {
+        definition.push_str(&format!("/** {} */\n", description));
+    }
+
+    let variants = ts_variants(schema);
+    let mut rest = schema.clone();
+    if let Some(rest) = rest.as_object_mut() {
+        for key in ["oneOf", "anyOf", "description"].iter() {
+            rest.remove(*key);
+        }
+    }
+
+    if variants.is_empty() {
+        match ts_properties(&rest) {
+            Some(properties) => definition.push_str(&format!(
+                "export interface {} {{\n{}}}",
+                name,
+                properties
+                    .iter()
+                    .map(|(property, description)| match description {
+                        Some(description) => {
+                            format!("    /** {} */\n    {};\n", description, property)
+                        }
+                        None => format!("    {};\n", property),
+                    })
+                    .collect::<String>()
+            )),
+            None => definition.push_str(&format!("export type {} = {};", name, ts_type(&rest))),
+        }
+    } else {
+        let variants: String = variants
+            .iter()
+            .map(|variant| format!("\n    | {}", variant))
+            .collect();
+        if is_plain_object(&rest) {
+            definition.push_str(&format!("export type {} ={};", name, variants));
+        } else {
+            definition.push_str(&format!(
+                "export type {} = {} & ({}\n);",
+                name,
+                ts_type(&rest),
+                variants
+            ));
+        }
+    }
+
+    definition
+}
+
+fn ts_type(schema: &Value) -> String {
+    let schema = match schema {
+        Value::Object(schema) => schema,
+        Value::Bool(false) => return String::from("never"),
+        _ => return String::from("unknown"),
+    };
+
+    let mut intersection = vec![];
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        intersection.push(String::from(reference.trim_start_matches("#/definitions/")));
+    }
+    if let Some(value) = schema.get("const") {
+        intersection.push(ts_literal(value));
+    }
+    if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+        let literals: Vec<String> = values.iter().map(ts_literal).collect();
+        intersection.push(literals.join(" | "));
+    } else if let Some(types) = schema.get("type") {
+        let types: Vec<&str> = match types {
+            Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+            types => types.as_str().into_iter().collect(),
+        };
+        let types: Vec<String> = types
+            .into_iter()
+            .filter_map(|primitive| ts_primitive(primitive, schema))
+            .collect();
+        if !types.is_empty() {
+            intersection.push(types.join(" | "));
+        }
+    }
+    if let Some(schemas) = schema.get("allOf").and_then(Value::as_array) {
+        intersection.extend(schemas.iter().map(ts_type));
+    }
+    let variants = ts_variants(&Value::Object(schema.clone()));
+    if !variants.is_empty() {
+        intersection.push(variants.join(" | "));
+    }
+
+    match intersection.len() {
+        0 => String::from("unknown"),
+        1 => intersection.remove(0),
+        _ => {
+            let intersection: Vec<String> =
+                intersection.iter().map(|ts| parenthesize(ts)).collect();
+            intersection.join(" & ")
+        }
+    }
+}
+
+fn ts_primitive(primitive: &str, schema: &Map<String, Value>) -> Option<String> {
+    let ts = match primitive {
+        "string" => String::from("string"),
+        "number" | "integer" => String::from("number"),
+        "boolean" => String::from("boolean"),
+        "null" => String::from("null"),
+        "array" => format!(
+            "{}[]",
+            parenthesize(&ts_type(schema.get("items").unwrap_or(&Value::Bool(true))))
+        ),
+        "object" => {
+            let schema = Value::Object(schema.clone());
+            match ts_properties(&schema) {
+                Some(properties) => {
+                    let properties: Vec<String> = properties
+                        .into_iter()
+                        .map(|(property, _)| property)
+                        .collect();
+                    format!("{{ {} }}", properties.join("; "))
+                }
+                // Properties of the variants are described by the variants
+                None if !ts_variants(&schema).is_empty() => return None,
+                None => String::from("{ [key: string]: unknown }"),
+            }
+        }
+        _ => String::from("unknown"),
+    };
+
+    Some(ts)
+}
+
+/// Properties of the object with their descriptions, or None when the schema
+/// is not an object with properties.
+fn ts_properties(schema: &Value) -> Option<Vec<(String, Option<String>)>> {
+    if schema.get("type").and_then(Value::as_str) != Some("object") {
+        return None;
+    }
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|required| required.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+
+    let mut properties: Vec<(String, Option<String>)> = schema
+        .get("properties")
+        .and_then(Value::as_object)
+        .map(|properties| {
+            properties
+                .iter()
+                .map(|(key, property)| {
+                    let optional = if required.contains(&key.as_str()) {
+                        ""
+                    } else {
+                        "?"
+                    };
+                    let description = property
+                        .get("description")
+                        .and_then(Value::as_str)
+                        .map(String::from);
+                    (
+                        format!("{}{}: {}", ts_key(key), optional, ts_type(property)),
+                        description,
+                    )
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if let Some(additional) = schema.get("additionalProperties") {
+        if additional.is_object() {
+            properties.push((format!("[key: string]: {}", ts_type(additional)), None));
+        }
+    }
+
+    if properties.is_empty() {
+        None
+    } else {
+        Some(properties)
+    }
+}
+
+fn ts_variants(schema: &Value) -> Vec<String> {
+    ["oneOf", "anyOf"]
+        .iter()
+        .filter_map(|key| schema.get(*key).and_then(Value::as_array))
+        .flat_map(|schemas| schemas.iter().map(ts_type))
+        .collect()
+}
+
+fn ts_literal(value: &Value) -> String {
+    serde_json::to_string(value).expect("Error when serializing literal")
+}
+
+fn ts_key(key: &str) -> String {
+    let is_identifier = key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !key.starts_with(|c: char| c.is_ascii_digit());
+    if is_identifier {
+        String::from(key)
+    } else {
+        ts_literal(&Value::String(String::from(key)))
+    }
+}
+
+fn parenthesize(ts: &str) -> String {
+    if ts.contains(" | ") || ts.contains(" & ") {
+        format!("({})", ts)
+    } else {
+        String::from(ts)
+    }
+}
+
+fn is_plain_object(schema: &Value) -> bool {
+    match schema.as_object() {
+        Some(schema) => schema
+            .iter()
+            .all(|(key, value)| key == "type" && value == "object"),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::env;
+    use std::fs;
+    use std::path::Path;
+
+    mod protocol_typescript {
+        use super::*;
+
+        #[test]
+        fn should_render_struct_as_interface_with_optional_fields() {
+            let typescript = protocol_typescript();
+
+            assert!(typescript.contains(
+                "export interface FinalizedGame {\n    estimate: string;\n    game_uuid: string;\n    reason?: string | null;\n}"
+            ));
+        }
+
+        #[test]
+        fn should_render_tagged_enums_as_union_of_objects() {
+            let typescript = protocol_typescript();
+
+            assert!(typescript.contains("\n    | { card: string; type: \"PlayCard\" }\n"));
+            assert!(typescript.contains("\n    | { RoomCreated: CreatedRoom }\n"));
+        }
+
+        #[test]
+        fn should_render_request_id_alongside_request() {
+            let typescript = protocol_typescript();
+
+            assert!(typescript
+                .contains("export type RequestFrame = { request_id?: string | null } & (\n"));
+        }
+    }
+
+    /// Set `UPDATE_GENERATED_FILES` to write the generated files instead of
+    /// checking them.
+    mod generated_files {
+        use super::*;
+
+        #[test]
+        fn should_be_up_to_date() {
+            check_generated_file("schema/protocol.schema.json", &protocol_schema_json());
+            check_generated_file(
+                "../web-app/apps/scrum-poker/src/app/server/protocol.ts",
+                &protocol_typescript(),
+            );
+        }
+
+        fn check_generated_file(path: &str, content: &str) {
+            let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(path);
+            if env::var("UPDATE_GENERATED_FILES").is_ok() {
+                fs::write(&path, content).unwrap();
+                return;
+            }
+
+            let generated = fs::read_to_string(&path).unwrap_or_default();
+            assert!(
+                generated == content,
+                "{} is out of date. Run `UPDATE_GENERATED_FILES=1 cargo test generated_files` to update it",
+                path.display()
+            );
+        }
+    }
+}
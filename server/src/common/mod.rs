@@ -1,3 +1,5 @@
+pub mod clock;
 pub mod error;
+pub mod http;
 pub mod message;
 pub mod model;
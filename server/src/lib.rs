@@ -3,6 +3,8 @@
 #[macro_use]
 extern crate diesel;
 
+use std::time::Duration;
+
 use diesel::r2d2::ConnectionManager;
 use diesel::PgConnection;
 
@@ -10,25 +12,33 @@ use client::channel::DefaultClientChannel;
 use client::store::DefaultClientStore;
 use common::error::ContextExt;
 use common::error::{ErrorKind, Result};
-use poker::model::RoomModel;
+use poker::model::{DeckModel, RoomModel};
 use poker::room::Room;
-use server::Server;
+use server::{Server, DEFAULT_RESUME_GRACE};
 use user::auth::provider::oauth::OAuthProviderImplConfig;
 use user::auth::provider::{GoogleProviderImpl, GuestProviderImpl, Provider};
-use user::auth::ProviderService;
+use user::auth::token::DEFAULT_TOKEN_TTL;
+use user::auth::{AuthTokenService, ProviderService};
 use user::model::UserModel;
+use websocket::heartbeat::{HeartbeatConfig, DEFAULT_CLIENT_TIMEOUT, DEFAULT_HEARTBEAT_INTERVAL};
 use websocket::Session;
 
 pub mod client;
 pub mod common;
 pub mod poker;
 pub mod schema;
+pub mod sdk;
 pub mod server;
 pub mod user;
 pub mod websocket;
 
-pub type AppServer =
-    Server<UserModel, RoomModel, DefaultClientStore<DefaultClientChannel>, DefaultClientChannel>;
+pub type AppServer = Server<
+    UserModel,
+    RoomModel,
+    DeckModel,
+    DefaultClientStore<DefaultClientChannel>,
+    DefaultClientChannel,
+>;
 pub type AppRoom = Room<RoomModel, DefaultClientStore<DefaultClientChannel>, DefaultClientChannel>;
 pub type AppSession = Session<UserModel>;
 
@@ -54,6 +64,38 @@ pub fn make_auth_provider_service() -> ProviderService {
     ProviderService::new(vec![Box::new(guest_provider), Box::new(google_provider)])
 }
 
+pub fn make_auth_token_service() -> AuthTokenService {
+    let secret = dotenv::var("AUTH_TOKEN_SECRET")
+        .unwrap_or_else(|_| panic!("Missing AUTH_TOKEN_SECRET in env"));
+
+    AuthTokenService::new(secret, DEFAULT_TOKEN_TTL)
+}
+
+pub fn make_heartbeat_config() -> Result<HeartbeatConfig> {
+    let interval = duration_secs_var("HEARTBEAT_INTERVAL_SECS", DEFAULT_HEARTBEAT_INTERVAL)?;
+    let client_timeout = duration_secs_var("CLIENT_TIMEOUT_SECS", DEFAULT_CLIENT_TIMEOUT)?;
+
+    HeartbeatConfig::new(interval, client_timeout)
+}
+
+/// Time for the disconnected client to resume its session before it leaves
+/// its room.
+pub fn make_resume_grace() -> Result<Duration> {
+    duration_secs_var("RESUME_GRACE_SECS", DEFAULT_RESUME_GRACE)
+}
+
+fn duration_secs_var(key: &str, default: Duration) -> Result<Duration> {
+    match dotenv::var(key) {
+        Ok(secs) => secs.parse().map(Duration::from_secs).context(|| {
+            (
+                ErrorKind::InvalidParams,
+                format!("{} must be a number of seconds", key),
+            )
+        }),
+        Err(_) => Ok(default),
+    }
+}
+
 pub fn make_google_auth_provider() -> Result<GoogleProviderImpl> {
     let client_id = dotenv::var("GOOGLE_OAUTH_CLIENT_ID")
         .unwrap_or_else(|_| panic!("Missing GOOGLE_OAUTH_CLIENT_ID in env"));
@@ -4,14 +4,21 @@ use actix_web::{web, App, HttpServer};
 use scrum_poker::client::channel::DefaultClientChannel;
 use scrum_poker::client::store::DefaultClientStore;
 use scrum_poker::common::error::{ContextExt, ErrorKind, Result as CommonResult};
-use scrum_poker::poker::model::RoomModel;
+use scrum_poker::poker::model::{DeckModel, RoomModel};
+use scrum_poker::poker::route::{
+    close_room, create_deck, create_room, delete_deck, export_results, get_game, get_room,
+    import_stories, list_deck_presets, list_decks, list_rooms, update_deck, update_room,
+};
 use scrum_poker::server::Server;
 use scrum_poker::user::model::UserModel;
 use scrum_poker::user::route::login_user;
 // TODO: Deteled after test
 use scrum_poker::user::route::exchange as UserExchangeRoute;
 use scrum_poker::websocket::route::websocket_route;
-use scrum_poker::{make_auth_provider_service, make_database_connection_pool};
+use scrum_poker::{
+    make_auth_provider_service, make_auth_token_service, make_database_connection_pool,
+    make_heartbeat_config, make_resume_grace,
+};
 
 fn main() -> CommonResult<()> {
     env_logger::init();
@@ -26,26 +33,72 @@ fn main() -> CommonResult<()> {
     let server_host = dotenv::var("SERVER_HOST").unwrap_or_else(|_| String::from("0.0.0.0"));
     let server_port = dotenv::var("SERVER_PORT").unwrap_or_else(|_| String::from("80"));
 
+    let heartbeat_config = make_heartbeat_config()?;
+    let resume_grace = make_resume_grace()?;
     let auth_provider_service = make_auth_provider_service();
     let auth_provider_service_data = web::Data::new(auth_provider_service);
+    let auth_token_service_data = web::Data::new(make_auth_token_service());
     let pool = make_database_connection_pool(&database_url)?;
     let user_model = UserModel::new(pool.clone());
     let room_model = RoomModel::new(pool.clone());
+    let deck_model = DeckModel::new(pool.clone());
     let client_store = DefaultClientStore::<DefaultClientChannel>::default();
 
     let sys = System::new("ScrumPoker");
 
-    let server = Server::new(user_model.clone(), room_model.clone(), client_store).start();
+    let server = Server::new(
+        user_model.clone(),
+        room_model.clone(),
+        deck_model.clone(),
+        client_store,
+        resume_grace,
+    )
+    .start();
 
     HttpServer::new(move || {
         App::new()
             .data(server.clone())
             .data(user_model.clone())
             .data(room_model.clone())
+            .data(deck_model.clone())
+            .data(heartbeat_config)
             .register_data(auth_provider_service_data.clone())
+            .register_data(auth_token_service_data.clone())
             .service(web::resource("/ws/").to(websocket_route))
             .service(web::resource("/user/login").to_async(login_user::<UserModel>))
             .service(web::resource("/user/exchange").to_async(UserExchangeRoute))
+            .service(web::resource("/deck/presets").route(web::get().to(list_deck_presets)))
+            .service(
+                web::resource("/deck")
+                    .route(web::get().to_async(list_decks::<DeckModel>))
+                    .route(web::post().to_async(create_deck::<DeckModel>)),
+            )
+            .service(
+                web::resource("/deck/{uuid}")
+                    .route(web::put().to_async(update_deck::<DeckModel>))
+                    .route(web::delete().to_async(delete_deck::<DeckModel>)),
+            )
+            .service(
+                web::resource("/export").route(web::get().to_async(export_results::<RoomModel>)),
+            )
+            .service(
+                web::resource("/room")
+                    .route(web::get().to_async(list_rooms::<RoomModel>))
+                    .route(web::post().to_async(create_room)),
+            )
+            .service(
+                web::resource("/room/{uuid}")
+                    .route(web::get().to_async(get_room::<RoomModel>))
+                    .route(web::put().to_async(update_room))
+                    .route(web::delete().to_async(close_room)),
+            )
+            .service(
+                web::resource("/game/{uuid}").route(web::get().to_async(get_game::<RoomModel>)),
+            )
+            .service(
+                web::resource("/room/{uuid}/backlog/import")
+                    .route(web::post().to_async(import_stories)),
+            )
     })
     .bind(format!("{}:{}", server_host, server_port))
     .context(|| {
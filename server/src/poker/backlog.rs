@@ -0,0 +1,291 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use url::Url;
+
+use crate::common::error::{ContextExt, Error, ErrorKind, Result as CommonResult};
+use crate::poker::game::Story;
+use crate::poker::model::CardLabel;
+
+pub const MAX_STORY_TITLE_LEN: usize = 256;
+
+/// Story to be added to the backlog, with its fields trimmed and validated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoryDraft {
+    pub title: String,
+    pub description: Option<String>,
+    pub link: Option<String>,
+    pub external_id: Option<String>,
+    pub pre_estimate: Option<CardLabel>,
+}
+
+impl StoryDraft {
+    pub fn new(
+        title: String,
+        description: Option<String>,
+        link: Option<String>,
+    ) -> CommonResult<Self> {
+        let title = String::from(title.trim());
+        if title.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidParams,
+                "Story title cannot be empty",
+            ));
+        }
+        if title.chars().count() > MAX_STORY_TITLE_LEN {
+            return Err(Error::new(
+                ErrorKind::InvalidParams,
+                format!(
+                    "Story title cannot be longer than {} characters",
+                    MAX_STORY_TITLE_LEN
+                ),
+            ));
+        }
+        let link = non_blank(link);
+        if let Some(link) = link.as_ref() {
+            validate_link(link)?;
+        }
+
+        Ok(StoryDraft {
+            title,
+            description: non_blank(description),
+            link,
+            external_id: None,
+            pre_estimate: None,
+        })
+    }
+}
+
+fn validate_link(link: &str) -> CommonResult<()> {
+    match Url::parse(link) {
+        Ok(ref url) if url.scheme() == "http" || url.scheme() == "https" => Ok(()),
+        _ => Err(Error::new(
+            ErrorKind::InvalidParams,
+            "Story link must be a HTTP or HTTPS URL",
+        )),
+    }
+}
+
+/// Trim the value and treat blank value as absent.
+pub fn non_blank(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| String::from(value.trim()))
+        .filter(|value| !value.is_empty())
+}
+
+/// Row of an imported backlog file. CSV files have a header row with the
+/// same field names.
+#[derive(Debug, Clone, Deserialize)]
+pub struct StoryImportRow {
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub link: Option<String>,
+    /// Estimate suggested before the game is played.
+    #[serde(default)]
+    pub estimate: Option<CardLabel>,
+    /// Id of the story in external issue tracker. Rows with an id which is
+    /// already imported to the room are skipped.
+    #[serde(default)]
+    pub external_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportFormat {
+    Csv,
+    Json,
+}
+
+impl ImportFormat {
+    pub fn from_content_type(content_type: &str) -> CommonResult<Self> {
+        let mime_type = content_type.split(';').next().unwrap_or("").trim();
+        match mime_type.to_lowercase().as_str() {
+            "text/csv" => Ok(ImportFormat::Csv),
+            "application/json" => Ok(ImportFormat::Json),
+            _ => Err(Error::new(
+                ErrorKind::InvalidParams,
+                "Backlog can only be imported from CSV or JSON",
+            )),
+        }
+    }
+}
+
+/// Parse the rows of an imported backlog file. A file which cannot be read
+/// at all is an error, while rows which cannot be parsed are returned as
+/// errors so that they can be reported individually.
+pub fn parse_import(
+    format: ImportFormat,
+    body: &[u8],
+) -> CommonResult<Vec<CommonResult<StoryImportRow>>> {
+    match format {
+        ImportFormat::Csv => {
+            let mut reader = csv::ReaderBuilder::new()
+                .trim(csv::Trim::All)
+                .from_reader(body);
+            reader.headers().context(|| {
+                (
+                    ErrorKind::InvalidParams,
+                    "Error when reading header row of CSV",
+                )
+            })?;
+
+            Ok(reader
+                .deserialize()
+                .map(|row| row.map_err(|err| Error::new(ErrorKind::InvalidParams, err.to_string())))
+                .collect())
+        }
+        ImportFormat::Json => {
+            let rows: Vec<Value> = serde_json::from_slice(body).context(|| {
+                (
+                    ErrorKind::InvalidParams,
+                    "Backlog JSON must be an array of stories",
+                )
+            })?;
+
+            Ok(rows
+                .into_iter()
+                .map(|row| {
+                    serde_json::from_value(row)
+                        .map_err(|err| Error::new(ErrorKind::InvalidParams, err.to_string()))
+                })
+                .collect())
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct StoryImportReport {
+    /// Stories added to the end of the backlog.
+    pub imported: Vec<Story>,
+    /// External ids of the rows skipped because they are already imported.
+    pub skipped: Vec<String>,
+    /// Nothing is imported when any row has error.
+    pub errors: Vec<StoryImportError>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct StoryImportError {
+    /// Position of the row in the file, starting from 1 and excluding the
+    /// header row.
+    pub row: usize,
+    pub message: String,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    mod story_draft {
+        use super::*;
+
+        #[test]
+        fn should_trim_fields_and_drop_blank_ones() {
+            let draft = StoryDraft::new(
+                String::from(" Login "),
+                Some(String::from("  ")),
+                Some(String::from(" https://example.com/issues/1 ")),
+            )
+            .unwrap();
+
+            assert_eq!(draft.title, "Login");
+            assert_eq!(draft.description, None);
+            assert_eq!(
+                draft.link,
+                Some(String::from("https://example.com/issues/1"))
+            );
+        }
+
+        #[test]
+        fn should_return_invalid_params_error_when_title_is_blank() {
+            let draft_result = StoryDraft::new(String::from(" "), None, None);
+
+            assert_eq!(draft_result.unwrap_err().kind(), ErrorKind::InvalidParams);
+        }
+
+        #[test]
+        fn should_return_invalid_params_error_when_link_is_not_http_url() {
+            let draft_result = StoryDraft::new(
+                String::from("Login"),
+                None,
+                Some(String::from("javascript:alert(1)")),
+            );
+
+            assert_eq!(draft_result.unwrap_err().kind(), ErrorKind::InvalidParams);
+        }
+    }
+
+    mod import_format {
+        use super::*;
+
+        #[test]
+        fn should_detect_format_from_content_type() {
+            assert_eq!(
+                ImportFormat::from_content_type("text/csv; charset=utf-8").unwrap(),
+                ImportFormat::Csv
+            );
+            assert_eq!(
+                ImportFormat::from_content_type("application/json").unwrap(),
+                ImportFormat::Json
+            );
+        }
+
+        #[test]
+        fn should_return_invalid_params_error_when_content_type_is_unsupported() {
+            let format_result = ImportFormat::from_content_type("text/plain");
+
+            assert_eq!(format_result.unwrap_err().kind(), ErrorKind::InvalidParams);
+        }
+    }
+
+    mod parse_import {
+        use super::*;
+
+        #[test]
+        fn should_parse_csv_rows_with_optional_columns() {
+            let body = "title,link,external_id\nLogin, https://example.com/1 ,JIRA-1\nLogout,,\n";
+
+            let rows = parse_import(ImportFormat::Csv, body.as_bytes()).unwrap();
+
+            assert_eq!(rows.len(), 2);
+            let login = rows[0].as_ref().unwrap();
+            assert_eq!(login.title, "Login");
+            assert_eq!(login.link, Some(String::from("https://example.com/1")));
+            assert_eq!(login.external_id, Some(String::from("JIRA-1")));
+            assert_eq!(login.estimate, None);
+            assert_eq!(rows[1].as_ref().unwrap().title, "Logout");
+        }
+
+        #[test]
+        fn should_report_csv_row_which_cannot_be_parsed() {
+            let body = "title,description\nLogin,Form\nLogout,Button,Extra\n";
+
+            let rows = parse_import(ImportFormat::Csv, body.as_bytes()).unwrap();
+
+            assert!(rows[0].is_ok());
+            assert_eq!(
+                rows[1].as_ref().unwrap_err().kind(),
+                ErrorKind::InvalidParams
+            );
+        }
+
+        #[test]
+        fn should_parse_json_rows() {
+            let body = r#"[{"title": "Login", "estimate": "3"}, {"description": "No title"}]"#;
+
+            let rows = parse_import(ImportFormat::Json, body.as_bytes()).unwrap();
+
+            assert_eq!(rows[0].as_ref().unwrap().estimate, Some(String::from("3")));
+            assert_eq!(
+                rows[1].as_ref().unwrap_err().kind(),
+                ErrorKind::InvalidParams
+            );
+        }
+
+        #[test]
+        fn should_return_invalid_params_error_when_json_is_not_array() {
+            let parse_result = parse_import(ImportFormat::Json, br#"{"title": "Login"}"#);
+
+            assert_eq!(parse_result.unwrap_err().kind(), ErrorKind::InvalidParams);
+        }
+    }
+}
@@ -0,0 +1,344 @@
+use std::cmp::Ordering;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::poker::model::CardLabel;
+
+pub const UNKNOWN_CARD: &str = "?";
+pub const BREAK_CARD: &str = "☕";
+pub const INFINITY_CARD: &str = "∞";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum CardKind {
+    /// Regular estimate card. Its value is absent for non-numeric decks such
+    /// as T-shirt sizes, which are compared by ordinal only.
+    Number,
+    Unknown,
+    Break,
+    Infinity,
+}
+
+/// Card of a room's card set with the metadata inferred from its label and
+/// its position in the card set.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Card {
+    pub label: CardLabel,
+    pub value: Option<f64>,
+    pub ordinal: u32,
+    pub kind: CardKind,
+}
+
+impl Card {
+    pub fn new(label: CardLabel, ordinal: u32) -> Self {
+        let kind = infer_kind(&label);
+        let value = match kind {
+            CardKind::Number => parse_value(&label),
+            _ => None,
+        };
+
+        Card {
+            label,
+            value,
+            ordinal,
+            kind,
+        }
+    }
+
+    /// Whether the card counts as an estimate. Unknown and break cards are
+    /// not estimates.
+    pub fn is_estimate(&self) -> bool {
+        match self.kind {
+            CardKind::Number | CardKind::Infinity => true,
+            CardKind::Unknown | CardKind::Break => false,
+        }
+    }
+}
+
+fn infer_kind(label: &str) -> CardKind {
+    match label.trim().to_lowercase().as_str() {
+        UNKNOWN_CARD => CardKind::Unknown,
+        BREAK_CARD | "coffee" | "break" => CardKind::Break,
+        INFINITY_CARD | "inf" | "infinity" => CardKind::Infinity,
+        _ => CardKind::Number,
+    }
+}
+
+fn parse_value(label: &str) -> Option<f64> {
+    match label.trim() {
+        "½" => Some(0.5),
+        label => label.parse().ok().filter(|value: &f64| value.is_finite()),
+    }
+}
+
+/// Build the structured cards of a card set. Ordinals follow the order of
+/// the card set.
+pub fn cards_from_labels(labels: &[CardLabel]) -> Vec<Card> {
+    labels
+        .iter()
+        .enumerate()
+        .map(|(ordinal, label)| Card::new(label.clone(), ordinal as u32))
+        .collect()
+}
+
+pub fn card_labels(cards: &[Card]) -> Vec<CardLabel> {
+    cards.iter().map(|card| card.label.clone()).collect()
+}
+
+/// Sort cards by their ordinals in the card set.
+pub fn sort_cards(cards: &mut [&Card]) {
+    cards.sort_by_key(|card| card.ordinal);
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CardCount {
+    pub label: CardLabel,
+    pub count: usize,
+}
+
+/// Statistics of the cards played in a game.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CardStats {
+    /// Average of numeric cards. Absent when no numeric card is played.
+    pub average: Option<f64>,
+    /// Median of numeric cards. Absent when no numeric card is played.
+    pub median: Option<f64>,
+    /// Lowest and highest estimate cards by ordinal, so they are available
+    /// for non-numeric decks too.
+    pub min: Option<CardLabel>,
+    pub max: Option<CardLabel>,
+    /// All players, except those taking a break, played the same estimate.
+    pub consensus: bool,
+    /// Number of times each card is played, sorted by ordinal.
+    pub distribution: Vec<CardCount>,
+}
+
+impl CardStats {
+    pub fn from_cards(cards: &[&Card]) -> Self {
+        let mut cards = cards.to_vec();
+        sort_cards(&mut cards);
+
+        let mut values: Vec<f64> = cards.iter().filter_map(|card| card.value).collect();
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+        let estimates: Vec<&Card> = cards
+            .iter()
+            .filter(|card| card.is_estimate())
+            .cloned()
+            .collect();
+        let voted_cards: Vec<&Card> = cards
+            .iter()
+            .filter(|card| card.kind != CardKind::Break)
+            .cloned()
+            .collect();
+        let consensus = !voted_cards.is_empty()
+            && voted_cards.iter().all(|card| card.is_estimate())
+            && voted_cards
+                .iter()
+                .all(|card| card.label == voted_cards[0].label);
+
+        let mut distribution: Vec<CardCount> = vec![];
+        for card in cards.iter() {
+            match distribution.last_mut() {
+                Some(card_count) if card_count.label == card.label => card_count.count += 1,
+                _ => distribution.push(CardCount {
+                    label: card.label.clone(),
+                    count: 1,
+                }),
+            }
+        }
+
+        CardStats {
+            average: average(&values),
+            median: median(&values),
+            min: estimates.first().map(|card| card.label.clone()),
+            max: estimates.last().map(|card| card.label.clone()),
+            consensus,
+            distribution,
+        }
+    }
+
+    /// Statistics of the cards played with the given labels. Cards which are
+    /// no longer in the card set are sorted after the others.
+    pub fn from_labels(card_set: &[CardLabel], labels: &[CardLabel]) -> Self {
+        let card_set = cards_from_labels(card_set);
+        let cards: Vec<Card> = labels
+            .iter()
+            .map(|label| {
+                card_set
+                    .iter()
+                    .find(|card| &card.label == label)
+                    .cloned()
+                    .unwrap_or_else(|| Card::new(label.clone(), card_set.len() as u32))
+            })
+            .collect();
+        let cards: Vec<&Card> = cards.iter().collect();
+
+        CardStats::from_cards(&cards)
+    }
+}
+
+fn average(sorted_values: &[f64]) -> Option<f64> {
+    if sorted_values.is_empty() {
+        return None;
+    }
+
+    Some(sorted_values.iter().sum::<f64>() / sorted_values.len() as f64)
+}
+
+fn median(sorted_values: &[f64]) -> Option<f64> {
+    let len = sorted_values.len();
+    if len == 0 {
+        return None;
+    }
+
+    let middle = len / 2;
+    match len % 2 {
+        0 => Some((sorted_values[middle - 1] + sorted_values[middle]) / 2.0),
+        _ => Some(sorted_values[middle]),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    mod card {
+        use super::*;
+
+        #[test]
+        fn should_infer_numeric_value_of_number_card() {
+            let card = Card::new(String::from("0.5"), 1);
+
+            assert_eq!(card.kind, CardKind::Number);
+            assert_eq!(card.value, Some(0.5));
+            assert_eq!(card.ordinal, 1);
+        }
+
+        #[test]
+        fn should_infer_no_value_for_non_numeric_card() {
+            let card = Card::new(String::from("XL"), 4);
+
+            assert_eq!(card.kind, CardKind::Number);
+            assert_eq!(card.value, None);
+        }
+
+        #[test]
+        fn should_infer_special_card_kinds() {
+            assert_eq!(Card::new(String::from("?"), 0).kind, CardKind::Unknown);
+            assert_eq!(Card::new(String::from("☕"), 0).kind, CardKind::Break);
+            assert_eq!(Card::new(String::from("Coffee"), 0).kind, CardKind::Break);
+            assert_eq!(Card::new(String::from("∞"), 0).kind, CardKind::Infinity);
+        }
+
+        #[test]
+        fn special_cards_should_carry_no_value() {
+            assert_eq!(Card::new(String::from("∞"), 0).value, None);
+            assert_eq!(Card::new(String::from("?"), 0).value, None);
+        }
+    }
+
+    mod cards_from_labels {
+        use super::*;
+
+        #[test]
+        fn should_assign_ordinals_by_position() {
+            let cards = cards_from_labels(&labels(&["S", "M", "L"]));
+
+            let ordinals: Vec<u32> = cards.iter().map(|card| card.ordinal).collect();
+            assert_eq!(ordinals, vec![0, 1, 2]);
+        }
+    }
+
+    mod card_stats {
+        use super::*;
+
+        #[test]
+        fn should_compute_average_and_median_of_numeric_cards() {
+            let cards = cards_from_labels(&labels(&["1", "2", "3", "5", "8", "?"]));
+
+            let stats = CardStats::from_cards(&[&cards[0], &cards[3], &cards[4], &cards[5]]);
+
+            assert_eq!(stats.average, Some(14.0 / 3.0));
+            assert_eq!(stats.median, Some(5.0));
+        }
+
+        #[test]
+        fn should_compute_median_of_even_number_of_cards() {
+            let cards = cards_from_labels(&labels(&["1", "2", "3", "5"]));
+
+            let stats = CardStats::from_cards(&[&cards[1], &cards[3]]);
+
+            assert_eq!(stats.median, Some(3.5));
+        }
+
+        #[test]
+        fn should_find_min_and_max_by_ordinal_for_non_numeric_deck() {
+            let cards = cards_from_labels(&labels(&["XS", "S", "M", "L", "XL", "?"]));
+
+            let stats = CardStats::from_cards(&[&cards[4], &cards[1], &cards[5], &cards[2]]);
+
+            assert_eq!(stats.average, None);
+            assert_eq!(stats.min, Some(String::from("S")));
+            assert_eq!(stats.max, Some(String::from("XL")));
+        }
+
+        #[test]
+        fn should_report_consensus_when_all_played_same_estimate() {
+            let cards = cards_from_labels(&labels(&["M", "L", "☕"]));
+
+            let stats = CardStats::from_cards(&[&cards[1], &cards[1], &cards[2]]);
+
+            assert!(stats.consensus);
+        }
+
+        #[test]
+        fn should_not_report_consensus_when_someone_is_unsure() {
+            let cards = cards_from_labels(&labels(&["M", "L", "?"]));
+
+            let stats = CardStats::from_cards(&[&cards[1], &cards[1], &cards[2]]);
+
+            assert!(!stats.consensus);
+        }
+
+        #[test]
+        fn should_not_report_consensus_when_no_card_is_played() {
+            let stats = CardStats::from_cards(&[]);
+
+            assert!(!stats.consensus);
+        }
+
+        #[test]
+        fn from_labels_should_sort_cards_not_in_card_set_last() {
+            let stats = CardStats::from_labels(&labels(&["1", "2"]), &labels(&["40", "2", "1"]));
+
+            assert_eq!(stats.min, Some(String::from("1")));
+            assert_eq!(stats.max, Some(String::from("40")));
+        }
+
+        #[test]
+        fn should_count_cards_in_ordinal_order() {
+            let cards = cards_from_labels(&labels(&["1", "2", "3"]));
+
+            let stats = CardStats::from_cards(&[&cards[2], &cards[0], &cards[2]]);
+
+            assert_eq!(
+                stats.distribution,
+                vec![
+                    CardCount {
+                        label: String::from("1"),
+                        count: 1,
+                    },
+                    CardCount {
+                        label: String::from("3"),
+                        count: 2,
+                    },
+                ]
+            );
+        }
+    }
+
+    fn labels(labels: &[&str]) -> Vec<CardLabel> {
+        labels.iter().map(|label| String::from(*label)).collect()
+    }
+}
@@ -0,0 +1,332 @@
+use std::collections::HashSet;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::common::error::{Error, ErrorKind, ErrorKindExt, Result as CommonResult};
+use crate::poker::card::{cards_from_labels, Card, BREAK_CARD, UNKNOWN_CARD};
+use crate::poker::model::{CardLabel, DeckORM, DeckRecord};
+
+pub const MAX_CARD_SET_LEN: usize = 32;
+pub const MAX_CARD_LABEL_LEN: usize = 16;
+pub const MAX_DECK_NAME_LEN: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum DeckPreset {
+    Fibonacci,
+    ModifiedFibonacci,
+    PowersOfTwo,
+    TShirt,
+    Hours,
+}
+
+impl DeckPreset {
+    pub fn all() -> Vec<DeckPreset> {
+        vec![
+            DeckPreset::Fibonacci,
+            DeckPreset::ModifiedFibonacci,
+            DeckPreset::PowersOfTwo,
+            DeckPreset::TShirt,
+            DeckPreset::Hours,
+        ]
+    }
+
+    pub fn card_set(self) -> Vec<CardLabel> {
+        let labels: &[&str] = match self {
+            DeckPreset::Fibonacci => &["0", "1", "2", "3", "5", "8", "13", "21", "34", "55", "89"],
+            DeckPreset::ModifiedFibonacci => {
+                &["0", "0.5", "1", "2", "3", "5", "8", "13", "20", "40", "100"]
+            }
+            DeckPreset::PowersOfTwo => &["0", "1", "2", "4", "8", "16", "32", "64"],
+            DeckPreset::TShirt => &["XS", "S", "M", "L", "XL", "XXL"],
+            DeckPreset::Hours => &[
+                "0", "0.5", "1", "2", "3", "4", "6", "8", "12", "16", "24", "32", "40",
+            ],
+        };
+
+        labels
+            .iter()
+            .chain([UNKNOWN_CARD, BREAK_CARD].iter())
+            .map(|label| String::from(*label))
+            .collect()
+    }
+
+    pub fn cards(self) -> Vec<Card> {
+        cards_from_labels(&self.card_set())
+    }
+}
+
+/// Resolve the card set of a room from either a custom card set or a preset.
+/// Exactly one of them has to be provided.
+pub fn resolve_card_set(
+    card_set: Option<Vec<CardLabel>>,
+    preset: Option<DeckPreset>,
+) -> CommonResult<Vec<CardLabel>> {
+    match (card_set, preset) {
+        (Some(_), Some(_)) => Err(Error::new(
+            ErrorKind::InvalidParams,
+            "Only one of card_set and deck_preset can be provided",
+        )),
+        (Some(card_set), None) => validate_card_set(&card_set),
+        (None, Some(preset)) => Ok(preset.card_set()),
+        (None, None) => Err(Error::new(
+            ErrorKind::InvalidParams,
+            "Either card_set or deck_preset is required",
+        )),
+    }
+}
+
+/// Check the card set is non-empty, has no duplicated cards and both the card
+/// set and card labels are within size limits. Return the card set with
+/// trimmed card labels, which are the labels checked and to be stored.
+pub fn validate_card_set(card_set: &[CardLabel]) -> CommonResult<Vec<CardLabel>> {
+    if card_set.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidCardSet,
+            "Card set cannot be empty",
+        ));
+    }
+    if card_set.len() > MAX_CARD_SET_LEN {
+        return Err(Error::new(
+            ErrorKind::InvalidCardSet,
+            format!("Card set cannot have more than {} cards", MAX_CARD_SET_LEN),
+        ));
+    }
+
+    let mut seen_labels = HashSet::new();
+    let mut labels = Vec::with_capacity(card_set.len());
+    for card in card_set.iter() {
+        let label = card.trim();
+        if label.is_empty() {
+            return Err(Error::new(
+                ErrorKind::InvalidCardSet,
+                "Card label cannot be empty",
+            ));
+        }
+        if label.chars().count() > MAX_CARD_LABEL_LEN {
+            return Err(Error::new(
+                ErrorKind::InvalidCardSet,
+                format!(
+                    "Card label cannot be longer than {} characters",
+                    MAX_CARD_LABEL_LEN
+                ),
+            ));
+        }
+        if !seen_labels.insert(label) {
+            return Err(Error::new(
+                ErrorKind::InvalidCardSet,
+                format!("Duplicated card {} in card set", label),
+            ));
+        }
+        labels.push(String::from(label));
+    }
+
+    Ok(labels)
+}
+
+/// Check the saved deck has a non-blank name within size limit and a valid
+/// card set. Return the card set with trimmed card labels.
+pub fn validate_deck(name: &str, card_set: &[CardLabel]) -> CommonResult<Vec<CardLabel>> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidParams,
+            "Deck name cannot be empty",
+        ));
+    }
+    if name.chars().count() > MAX_DECK_NAME_LEN {
+        return Err(Error::new(
+            ErrorKind::InvalidParams,
+            format!(
+                "Deck name cannot be longer than {} characters",
+                MAX_DECK_NAME_LEN
+            ),
+        ));
+    }
+
+    validate_card_set(card_set)
+}
+
+/// Find saved deck by Uuid which is owned by the user or shared with a team
+/// of the user.
+pub fn find_accessible_deck<D>(
+    deck_model: &D,
+    deck_uuid: &str,
+    user_uuid: &str,
+) -> CommonResult<DeckRecord>
+where
+    D: DeckORM + ?Sized,
+{
+    let deck = deck_model
+        .find_by_uuid(deck_uuid)?
+        .kind(|| ErrorKind::DeckNotFound)?;
+    if deck.owner_uuid == user_uuid {
+        return Ok(deck);
+    }
+    match deck.team_uuid.as_ref() {
+        Some(team_uuid) if deck_model.is_team_member(team_uuid, user_uuid)? => Ok(deck),
+        _ => Err(Error::new(
+            ErrorKind::ForbiddenError,
+            "Deck is not shared with the user",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::poker::card::CardKind;
+
+    mod deck_preset {
+        use super::*;
+
+        #[test]
+        fn all_presets_should_have_valid_card_set() {
+            for preset in DeckPreset::all() {
+                assert!(validate_card_set(&preset.card_set()).is_ok());
+            }
+        }
+
+        #[test]
+        fn numeric_preset_cards_should_carry_value_and_ordinal() {
+            let cards = DeckPreset::ModifiedFibonacci.cards();
+
+            assert_eq!(cards[1].label, "0.5");
+            assert_eq!(cards[1].value, Some(0.5));
+            assert_eq!(cards[1].ordinal, 1);
+            assert_eq!(cards[1].kind, CardKind::Number);
+        }
+
+        #[test]
+        fn preset_should_end_with_special_cards() {
+            let cards = DeckPreset::TShirt.cards();
+            let kinds: Vec<CardKind> = cards.iter().rev().take(2).map(|card| card.kind).collect();
+
+            assert_eq!(kinds, vec![CardKind::Break, CardKind::Unknown]);
+        }
+
+        #[test]
+        fn should_deserialize_from_preset_name() {
+            let preset: DeckPreset = serde_json::from_str(r#""PowersOfTwo""#).unwrap();
+
+            assert_eq!(preset, DeckPreset::PowersOfTwo);
+        }
+    }
+
+    mod resolve_card_set {
+        use super::*;
+
+        #[test]
+        fn should_return_preset_card_set_when_preset_is_provided() {
+            let card_set = resolve_card_set(None, Some(DeckPreset::TShirt)).unwrap();
+
+            assert_eq!(card_set, DeckPreset::TShirt.card_set());
+        }
+
+        #[test]
+        fn should_return_custom_card_set_when_it_is_valid() {
+            let custom_card_set = vec![String::from("1"), String::from("2")];
+
+            let card_set = resolve_card_set(Some(custom_card_set.clone()), None).unwrap();
+
+            assert_eq!(card_set, custom_card_set);
+        }
+
+        #[test]
+        fn should_return_custom_card_set_with_trimmed_labels() {
+            let custom_card_set = vec![String::from(" 3"), String::from("5 ")];
+
+            let card_set = resolve_card_set(Some(custom_card_set), None).unwrap();
+
+            assert_eq!(card_set, vec![String::from("3"), String::from("5")]);
+        }
+
+        #[test]
+        fn should_return_invalid_card_set_error_when_custom_card_set_is_invalid() {
+            let err = resolve_card_set(Some(vec![]), None).unwrap_err();
+
+            assert_eq!(err.kind(), ErrorKind::InvalidCardSet);
+        }
+
+        #[test]
+        fn should_return_invalid_params_error_when_both_are_provided() {
+            let err = resolve_card_set(Some(vec![String::from("1")]), Some(DeckPreset::Fibonacci))
+                .unwrap_err();
+
+            assert_eq!(err.kind(), ErrorKind::InvalidParams);
+        }
+
+        #[test]
+        fn should_return_invalid_params_error_when_neither_is_provided() {
+            let err = resolve_card_set(None, None).unwrap_err();
+
+            assert_eq!(err.kind(), ErrorKind::InvalidParams);
+        }
+    }
+
+    mod validate_card_set {
+        use super::*;
+
+        #[test]
+        fn should_return_error_when_card_set_is_empty() {
+            assert_invalid_card_set(vec![]);
+        }
+
+        #[test]
+        fn should_return_error_when_card_set_has_duplicated_cards() {
+            assert_invalid_card_set(vec![
+                String::from("1"),
+                String::from("2"),
+                String::from("1"),
+            ]);
+        }
+
+        #[test]
+        fn should_return_error_when_cards_are_duplicated_after_trimming() {
+            assert_invalid_card_set(vec![String::from("1"), String::from(" 1 ")]);
+        }
+
+        #[test]
+        fn should_return_error_when_card_label_is_blank() {
+            assert_invalid_card_set(vec![String::from("1"), String::from("  ")]);
+        }
+
+        #[test]
+        fn should_return_error_when_card_set_is_too_long() {
+            let card_set = (0..=MAX_CARD_SET_LEN).map(|i| i.to_string()).collect();
+
+            assert_invalid_card_set(card_set);
+        }
+
+        #[test]
+        fn should_return_error_when_card_label_is_too_long() {
+            let label = "x".repeat(MAX_CARD_LABEL_LEN + 1);
+
+            assert_invalid_card_set(vec![label]);
+        }
+
+        #[test]
+        fn should_count_card_label_length_in_characters() {
+            let label = "🤷".repeat(MAX_CARD_LABEL_LEN);
+
+            assert!(validate_card_set(&[label]).is_ok());
+        }
+
+        #[test]
+        fn should_return_trimmed_card_labels() {
+            let card_set = vec![String::from(" 3"), String::from("5\t"), String::from(" ? ")];
+
+            assert_eq!(
+                validate_card_set(&card_set).unwrap(),
+                vec![String::from("3"), String::from("5"), String::from("?")]
+            );
+        }
+
+        fn assert_invalid_card_set(card_set: Vec<CardLabel>) {
+            let err = validate_card_set(&card_set).unwrap_err();
+
+            assert_eq!(err.kind(), ErrorKind::InvalidCardSet);
+        }
+    }
+}
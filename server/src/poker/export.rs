@@ -0,0 +1,407 @@
+use serde::{Deserialize, Serialize};
+
+use crate::common::clock::format_timestamp;
+use crate::common::error::{ContextExt, ErrorKind, Result as CommonResult};
+use crate::common::model::Uuid;
+use crate::poker::card::CardStats;
+use crate::poker::model::{CardLabel, GameResultRecord, PlayedHandRecord};
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Markdown,
+}
+
+impl ExportFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Json => "application/json",
+            ExportFormat::Markdown => "text/markdown; charset=utf-8",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Markdown => "md",
+        }
+    }
+}
+
+/// Result of a played game. Only the hands of its latest attempt are
+/// exported.
+#[derive(Debug, Clone, Serialize)]
+pub struct GameResult {
+    pub room_uuid: Uuid,
+    pub game_uuid: Uuid,
+    pub title: String,
+    pub link: Option<String>,
+    pub final_estimate: Option<CardLabel>,
+    pub final_estimate_reason: Option<String>,
+    pub attempt: u32,
+    /// Hands played in anonymous mode are not linked to their players, so
+    /// only the distribution in stats is available for them.
+    pub anonymous: bool,
+    pub votes: Vec<Vote>,
+    pub stats: CardStats,
+    pub started_at: String,
+    pub last_updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Vote {
+    pub player_uuid: Uuid,
+    pub player_name: Option<String>,
+    pub card: CardLabel,
+}
+
+pub fn game_results(records: Vec<GameResultRecord>) -> Vec<GameResult> {
+    records.into_iter().map(game_result).collect()
+}
+
+fn game_result(record: GameResultRecord) -> GameResult {
+    let attempt = record
+        .hands
+        .iter()
+        .map(|played_hand| played_hand.hand.attempt)
+        .max()
+        .unwrap_or(1);
+    let hands: Vec<&PlayedHandRecord> = record
+        .hands
+        .iter()
+        .filter(|played_hand| played_hand.hand.attempt == attempt)
+        .collect();
+
+    let labels: Vec<CardLabel> = hands
+        .iter()
+        .map(|played_hand| played_hand.hand.card.clone())
+        .collect();
+
+    let anonymous = hands
+        .iter()
+        .any(|played_hand| played_hand.hand.user_uuid.is_none());
+    let votes = if anonymous {
+        vec![]
+    } else {
+        hands
+            .iter()
+            .filter_map(|played_hand| {
+                Some(Vote {
+                    player_uuid: played_hand.hand.user_uuid.clone()?,
+                    player_name: played_hand.user_name.clone(),
+                    card: played_hand.hand.card.clone(),
+                })
+            })
+            .collect()
+    };
+
+    GameResult {
+        room_uuid: record.room.uuid,
+        game_uuid: record.game.uuid,
+        title: record.game.title,
+        link: record.game.link,
+        final_estimate: record.game.final_estimate,
+        final_estimate_reason: record.game.final_estimate_reason,
+        attempt: attempt as u32,
+        anonymous,
+        votes,
+        stats: CardStats::from_labels(&record.room.card_set, &labels),
+        started_at: format_timestamp(record.game.started_at.unwrap_or(record.game.created_at)),
+        last_updated_at: format_timestamp(record.game.last_updated_at),
+    }
+}
+
+pub fn render(format: ExportFormat, results: &[GameResult]) -> CommonResult<String> {
+    match format {
+        ExportFormat::Csv => render_csv(results),
+        ExportFormat::Json => serde_json::to_string_pretty(results).context(|| {
+            (
+                ErrorKind::InternalServerError,
+                "Error when serializing results to JSON",
+            )
+        }),
+        ExportFormat::Markdown => Ok(render_markdown(results)),
+    }
+}
+
+#[derive(Serialize)]
+struct CsvRow<'a> {
+    room_uuid: &'a str,
+    game_uuid: &'a str,
+    title: &'a str,
+    link: Option<&'a str>,
+    final_estimate: Option<&'a str>,
+    final_estimate_reason: Option<&'a str>,
+    attempt: u32,
+    votes: String,
+    average: Option<String>,
+    median: Option<String>,
+    min: Option<&'a str>,
+    max: Option<&'a str>,
+    consensus: bool,
+    started_at: &'a str,
+    last_updated_at: &'a str,
+}
+
+fn render_csv(results: &[GameResult]) -> CommonResult<String> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    for result in results.iter() {
+        writer
+            .serialize(CsvRow {
+                room_uuid: &result.room_uuid,
+                game_uuid: &result.game_uuid,
+                title: &result.title,
+                link: result.link.as_deref(),
+                final_estimate: result.final_estimate.as_deref(),
+                final_estimate_reason: result.final_estimate_reason.as_deref(),
+                attempt: result.attempt,
+                votes: votes_summary(result),
+                average: result.stats.average.map(format_number),
+                median: result.stats.median.map(format_number),
+                min: result.stats.min.as_deref(),
+                max: result.stats.max.as_deref(),
+                consensus: result.stats.consensus,
+                started_at: &result.started_at,
+                last_updated_at: &result.last_updated_at,
+            })
+            .context(|| {
+                (
+                    ErrorKind::InternalServerError,
+                    "Error when writing results to CSV",
+                )
+            })?;
+    }
+    let bytes = writer.into_inner().context(|| {
+        (
+            ErrorKind::InternalServerError,
+            "Error when writing results to CSV",
+        )
+    })?;
+
+    String::from_utf8(bytes).context(|| (ErrorKind::InternalServerError, "CSV is not UTF-8"))
+}
+
+fn render_markdown(results: &[GameResult]) -> String {
+    let mut lines = vec![
+        String::from(
+            "| Title | Final estimate | Votes | Average | Median | Consensus | Started at |",
+        ),
+        String::from("| --- | --- | --- | --- | --- | --- | --- |"),
+    ];
+    for result in results.iter() {
+        let title = match result.link.as_ref() {
+            Some(link) => format!("[{}]({})", result.title, link),
+            None => result.title.clone(),
+        };
+        let final_estimate = match (
+            result.final_estimate.as_ref(),
+            result.final_estimate_reason.as_ref(),
+        ) {
+            (Some(estimate), Some(reason)) => format!("{} ({})", estimate, reason),
+            (Some(estimate), None) => estimate.clone(),
+            (None, _) => String::new(),
+        };
+        let cells = [
+            title,
+            final_estimate,
+            votes_summary(result),
+            result.stats.average.map(format_number).unwrap_or_default(),
+            result.stats.median.map(format_number).unwrap_or_default(),
+            String::from(if result.stats.consensus { "Yes" } else { "No" }),
+            result.started_at.clone(),
+        ];
+        let cells: Vec<String> = cells
+            .iter()
+            .map(|cell| escape_markdown_cell(cell))
+            .collect();
+        lines.push(format!("| {} |", cells.join(" | ")));
+    }
+
+    lines.join("\n") + "\n"
+}
+
+/// Players with their cards, or the distribution of the cards in anonymous
+/// mode.
+fn votes_summary(result: &GameResult) -> String {
+    let votes: Vec<String> = if result.anonymous {
+        result
+            .stats
+            .distribution
+            .iter()
+            .map(|card_count| format!("{} x{}", card_count.label, card_count.count))
+            .collect()
+    } else {
+        result
+            .votes
+            .iter()
+            .map(|vote| {
+                let player = vote.player_name.as_ref().unwrap_or(&vote.player_uuid);
+                format!("{}: {}", player, vote.card)
+            })
+            .collect()
+    };
+
+    votes.join(", ")
+}
+
+fn format_number(number: f64) -> String {
+    format!("{}", (number * 100.0).round() / 100.0)
+}
+
+fn escape_markdown_cell(cell: &str) -> String {
+    cell.replace('|', "\\|")
+        .replace('\r', "")
+        .replace('\n', " ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use crate::poker::model::{GameHandRecord, GameRecord, RoomOptions, RoomRecord};
+
+    mod game_results {
+        use super::*;
+
+        #[test]
+        fn should_only_keep_hands_of_latest_attempt() {
+            let record = make_game_result_record(vec![
+                make_hand(Some("player-1"), "1", 1),
+                make_hand(Some("player-1"), "5", 2),
+                make_hand(Some("player-2"), "3", 2),
+            ]);
+
+            let results = game_results(vec![record]);
+
+            assert_eq!(results[0].attempt, 2);
+            let cards: Vec<&str> = results[0]
+                .votes
+                .iter()
+                .map(|vote| vote.card.as_str())
+                .collect();
+            assert_eq!(cards, vec!["5", "3"]);
+            assert_eq!(results[0].stats.average, Some(4.0));
+        }
+
+        #[test]
+        fn should_not_export_votes_of_anonymous_hands() {
+            let record =
+                make_game_result_record(vec![make_hand(None, "3", 1), make_hand(None, "3", 1)]);
+
+            let results = game_results(vec![record]);
+
+            assert!(results[0].anonymous);
+            assert!(results[0].votes.is_empty());
+            assert_eq!(results[0].stats.distribution[0].count, 2);
+        }
+
+        #[test]
+        fn should_format_timestamps_in_utc() {
+            let results = game_results(vec![make_game_result_record(vec![])]);
+
+            assert_eq!(results[0].started_at, "2019-10-20T14:22:10Z");
+        }
+
+        #[test]
+        fn should_export_when_queued_story_is_started() {
+            let mut record = make_game_result_record(vec![]);
+            record.game.created_at -= Duration::from_secs(3600);
+
+            let results = game_results(vec![record]);
+
+            assert_eq!(results[0].started_at, "2019-10-20T14:22:10Z");
+        }
+    }
+
+    mod render {
+        use super::*;
+
+        #[test]
+        fn should_render_csv_with_header_row() {
+            let results = game_results(vec![make_game_result_record(vec![make_hand(
+                Some("player-1"),
+                "3",
+                1,
+            )])]);
+
+            let csv = render(ExportFormat::Csv, &results).unwrap();
+
+            let lines: Vec<&str> = csv.lines().collect();
+            assert!(lines[0].starts_with("room_uuid,game_uuid,title,link,final_estimate,"));
+            assert!(lines[1].contains(",Login,"));
+            assert!(lines[1].contains(",Calvin Lau: 3,"));
+        }
+
+        #[test]
+        fn should_render_markdown_table_with_escaped_cells() {
+            let mut record = make_game_result_record(vec![make_hand(None, "3", 1)]);
+            record.game.title = String::from("Login | Logout");
+
+            let markdown = render(ExportFormat::Markdown, &game_results(vec![record])).unwrap();
+
+            let lines: Vec<&str> = markdown.lines().collect();
+            assert_eq!(lines.len(), 3);
+            assert_eq!(
+                lines[2],
+                "| Login \\| Logout | 3 | 3 x1 | 3 | 3 | Yes | 2019-10-20T14:22:10Z |"
+            );
+        }
+    }
+
+    fn make_game_result_record(hands: Vec<GameHandRecord>) -> GameResultRecord {
+        let time = UNIX_EPOCH + Duration::from_secs(1_571_581_330);
+
+        GameResultRecord {
+            room: RoomRecord {
+                uuid: String::from("room-uuid"),
+                passphrase: None,
+                card_set: vec![String::from("1"), String::from("3"), String::from("5")],
+                owner_uuid: String::from("owner-uuid"),
+                created_at: time,
+                last_updated_at: time,
+                options: RoomOptions::default(),
+                join_code: None,
+                closed_at: None,
+            },
+            game: GameRecord {
+                uuid: String::from("game-uuid"),
+                room_uuid: Some(String::from("room-uuid")),
+                sequence: None,
+                title: String::from("Login"),
+                description: None,
+                created_at: time,
+                last_updated_at: time,
+                final_estimate: Some(String::from("3")),
+                final_estimate_reason: None,
+                link: None,
+                external_id: None,
+                pre_estimate: None,
+                started_at: Some(time),
+            },
+            hands: hands
+                .into_iter()
+                .map(|hand| PlayedHandRecord {
+                    user_name: hand.user_uuid.as_ref().map(|_| String::from("Calvin Lau")),
+                    hand,
+                })
+                .collect(),
+        }
+    }
+
+    fn make_hand(user_uuid: Option<&str>, card: &str, attempt: i32) -> GameHandRecord {
+        GameHandRecord {
+            id: 1,
+            game_uuid: Some(String::from("game-uuid")),
+            user_uuid: user_uuid.map(String::from),
+            card: String::from(card),
+            last_updated_at: SystemTime::now(),
+            attempt,
+        }
+    }
+}
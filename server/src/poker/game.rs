@@ -1,10 +1,66 @@
 use std::collections::HashMap;
+use std::time::Instant;
 
-use crate::client::ClientId;
-use crate::poker::model::Card;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::common::model::Uuid as UuidType;
+use crate::poker::model::CardLabel;
 
 pub struct Game {
+    pub uuid: UuidType,
+    pub title: String,
+    pub description: Option<String>,
+    pub link: Option<String>,
+    /// Cards played by the Uuids of their players.
+    pub players_hands: HashMap<UuidType, CardLabel>,
+    pub revealed: bool,
+    /// Round of voting, increased when the game is voted again after reveal.
+    pub attempt: u32,
+    /// Time when the cards are revealed automatically, if the game is
+    /// timeboxed.
+    pub deadline: Option<Instant>,
+}
+
+impl Game {
+    pub fn new(title: String, description: Option<String>) -> Self {
+        Game {
+            uuid: Uuid::new_v4().to_string(),
+            title,
+            description,
+            link: None,
+            players_hands: HashMap::new(),
+            revealed: false,
+            attempt: 1,
+            deadline: None,
+        }
+    }
+}
+
+impl From<Story> for Game {
+    fn from(story: Story) -> Self {
+        Game {
+            uuid: story.uuid,
+            link: story.link,
+            ..Game::new(story.title, story.description)
+        }
+    }
+}
+
+/// Story waiting in the backlog of a room to be estimated.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Story {
+    pub uuid: UuidType,
     pub title: String,
     pub description: Option<String>,
-    pub players_hands: HashMap<ClientId, Card>,
+    /// Link to the story in external issue tracker.
+    pub link: Option<String>,
+    pub external_id: Option<String>,
+    /// Estimate suggested before the game is played.
+    pub pre_estimate: Option<CardLabel>,
+    /// Position of the story in the persisted backlog. Sequences are
+    /// ascending but not necessarily consecutive.
+    #[serde(skip)]
+    pub sequence: i32,
 }
@@ -0,0 +1,555 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::clock::format_timestamp;
+use crate::common::error::{Error, ErrorKind, Result as CommonResult};
+use crate::common::model::Uuid;
+use crate::poker::card::CardStats;
+use crate::poker::model::{
+    CardLabel, GameRecord, PlayedHandRecord, RoomORM, RoomOptions, RoomRecord,
+};
+
+pub const DEFAULT_PAGE_SIZE: i64 = 20;
+pub const MAX_PAGE_SIZE: i64 = 100;
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct PageQuery {
+    pub offset: Option<i64>,
+    /// DEFAULT_PAGE_SIZE when absent.
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Paging {
+    pub offset: i64,
+    pub limit: i64,
+}
+
+impl Paging {
+    pub fn from_query(query: PageQuery) -> CommonResult<Self> {
+        let offset = query.offset.unwrap_or(0);
+        let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+        if offset < 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidParams,
+                "Offset cannot be negative",
+            ));
+        }
+        if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+            return Err(Error::new(
+                ErrorKind::InvalidParams,
+                format!("Limit must be between 1 and {}", MAX_PAGE_SIZE),
+            ));
+        }
+
+        Ok(Paging { offset, limit })
+    }
+
+    /// Number of records to query. One more record than the page size is
+    /// queried to tell whether there are more pages.
+    fn query_limit(self) -> i64 {
+        self.limit + 1
+    }
+
+    fn page<T>(self, mut items: Vec<T>) -> Page<T> {
+        let has_more = items.len() as i64 > self.limit;
+        items.truncate(self.limit as usize);
+
+        Page {
+            items,
+            offset: self.offset,
+            limit: self.limit,
+            has_more,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub offset: i64,
+    pub limit: i64,
+    pub has_more: bool,
+}
+
+/// Room as listed in history. Passphrase of private room is never returned.
+#[derive(Debug, Serialize)]
+pub struct RoomSummary {
+    pub uuid: Uuid,
+    pub private: bool,
+    pub card_set: Vec<CardLabel>,
+    pub owner_uuid: Uuid,
+    pub options: RoomOptions,
+    pub created_at: String,
+    pub last_updated_at: String,
+    pub closed_at: Option<String>,
+}
+
+impl From<RoomRecord> for RoomSummary {
+    fn from(record: RoomRecord) -> Self {
+        RoomSummary {
+            uuid: record.uuid,
+            private: record.passphrase.is_some(),
+            card_set: record.card_set,
+            owner_uuid: record.owner_uuid,
+            options: record.options,
+            created_at: format_timestamp(record.created_at),
+            last_updated_at: format_timestamp(record.last_updated_at),
+            closed_at: record.closed_at.map(format_timestamp),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RoomDetails {
+    #[serde(flatten)]
+    pub room: RoomSummary,
+    /// Played games in the order they are started.
+    pub games: Page<GameSummary>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GameSummary {
+    pub uuid: Uuid,
+    pub title: String,
+    pub description: Option<String>,
+    pub link: Option<String>,
+    pub final_estimate: Option<CardLabel>,
+    pub final_estimate_reason: Option<String>,
+    pub started_at: String,
+    pub last_updated_at: String,
+}
+
+impl From<GameRecord> for GameSummary {
+    fn from(record: GameRecord) -> Self {
+        GameSummary {
+            uuid: record.uuid,
+            title: record.title,
+            description: record.description,
+            link: record.link,
+            final_estimate: record.final_estimate,
+            final_estimate_reason: record.final_estimate_reason,
+            started_at: format_timestamp(record.started_at.unwrap_or(record.created_at)),
+            last_updated_at: format_timestamp(record.last_updated_at),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct GameDetails {
+    #[serde(flatten)]
+    pub game: GameSummary,
+    pub room_uuid: Uuid,
+    /// Every attempt of the game, including the ones which are re-voted.
+    pub attempts: Vec<Attempt>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Attempt {
+    pub attempt: u32,
+    pub hands: Vec<PlayedHand>,
+    pub stats: CardStats,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PlayedHand {
+    /// Absent when the hand is played in anonymous mode.
+    pub player_uuid: Option<Uuid>,
+    pub player_name: Option<String>,
+    pub card: CardLabel,
+}
+
+/// List the rooms the user owns or has joined, most recently created first.
+pub fn list_rooms<R>(
+    room_model: &R,
+    user_uuid: &str,
+    paging: Paging,
+) -> CommonResult<Page<RoomSummary>>
+where
+    R: RoomORM,
+{
+    let room_records = room_model.list_by_member(user_uuid, paging.offset, paging.query_limit())?;
+
+    Ok(paging.page(room_records.into_iter().map(RoomSummary::from).collect()))
+}
+
+/// Find the room with a page of its played games.
+pub fn find_room<R>(
+    room_model: &R,
+    user_uuid: &str,
+    room_uuid: &str,
+    paging: Paging,
+) -> CommonResult<RoomDetails>
+where
+    R: RoomORM,
+{
+    let room_record = room_model
+        .find_by_uuid(room_uuid)?
+        .ok_or_else(|| Error::from(ErrorKind::RoomNotFound))?;
+    check_access(room_model, user_uuid, &room_record)?;
+
+    let game_records = room_model.list_games(room_uuid, paging.offset, paging.query_limit())?;
+
+    Ok(RoomDetails {
+        room: RoomSummary::from(room_record),
+        games: paging.page(game_records.into_iter().map(GameSummary::from).collect()),
+    })
+}
+
+/// Find the played game with its hands and the stats of each attempt.
+pub fn find_game<R>(room_model: &R, user_uuid: &str, game_uuid: &str) -> CommonResult<GameDetails>
+where
+    R: RoomORM,
+{
+    let game_record = room_model
+        .find_game(game_uuid)?
+        // Stories in backlog are not played yet
+        .filter(|game_record| game_record.sequence.is_none())
+        .ok_or_else(|| Error::from(ErrorKind::GameNotFound))?;
+    let room_uuid = game_record
+        .room_uuid
+        .clone()
+        .ok_or_else(|| Error::from(ErrorKind::GameNotFound))?;
+    let room_record = room_model
+        .find_by_uuid(&room_uuid)?
+        .ok_or_else(|| Error::from(ErrorKind::GameNotFound))?;
+    check_access(room_model, user_uuid, &room_record)?;
+
+    let hand_records = room_model.list_game_hands(game_uuid)?;
+
+    Ok(GameDetails {
+        game: GameSummary::from(game_record),
+        room_uuid,
+        attempts: attempts(&room_record.card_set, hand_records),
+    })
+}
+
+/// Only the room owner and players who have joined the room can read its
+/// history.
+fn check_access<R>(room_model: &R, user_uuid: &str, room_record: &RoomRecord) -> CommonResult<()>
+where
+    R: RoomORM,
+{
+    if room_record.owner_uuid == user_uuid || room_model.is_player(&room_record.uuid, user_uuid)? {
+        Ok(())
+    } else {
+        Err(Error::new(
+            ErrorKind::ForbiddenError,
+            "Only room owner and players can read room history",
+        ))
+    }
+}
+
+fn attempts(card_set: &[CardLabel], hand_records: Vec<PlayedHandRecord>) -> Vec<Attempt> {
+    let mut hands_by_attempt: BTreeMap<i32, Vec<PlayedHandRecord>> = BTreeMap::new();
+    for played_hand in hand_records.into_iter() {
+        hands_by_attempt
+            .entry(played_hand.hand.attempt)
+            .or_default()
+            .push(played_hand);
+    }
+
+    hands_by_attempt
+        .into_iter()
+        .map(|(attempt, played_hands)| {
+            let labels: Vec<CardLabel> = played_hands
+                .iter()
+                .map(|played_hand| played_hand.hand.card.clone())
+                .collect();
+
+            Attempt {
+                attempt: attempt as u32,
+                stats: CardStats::from_labels(card_set, &labels),
+                hands: played_hands
+                    .into_iter()
+                    .map(|played_hand| PlayedHand {
+                        player_uuid: played_hand.hand.user_uuid,
+                        player_name: played_hand.user_name,
+                        card: played_hand.hand.card,
+                    })
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use crate::poker::model::{GameHandRecord, MockRoomORM};
+
+    const OWNER_UUID: &str = "owner-uuid";
+    const PLAYER_UUID: &str = "player-uuid";
+    const ROOM_UUID: &str = "room-uuid";
+    const GAME_UUID: &str = "game-uuid";
+
+    mod paging {
+        use super::*;
+
+        #[test]
+        fn should_use_default_page_size_when_limit_is_absent() {
+            let paging = Paging::from_query(PageQuery::default()).unwrap();
+
+            assert_eq!(
+                paging,
+                Paging {
+                    offset: 0,
+                    limit: DEFAULT_PAGE_SIZE,
+                }
+            );
+        }
+
+        #[test]
+        fn should_return_invalid_params_error_when_limit_is_out_of_range() {
+            for limit in [0, MAX_PAGE_SIZE + 1].iter() {
+                let paging_result = Paging::from_query(PageQuery {
+                    offset: None,
+                    limit: Some(*limit),
+                });
+
+                assert_eq!(paging_result.unwrap_err().kind(), ErrorKind::InvalidParams);
+            }
+        }
+
+        #[test]
+        fn should_return_invalid_params_error_when_offset_is_negative() {
+            let paging_result = Paging::from_query(PageQuery {
+                offset: Some(-1),
+                limit: None,
+            });
+
+            assert_eq!(paging_result.unwrap_err().kind(), ErrorKind::InvalidParams);
+        }
+
+        #[test]
+        fn should_tell_whether_there_are_more_items() {
+            let paging = Paging {
+                offset: 0,
+                limit: 2,
+            };
+
+            let page = paging.page(vec![1, 2, 3]);
+            assert_eq!(page.items, vec![1, 2]);
+            assert!(page.has_more);
+
+            let page = paging.page(vec![1, 2]);
+            assert!(!page.has_more);
+        }
+    }
+
+    mod list_rooms {
+        use super::*;
+
+        #[test]
+        fn should_hide_passphrase_of_private_rooms() {
+            let mut room_model = MockRoomORM::new();
+            room_model
+                .expect_list_by_member()
+                .withf(|user_uuid, offset, limit| {
+                    user_uuid == PLAYER_UUID && *offset == 0 && *limit == DEFAULT_PAGE_SIZE + 1
+                })
+                .once()
+                .returning(|_, _, _| {
+                    let mut room_record = make_room_record();
+                    room_record.passphrase = Some(String::from("Passphrase"));
+                    Ok(vec![room_record])
+                });
+
+            let page = list_rooms(
+                &room_model,
+                PLAYER_UUID,
+                Paging::from_query(PageQuery::default()).unwrap(),
+            )
+            .unwrap();
+
+            assert_eq!(page.items.len(), 1);
+            assert!(page.items[0].private);
+            let json = serde_json::to_string(&page).unwrap();
+            assert!(!json.contains("Passphrase"));
+        }
+    }
+
+    mod find_room {
+        use super::*;
+
+        #[test]
+        fn should_return_room_not_found_error_when_room_does_not_exist() {
+            let mut room_model = MockRoomORM::new();
+            room_model.expect_find_by_uuid().returning(|_| Ok(None));
+
+            let find_result = find_room(&room_model, OWNER_UUID, ROOM_UUID, default_paging());
+
+            assert_eq!(find_result.unwrap_err().kind(), ErrorKind::RoomNotFound);
+        }
+
+        #[test]
+        fn should_return_forbidden_error_when_user_has_not_joined_room() {
+            let mut room_model = MockRoomORM::new();
+            room_model
+                .expect_find_by_uuid()
+                .returning(|_| Ok(Some(make_room_record())));
+            room_model
+                .expect_is_player()
+                .withf(|room_uuid, user_uuid| room_uuid == ROOM_UUID && user_uuid == PLAYER_UUID)
+                .returning(|_, _| Ok(false));
+            room_model.expect_list_games().never();
+
+            let find_result = find_room(&room_model, PLAYER_UUID, ROOM_UUID, default_paging());
+
+            assert_eq!(find_result.unwrap_err().kind(), ErrorKind::ForbiddenError);
+        }
+
+        #[test]
+        fn should_return_room_with_games_to_player() {
+            let mut room_model = MockRoomORM::new();
+            room_model
+                .expect_find_by_uuid()
+                .returning(|_| Ok(Some(make_room_record())));
+            room_model.expect_is_player().returning(|_, _| Ok(true));
+            room_model
+                .expect_list_games()
+                .withf(|room_uuid, offset, limit| {
+                    room_uuid == ROOM_UUID && *offset == 0 && *limit == DEFAULT_PAGE_SIZE + 1
+                })
+                .returning(|_, _, _| Ok(vec![make_game_record()]));
+
+            let room_details =
+                find_room(&room_model, PLAYER_UUID, ROOM_UUID, default_paging()).unwrap();
+
+            assert_eq!(room_details.room.uuid, ROOM_UUID);
+            assert_eq!(room_details.games.items[0].title, "Login");
+            assert!(!room_details.games.has_more);
+        }
+
+        #[test]
+        fn should_return_when_queued_story_is_started() {
+            let mut room_model = MockRoomORM::new();
+            room_model
+                .expect_find_by_uuid()
+                .returning(|_| Ok(Some(make_room_record())));
+            room_model.expect_is_player().returning(|_, _| Ok(true));
+            room_model.expect_list_games().returning(|_, _, _| {
+                let mut game_record = make_game_record();
+                game_record.created_at -= Duration::from_secs(3600);
+                Ok(vec![game_record])
+            });
+
+            let room_details =
+                find_room(&room_model, PLAYER_UUID, ROOM_UUID, default_paging()).unwrap();
+
+            assert_eq!(
+                room_details.games.items[0].started_at,
+                "2019-10-20T14:22:10Z"
+            );
+        }
+    }
+
+    mod find_game {
+        use super::*;
+
+        #[test]
+        fn should_return_game_not_found_error_when_game_is_in_backlog() {
+            let mut room_model = MockRoomORM::new();
+            room_model.expect_find_game().returning(|_| {
+                let mut game_record = make_game_record();
+                game_record.sequence = Some(1);
+                Ok(Some(game_record))
+            });
+
+            let find_result = find_game(&room_model, OWNER_UUID, GAME_UUID);
+
+            assert_eq!(find_result.unwrap_err().kind(), ErrorKind::GameNotFound);
+        }
+
+        #[test]
+        fn should_group_hands_by_attempt_with_stats() {
+            let mut room_model = MockRoomORM::new();
+            room_model
+                .expect_find_game()
+                .returning(|_| Ok(Some(make_game_record())));
+            room_model
+                .expect_find_by_uuid()
+                .returning(|_| Ok(Some(make_room_record())));
+            room_model.expect_is_player().never();
+            room_model.expect_list_game_hands().returning(|_| {
+                Ok(vec![
+                    make_played_hand(Some(PLAYER_UUID), "1", 1),
+                    make_played_hand(Some(PLAYER_UUID), "5", 2),
+                    make_played_hand(None, "3", 2),
+                ])
+            });
+
+            let game_details = find_game(&room_model, OWNER_UUID, GAME_UUID).unwrap();
+
+            assert_eq!(game_details.room_uuid, ROOM_UUID);
+            assert_eq!(game_details.attempts.len(), 2);
+            assert_eq!(game_details.attempts[0].hands.len(), 1);
+            let latest_attempt = &game_details.attempts[1];
+            assert_eq!(latest_attempt.attempt, 2);
+            assert_eq!(
+                latest_attempt.hands[0].player_name,
+                Some(String::from("Calvin Lau"))
+            );
+            assert_eq!(latest_attempt.hands[1].player_uuid, None);
+            assert_eq!(latest_attempt.stats.average, Some(4.0));
+        }
+    }
+
+    fn default_paging() -> Paging {
+        Paging::from_query(PageQuery::default()).unwrap()
+    }
+
+    fn make_room_record() -> RoomRecord {
+        let time = UNIX_EPOCH + Duration::from_secs(1_571_581_330);
+
+        RoomRecord {
+            uuid: String::from(ROOM_UUID),
+            passphrase: None,
+            card_set: vec![String::from("1"), String::from("3"), String::from("5")],
+            owner_uuid: String::from(OWNER_UUID),
+            created_at: time,
+            last_updated_at: time,
+            options: RoomOptions::default(),
+            join_code: None,
+            closed_at: None,
+        }
+    }
+
+    fn make_game_record() -> GameRecord {
+        let time = UNIX_EPOCH + Duration::from_secs(1_571_581_330);
+
+        GameRecord {
+            uuid: String::from(GAME_UUID),
+            room_uuid: Some(String::from(ROOM_UUID)),
+            sequence: None,
+            title: String::from("Login"),
+            description: None,
+            created_at: time,
+            last_updated_at: time,
+            final_estimate: None,
+            final_estimate_reason: None,
+            link: None,
+            external_id: None,
+            pre_estimate: None,
+            started_at: Some(time),
+        }
+    }
+
+    fn make_played_hand(user_uuid: Option<&str>, card: &str, attempt: i32) -> PlayedHandRecord {
+        PlayedHandRecord {
+            hand: GameHandRecord {
+                id: 1,
+                game_uuid: Some(String::from(GAME_UUID)),
+                user_uuid: user_uuid.map(String::from),
+                card: String::from(card),
+                last_updated_at: SystemTime::now(),
+                attempt,
+            },
+            user_name: user_uuid.map(|_| String::from("Calvin Lau")),
+        }
+    }
+}
@@ -1,6 +1,12 @@
+pub mod backlog;
+pub mod card;
+pub mod deck;
+pub mod export;
 pub mod game;
+pub mod history;
 pub mod model;
 pub mod room;
+pub mod route;
 
 pub use game::Game;
 pub use room::Room;
@@ -1,18 +1,57 @@
+use std::io::Write;
 use std::time::SystemTime;
 
+use diesel::deserialize::{self, FromSql};
+use diesel::pg::Pg;
 use diesel::prelude::*;
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sql_types::Jsonb;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::common::error::{ContextExt, ErrorKind, Result as CommonResult};
 use crate::common::model::{ConnectionPool, Uuid as UuidType};
-use crate::schema::rooms;
+use crate::schema::{
+    decks, game_hands, games, room_bans, room_players, rooms, team_members, users,
+};
 use crate::user::model::UserRecord;
 
 #[cfg(test)]
 use mockall::automock;
 
-pub type Card = String;
+pub type CardLabel = String;
+
+/// Room-level settings which can be changed by the room owner mid-session.
+/// Persisted as JSON so that new options do not require a schema change.
+#[derive(
+    AsExpression, FromSqlRow, Clone, Debug, Default, Serialize, Deserialize, PartialEq, JsonSchema,
+)]
+#[sql_type = "Jsonb"]
+#[serde(default)]
+pub struct RoomOptions {
+    /// Reject new players from joining the room.
+    pub locked: bool,
+    /// Reveal the cards as soon as every player, except spectators, has
+    /// played.
+    pub auto_reveal: bool,
+    /// Reveal and persist the played cards without who played them.
+    pub anonymous: bool,
+}
+
+impl FromSql<Jsonb, Pg> for RoomOptions {
+    fn from_sql(bytes: Option<&[u8]>) -> deserialize::Result<Self> {
+        let value = <serde_json::Value as FromSql<Jsonb, Pg>>::from_sql(bytes)?;
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+impl ToSql<Jsonb, Pg> for RoomOptions {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, Pg>) -> serialize::Result {
+        let value = serde_json::to_value(self)?;
+        <serde_json::Value as ToSql<Jsonb, Pg>>::to_sql(&value, out)
+    }
+}
 
 #[derive(Insertable, Queryable, Associations, Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[belongs_to(UserRecord, foreign_key = "owner_uuid")]
@@ -20,23 +59,257 @@ pub type Card = String;
 pub struct RoomRecord {
     pub uuid: UuidType,
     pub passphrase: Option<String>,
-    pub card_set: Vec<Card>,
+    pub card_set: Vec<CardLabel>,
     pub owner_uuid: UuidType,
     pub created_at: SystemTime,
     pub last_updated_at: SystemTime,
+    pub options: RoomOptions,
+    /// Short code to join the room with instead of its Uuid.
+    pub join_code: Option<String>,
+    pub closed_at: Option<SystemTime>,
 }
 
+/// Columns of rooms table which are loaded into RoomRecord.
+type RoomColumns = (
+    rooms::uuid,
+    rooms::passphrase,
+    rooms::card_set,
+    rooms::owner_uuid,
+    rooms::created_at,
+    rooms::last_updated_at,
+    rooms::options,
+    rooms::join_code,
+    rooms::closed_at,
+);
+
+const ROOM_COLUMNS: RoomColumns = (
+    rooms::uuid,
+    rooms::passphrase,
+    rooms::card_set,
+    rooms::owner_uuid,
+    rooms::created_at,
+    rooms::last_updated_at,
+    rooms::options,
+    rooms::join_code,
+    rooms::closed_at,
+);
+
 #[derive(Debug, PartialEq)]
 pub struct NewRoomRecordParams {
     pub passphrase: Option<String>,
     pub owner_uuid: UuidType,
-    pub card_set: Vec<Card>,
+    pub card_set: Vec<CardLabel>,
+    pub options: RoomOptions,
+    pub join_code: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct UpdateRoomRecordParams {
+    pub uuid: UuidType,
+    pub passphrase: Option<String>,
+    pub card_set: Vec<CardLabel>,
+    pub options: RoomOptions,
+}
+
+#[derive(Insertable, Queryable, Associations, Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[belongs_to(RoomRecord, foreign_key = "room_uuid")]
+#[table_name = "room_bans"]
+pub struct RoomBanRecord {
+    pub room_uuid: UuidType,
+    pub user_uuid: UuidType,
+    pub banned_at: SystemTime,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct NewRoomBanRecordParams {
+    pub room_uuid: UuidType,
+    pub user_uuid: UuidType,
+}
+
+#[derive(Insertable, Queryable, Associations, Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[belongs_to(RoomRecord, foreign_key = "room_uuid")]
+#[table_name = "room_players"]
+pub struct RoomPlayerRecord {
+    pub room_uuid: UuidType,
+    pub player_uuid: UuidType,
+    pub joined_at: SystemTime,
+    pub left_at: Option<SystemTime>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct NewRoomPlayerRecordParams {
+    pub room_uuid: UuidType,
+    pub player_uuid: UuidType,
+}
+
+#[derive(Insertable, Queryable, Associations, Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[belongs_to(RoomRecord, foreign_key = "room_uuid")]
+#[table_name = "games"]
+pub struct GameRecord {
+    pub uuid: UuidType,
+    pub room_uuid: Option<UuidType>,
+    pub sequence: Option<i32>,
+    pub title: String,
+    pub description: Option<String>,
+    pub created_at: SystemTime,
+    pub last_updated_at: SystemTime,
+    /// Estimate agreed after the cards are revealed.
+    pub final_estimate: Option<CardLabel>,
+    /// Reason of overriding the final estimate with a card not in the deck.
+    pub final_estimate_reason: Option<String>,
+    /// Link to the story in external issue tracker.
+    pub link: Option<String>,
+    /// Id of the story in external issue tracker, used to skip stories which
+    /// are already imported.
+    pub external_id: Option<String>,
+    /// Estimate suggested before the game is played.
+    pub pre_estimate: Option<CardLabel>,
+    /// None while the story waits in the backlog.
+    pub started_at: Option<SystemTime>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct NewGameRecordParams {
+    pub uuid: UuidType,
+    pub room_uuid: UuidType,
+    pub title: String,
+    pub description: Option<String>,
+}
+
+/// Story waiting in the backlog of a room. It is persisted as a game with
+/// sequence until it is started.
+#[derive(Debug, PartialEq)]
+pub struct NewStoryRecordParams {
+    pub uuid: UuidType,
+    pub room_uuid: UuidType,
+    pub sequence: i32,
+    pub title: String,
+    pub description: Option<String>,
+    pub link: Option<String>,
+    pub external_id: Option<String>,
+    pub pre_estimate: Option<CardLabel>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct FinalizeGameRecordParams {
+    pub uuid: UuidType,
+    pub room_uuid: UuidType,
+    pub final_estimate: CardLabel,
+    pub final_estimate_reason: Option<String>,
+}
+
+#[derive(Queryable, Associations, Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[belongs_to(GameRecord, foreign_key = "game_uuid")]
+#[table_name = "game_hands"]
+pub struct GameHandRecord {
+    pub id: i32,
+    pub game_uuid: Option<UuidType>,
+    /// None when the hand is played in anonymous mode.
+    pub user_uuid: Option<UuidType>,
+    pub card: CardLabel,
+    pub last_updated_at: SystemTime,
+    /// Round of voting of the game which the hand is played in, starting
+    /// from 1.
+    pub attempt: i32,
+}
+
+#[derive(Insertable, Clone, Debug, PartialEq)]
+#[table_name = "game_hands"]
+pub struct NewGameHandRecordParams {
+    pub game_uuid: Option<UuidType>,
+    pub user_uuid: Option<UuidType>,
+    pub card: CardLabel,
+    pub last_updated_at: SystemTime,
+    pub attempt: i32,
+}
+
+/// Filter of the played games of rooms owned by the user. Time range applies
+/// to when the games are started.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FindGameResultsParams {
+    pub owner_uuid: UuidType,
+    pub room_uuid: Option<UuidType>,
+    pub from: Option<SystemTime>,
+    pub to: Option<SystemTime>,
+}
+
+/// Played game together with its room and the hands played in it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameResultRecord {
+    pub room: RoomRecord,
+    pub game: GameRecord,
+    pub hands: Vec<PlayedHandRecord>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayedHandRecord {
+    pub hand: GameHandRecord,
+    pub user_name: Option<String>,
 }
 
 #[cfg_attr(test, automock)]
 pub trait RoomORM: Send + Sync {
     /// Create and return room record in database based on given params.
     fn create(&self, room: NewRoomRecordParams) -> CommonResult<RoomRecord>;
+    /// Update room configuration in database based on given params.
+    fn update(&self, room: UpdateRoomRecordParams) -> CommonResult<()>;
+    /// Mark the room as closed.
+    fn close(&self, target_uuid: &str) -> CommonResult<()>;
+    /// Create and return room ban record in database based on given params.
+    fn create_ban(&self, ban: NewRoomBanRecordParams) -> CommonResult<RoomBanRecord>;
+    /// Create game record and set it as the current game of its room.
+    fn create_game(&self, game: NewGameRecordParams) -> CommonResult<GameRecord>;
+    /// Create game hand records in database.
+    fn create_game_hands(&self, hands: Vec<NewGameHandRecordParams>) -> CommonResult<()>;
+    /// Store the final estimate of the game and clear it from the current
+    /// game of its room.
+    fn finalize_game(&self, game: FinalizeGameRecordParams) -> CommonResult<()>;
+    /// Create and return game record of a story in the backlog of its room.
+    fn create_story(&self, story: NewStoryRecordParams) -> CommonResult<GameRecord>;
+    /// Update the backlog sequences of the given stories.
+    fn update_story_sequences(&self, sequences: Vec<(UuidType, i32)>) -> CommonResult<()>;
+    /// Delete story which is not started yet.
+    fn delete_story(&self, target_uuid: &str) -> CommonResult<()>;
+    /// Remove story from the backlog and set it as the current game of its
+    /// room.
+    fn start_story(&self, target_uuid: &str, target_room_uuid: &str) -> CommonResult<()>;
+    /// Find the external ids of all games of the room, including those
+    /// already played.
+    fn find_game_external_ids(&self, target_room_uuid: &str) -> CommonResult<Vec<String>>;
+    /// Find the played games matching the params, ordered by when they are
+    /// started. Stories still in the backlog are excluded.
+    fn find_game_results(
+        &self,
+        params: FindGameResultsParams,
+    ) -> CommonResult<Vec<GameResultRecord>>;
+    /// Record that the user has joined the room. Joining again updates the
+    /// time joined.
+    fn create_player(&self, player: NewRoomPlayerRecordParams) -> CommonResult<()>;
+    /// Find room record by Uuid.
+    fn find_by_uuid(&self, target_uuid: &str) -> CommonResult<Option<RoomRecord>>;
+    /// List room records owned or joined by the user, most recently created
+    /// first.
+    fn list_by_member(
+        &self,
+        target_user_uuid: &str,
+        offset: i64,
+        limit: i64,
+    ) -> CommonResult<Vec<RoomRecord>>;
+    /// Whether the user has joined the room as player.
+    fn is_player(&self, target_room_uuid: &str, target_user_uuid: &str) -> CommonResult<bool>;
+    /// List Uuids of the users banned from the room.
+    fn list_bans(&self, target_room_uuid: &str) -> CommonResult<Vec<UuidType>>;
+    /// List played games of the room in the order they are started.
+    fn list_games(
+        &self,
+        target_room_uuid: &str,
+        offset: i64,
+        limit: i64,
+    ) -> CommonResult<Vec<GameRecord>>;
+    /// Find game record by Uuid.
+    fn find_game(&self, target_uuid: &str) -> CommonResult<Option<GameRecord>>;
+    /// List hands of the game, in the order they are played.
+    fn list_game_hands(&self, target_game_uuid: &str) -> CommonResult<Vec<PlayedHandRecord>>;
 }
 
 #[derive(Clone)]
@@ -62,6 +335,9 @@ impl RoomORM for RoomModel {
             owner_uuid: room.owner_uuid,
             created_at: now,
             last_updated_at: now,
+            options: room.options,
+            join_code: Some(room.join_code),
+            closed_at: None,
         };
         diesel::insert_into(rooms::table)
             .values(&new_room)
@@ -75,6 +351,632 @@ impl RoomORM for RoomModel {
 
         Ok(new_room)
     }
+
+    fn update(&self, room: UpdateRoomRecordParams) -> CommonResult<()> {
+        use crate::schema::rooms::dsl::{card_set, last_updated_at, options, passphrase, rooms};
+
+        let pool = self.pool.get().context(|| {
+            (
+                ErrorKind::ConnectionPoolError,
+                "Error when getting DB connection from pool",
+            )
+        })?;
+        let conn = &pool;
+
+        diesel::update(rooms.find(room.uuid))
+            .set((
+                passphrase.eq(room.passphrase),
+                card_set.eq(room.card_set),
+                options.eq(room.options),
+                last_updated_at.eq(SystemTime::now()),
+            ))
+            .execute(conn)
+            .context(|| {
+                (
+                    ErrorKind::UpdateError,
+                    "Error when updating room record in DB",
+                )
+            })?;
+
+        Ok(())
+    }
+
+    fn close(&self, target_uuid: &str) -> CommonResult<()> {
+        let pool = self.pool.get().context(|| {
+            (
+                ErrorKind::ConnectionPoolError,
+                "Error when getting DB connection from pool",
+            )
+        })?;
+        let conn = &pool;
+
+        let now = SystemTime::now();
+        diesel::update(rooms::table.find(target_uuid))
+            .set((rooms::closed_at.eq(now), rooms::last_updated_at.eq(now)))
+            .execute(conn)
+            .context(|| {
+                (
+                    ErrorKind::UpdateError,
+                    "Error when closing room record in DB",
+                )
+            })?;
+
+        Ok(())
+    }
+
+    fn create_ban(&self, ban: NewRoomBanRecordParams) -> CommonResult<RoomBanRecord> {
+        let pool = self.pool.get().context(|| {
+            (
+                ErrorKind::ConnectionPoolError,
+                "Error when getting DB connection from pool",
+            )
+        })?;
+        let conn = &pool;
+
+        let new_ban = RoomBanRecord {
+            room_uuid: ban.room_uuid,
+            user_uuid: ban.user_uuid,
+            banned_at: SystemTime::now(),
+        };
+        diesel::insert_into(room_bans::table)
+            .values(&new_ban)
+            .on_conflict_do_nothing()
+            .execute(conn)
+            .context(|| {
+                (
+                    ErrorKind::InsertionError,
+                    "Error when inserting room ban record into DB",
+                )
+            })?;
+
+        Ok(new_ban)
+    }
+
+    fn create_game(&self, game: NewGameRecordParams) -> CommonResult<GameRecord> {
+        use crate::schema::rooms::dsl::{current_game_uuid, rooms};
+
+        let pool = self.pool.get().context(|| {
+            (
+                ErrorKind::ConnectionPoolError,
+                "Error when getting DB connection from pool",
+            )
+        })?;
+        let conn = &pool;
+
+        let now = SystemTime::now();
+        let room_uuid = game.room_uuid;
+        let new_game = GameRecord {
+            uuid: game.uuid,
+            room_uuid: Some(room_uuid.clone()),
+            sequence: None,
+            title: game.title,
+            description: game.description,
+            created_at: now,
+            last_updated_at: now,
+            final_estimate: None,
+            final_estimate_reason: None,
+            link: None,
+            external_id: None,
+            pre_estimate: None,
+            started_at: Some(now),
+        };
+        conn.transaction::<_, diesel::result::Error, _>(|| {
+            diesel::insert_into(games::table)
+                .values(&new_game)
+                .execute(conn)?;
+            diesel::update(rooms.find(room_uuid))
+                .set(current_game_uuid.eq(&new_game.uuid))
+                .execute(conn)
+        })
+        .context(|| {
+            (
+                ErrorKind::InsertionError,
+                "Error when inserting game record into DB",
+            )
+        })?;
+
+        Ok(new_game)
+    }
+
+    fn create_game_hands(&self, hands: Vec<NewGameHandRecordParams>) -> CommonResult<()> {
+        let pool = self.pool.get().context(|| {
+            (
+                ErrorKind::ConnectionPoolError,
+                "Error when getting DB connection from pool",
+            )
+        })?;
+        let conn = &pool;
+
+        diesel::insert_into(game_hands::table)
+            .values(&hands)
+            .execute(conn)
+            .context(|| {
+                (
+                    ErrorKind::InsertionError,
+                    "Error when inserting game hand records into DB",
+                )
+            })?;
+
+        Ok(())
+    }
+
+    fn finalize_game(&self, game: FinalizeGameRecordParams) -> CommonResult<()> {
+        use crate::schema::games::dsl::{
+            final_estimate, final_estimate_reason, games, last_updated_at,
+        };
+        use crate::schema::rooms::dsl::{current_game_uuid, rooms};
+
+        let pool = self.pool.get().context(|| {
+            (
+                ErrorKind::ConnectionPoolError,
+                "Error when getting DB connection from pool",
+            )
+        })?;
+        let conn = &pool;
+
+        conn.transaction::<_, diesel::result::Error, _>(|| {
+            diesel::update(games.find(game.uuid))
+                .set((
+                    final_estimate.eq(game.final_estimate),
+                    final_estimate_reason.eq(game.final_estimate_reason),
+                    last_updated_at.eq(SystemTime::now()),
+                ))
+                .execute(conn)?;
+            diesel::update(rooms.find(game.room_uuid))
+                .set(current_game_uuid.eq(None::<String>))
+                .execute(conn)
+        })
+        .context(|| {
+            (
+                ErrorKind::UpdateError,
+                "Error when updating game record in DB",
+            )
+        })?;
+
+        Ok(())
+    }
+
+    fn create_story(&self, story: NewStoryRecordParams) -> CommonResult<GameRecord> {
+        let pool = self.pool.get().context(|| {
+            (
+                ErrorKind::ConnectionPoolError,
+                "Error when getting DB connection from pool",
+            )
+        })?;
+        let conn = &pool;
+
+        let now = SystemTime::now();
+        let new_story = GameRecord {
+            uuid: story.uuid,
+            room_uuid: Some(story.room_uuid),
+            sequence: Some(story.sequence),
+            title: story.title,
+            description: story.description,
+            created_at: now,
+            last_updated_at: now,
+            final_estimate: None,
+            final_estimate_reason: None,
+            link: story.link,
+            external_id: story.external_id,
+            pre_estimate: story.pre_estimate,
+            started_at: None,
+        };
+        diesel::insert_into(games::table)
+            .values(&new_story)
+            .execute(conn)
+            .context(|| {
+                (
+                    ErrorKind::InsertionError,
+                    "Error when inserting story record into DB",
+                )
+            })?;
+
+        Ok(new_story)
+    }
+
+    fn update_story_sequences(&self, sequences: Vec<(UuidType, i32)>) -> CommonResult<()> {
+        use crate::schema::games::dsl::{games, last_updated_at, sequence};
+
+        let pool = self.pool.get().context(|| {
+            (
+                ErrorKind::ConnectionPoolError,
+                "Error when getting DB connection from pool",
+            )
+        })?;
+        let conn = &pool;
+
+        let now = SystemTime::now();
+        conn.transaction::<_, diesel::result::Error, _>(|| {
+            for (target_uuid, target_sequence) in sequences.into_iter() {
+                diesel::update(games.find(target_uuid))
+                    .set((sequence.eq(target_sequence), last_updated_at.eq(now)))
+                    .execute(conn)?;
+            }
+            Ok(())
+        })
+        .context(|| {
+            (
+                ErrorKind::UpdateError,
+                "Error when updating story sequences in DB",
+            )
+        })?;
+
+        Ok(())
+    }
+
+    fn delete_story(&self, target_uuid: &str) -> CommonResult<()> {
+        use crate::schema::games::dsl::{games, sequence};
+
+        let pool = self.pool.get().context(|| {
+            (
+                ErrorKind::ConnectionPoolError,
+                "Error when getting DB connection from pool",
+            )
+        })?;
+        let conn = &pool;
+
+        diesel::delete(games.find(target_uuid).filter(sequence.is_not_null()))
+            .execute(conn)
+            .context(|| {
+                (
+                    ErrorKind::DeletionError,
+                    "Error when deleting story record from DB",
+                )
+            })?;
+
+        Ok(())
+    }
+
+    fn start_story(&self, target_uuid: &str, target_room_uuid: &str) -> CommonResult<()> {
+        use crate::schema::games::dsl::{games, last_updated_at, sequence, started_at};
+        use crate::schema::rooms::dsl::{current_game_uuid, rooms};
+
+        let pool = self.pool.get().context(|| {
+            (
+                ErrorKind::ConnectionPoolError,
+                "Error when getting DB connection from pool",
+            )
+        })?;
+        let conn = &pool;
+
+        let now = SystemTime::now();
+        conn.transaction::<_, diesel::result::Error, _>(|| {
+            diesel::update(games.find(target_uuid))
+                .set((
+                    sequence.eq(None::<i32>),
+                    started_at.eq(now),
+                    last_updated_at.eq(now),
+                ))
+                .execute(conn)?;
+            diesel::update(rooms.find(target_room_uuid))
+                .set(current_game_uuid.eq(target_uuid))
+                .execute(conn)
+        })
+        .context(|| (ErrorKind::UpdateError, "Error when starting story in DB"))?;
+
+        Ok(())
+    }
+
+    fn find_game_external_ids(&self, target_room_uuid: &str) -> CommonResult<Vec<String>> {
+        use crate::schema::games::dsl::{external_id, games, room_uuid};
+
+        let pool = self.pool.get().context(|| {
+            (
+                ErrorKind::ConnectionPoolError,
+                "Error when getting DB connection from pool",
+            )
+        })?;
+        let conn = &pool;
+
+        let external_ids = games
+            .filter(room_uuid.eq(target_room_uuid))
+            .filter(external_id.is_not_null())
+            .select(external_id)
+            .load::<Option<String>>(conn)
+            .context(|| {
+                (
+                    ErrorKind::QueryError,
+                    "Error when querying game external ids from DB",
+                )
+            })?;
+
+        Ok(external_ids.into_iter().flatten().collect())
+    }
+
+    fn find_game_results(
+        &self,
+        params: FindGameResultsParams,
+    ) -> CommonResult<Vec<GameResultRecord>> {
+        let pool = self.pool.get().context(|| {
+            (
+                ErrorKind::ConnectionPoolError,
+                "Error when getting DB connection from pool",
+            )
+        })?;
+        let conn = &pool;
+
+        let mut room_query = rooms::table
+            .select(ROOM_COLUMNS)
+            .filter(rooms::owner_uuid.eq(&params.owner_uuid))
+            .into_boxed();
+        if let Some(target_room_uuid) = params.room_uuid.as_ref() {
+            room_query = room_query.filter(rooms::uuid.eq(target_room_uuid));
+        }
+        let room_records = room_query.load::<RoomRecord>(conn).context(|| {
+            (
+                ErrorKind::QueryError,
+                "Error when querying room records from DB",
+            )
+        })?;
+        let room_uuids: Vec<&UuidType> = room_records.iter().map(|room| &room.uuid).collect();
+
+        let mut game_query = games::table
+            .filter(games::room_uuid.eq_any(room_uuids))
+            .filter(games::sequence.is_null())
+            .into_boxed();
+        if let Some(from) = params.from {
+            game_query = game_query.filter(games::started_at.ge(from));
+        }
+        if let Some(to) = params.to {
+            game_query = game_query.filter(games::started_at.lt(to));
+        }
+        let game_records = game_query
+            .order(games::started_at.asc())
+            .load::<GameRecord>(conn)
+            .context(|| {
+                (
+                    ErrorKind::QueryError,
+                    "Error when querying game records from DB",
+                )
+            })?;
+        let game_uuids: Vec<&UuidType> = game_records.iter().map(|game| &game.uuid).collect();
+
+        let hand_records = load_played_hands(conn, game_uuids)?;
+
+        Ok(game_records
+            .into_iter()
+            .filter_map(|game| {
+                let room = room_records
+                    .iter()
+                    .find(|room| game.room_uuid.as_ref() == Some(&room.uuid))?
+                    .clone();
+                let hands = hand_records
+                    .iter()
+                    .filter(|played_hand| played_hand.hand.game_uuid.as_ref() == Some(&game.uuid))
+                    .cloned()
+                    .collect();
+
+                Some(GameResultRecord { room, game, hands })
+            })
+            .collect())
+    }
+
+    fn create_player(&self, player: NewRoomPlayerRecordParams) -> CommonResult<()> {
+        let pool = self.pool.get().context(|| {
+            (
+                ErrorKind::ConnectionPoolError,
+                "Error when getting DB connection from pool",
+            )
+        })?;
+        let conn = &pool;
+
+        let now = SystemTime::now();
+        let new_player = RoomPlayerRecord {
+            room_uuid: player.room_uuid,
+            player_uuid: player.player_uuid,
+            joined_at: now,
+            left_at: None,
+        };
+        diesel::insert_into(room_players::table)
+            .values(&new_player)
+            .on_conflict((room_players::room_uuid, room_players::player_uuid))
+            .do_update()
+            .set((
+                room_players::joined_at.eq(now),
+                room_players::left_at.eq(None::<SystemTime>),
+            ))
+            .execute(conn)
+            .context(|| {
+                (
+                    ErrorKind::InsertionError,
+                    "Error when inserting room player record into DB",
+                )
+            })?;
+
+        Ok(())
+    }
+
+    fn find_by_uuid(&self, target_uuid: &str) -> CommonResult<Option<RoomRecord>> {
+        let pool = self.pool.get().context(|| {
+            (
+                ErrorKind::ConnectionPoolError,
+                "Error when getting DB connection from pool",
+            )
+        })?;
+        let conn = &pool;
+
+        rooms::table
+            .select(ROOM_COLUMNS)
+            .find(target_uuid)
+            .first::<RoomRecord>(conn)
+            .optional()
+            .context(|| {
+                (
+                    ErrorKind::QueryError,
+                    "Error when querying room record from DB",
+                )
+            })
+    }
+
+    fn list_by_member(
+        &self,
+        target_user_uuid: &str,
+        offset: i64,
+        limit: i64,
+    ) -> CommonResult<Vec<RoomRecord>> {
+        let pool = self.pool.get().context(|| {
+            (
+                ErrorKind::ConnectionPoolError,
+                "Error when getting DB connection from pool",
+            )
+        })?;
+        let conn = &pool;
+
+        let joined_room_uuids = room_players::table
+            .filter(room_players::player_uuid.eq(target_user_uuid))
+            .select(room_players::room_uuid);
+        rooms::table
+            .select(ROOM_COLUMNS)
+            .filter(
+                rooms::owner_uuid
+                    .eq(target_user_uuid)
+                    .or(rooms::uuid.eq_any(joined_room_uuids)),
+            )
+            .order(rooms::created_at.desc())
+            .offset(offset)
+            .limit(limit)
+            .load::<RoomRecord>(conn)
+            .context(|| {
+                (
+                    ErrorKind::QueryError,
+                    "Error when querying room records from DB",
+                )
+            })
+    }
+
+    fn is_player(&self, target_room_uuid: &str, target_user_uuid: &str) -> CommonResult<bool> {
+        let pool = self.pool.get().context(|| {
+            (
+                ErrorKind::ConnectionPoolError,
+                "Error when getting DB connection from pool",
+            )
+        })?;
+        let conn = &pool;
+
+        diesel::select(diesel::dsl::exists(
+            room_players::table
+                .filter(room_players::room_uuid.eq(target_room_uuid))
+                .filter(room_players::player_uuid.eq(target_user_uuid)),
+        ))
+        .get_result(conn)
+        .context(|| {
+            (
+                ErrorKind::QueryError,
+                "Error when querying room player record from DB",
+            )
+        })
+    }
+
+    fn list_bans(&self, target_room_uuid: &str) -> CommonResult<Vec<UuidType>> {
+        let pool = self.pool.get().context(|| {
+            (
+                ErrorKind::ConnectionPoolError,
+                "Error when getting DB connection from pool",
+            )
+        })?;
+        let conn = &pool;
+
+        room_bans::table
+            .select(room_bans::user_uuid)
+            .filter(room_bans::room_uuid.eq(target_room_uuid))
+            .load::<UuidType>(conn)
+            .context(|| {
+                (
+                    ErrorKind::QueryError,
+                    "Error when querying room ban records from DB",
+                )
+            })
+    }
+
+    fn list_games(
+        &self,
+        target_room_uuid: &str,
+        offset: i64,
+        limit: i64,
+    ) -> CommonResult<Vec<GameRecord>> {
+        let pool = self.pool.get().context(|| {
+            (
+                ErrorKind::ConnectionPoolError,
+                "Error when getting DB connection from pool",
+            )
+        })?;
+        let conn = &pool;
+
+        games::table
+            .filter(games::room_uuid.eq(target_room_uuid))
+            .filter(games::sequence.is_null())
+            .order(games::started_at.asc())
+            .offset(offset)
+            .limit(limit)
+            .load::<GameRecord>(conn)
+            .context(|| {
+                (
+                    ErrorKind::QueryError,
+                    "Error when querying game records from DB",
+                )
+            })
+    }
+
+    fn find_game(&self, target_uuid: &str) -> CommonResult<Option<GameRecord>> {
+        let pool = self.pool.get().context(|| {
+            (
+                ErrorKind::ConnectionPoolError,
+                "Error when getting DB connection from pool",
+            )
+        })?;
+        let conn = &pool;
+
+        games::table
+            .find(target_uuid)
+            .first::<GameRecord>(conn)
+            .optional()
+            .context(|| {
+                (
+                    ErrorKind::QueryError,
+                    "Error when querying game record from DB",
+                )
+            })
+    }
+
+    fn list_game_hands(&self, target_game_uuid: &str) -> CommonResult<Vec<PlayedHandRecord>> {
+        let pool = self.pool.get().context(|| {
+            (
+                ErrorKind::ConnectionPoolError,
+                "Error when getting DB connection from pool",
+            )
+        })?;
+        let conn = &pool;
+
+        load_played_hands(conn, vec![target_game_uuid])
+    }
+}
+
+/// Load the hands of the games together with the names of their players.
+fn load_played_hands<G>(
+    conn: &PgConnection,
+    game_uuids: Vec<G>,
+) -> CommonResult<Vec<PlayedHandRecord>>
+where
+    G: AsRef<str>,
+{
+    let game_uuids: Vec<&str> = game_uuids.iter().map(|uuid| uuid.as_ref()).collect();
+    let hand_records = game_hands::table
+        .left_join(users::table)
+        .filter(game_hands::game_uuid.eq_any(game_uuids))
+        .order(game_hands::id.asc())
+        .select((game_hands::all_columns, users::name.nullable()))
+        .load::<(GameHandRecord, Option<String>)>(conn)
+        .context(|| {
+            (
+                ErrorKind::QueryError,
+                "Error when querying game hand records from DB",
+            )
+        })?;
+
+    Ok(hand_records
+        .into_iter()
+        .map(|(hand, user_name)| PlayedHandRecord { hand, user_name })
+        .collect())
 }
 
 impl RoomModel {
@@ -82,3 +984,217 @@ impl RoomModel {
         RoomModel { pool }
     }
 }
+
+#[derive(Insertable, Queryable, Associations, Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[belongs_to(UserRecord, foreign_key = "owner_uuid")]
+#[table_name = "decks"]
+pub struct DeckRecord {
+    pub uuid: UuidType,
+    pub owner_uuid: UuidType,
+    pub name: String,
+    pub card_set: Vec<CardLabel>,
+    pub created_at: SystemTime,
+    pub last_updated_at: SystemTime,
+    /// Team sharing the deck with its members, in addition to the owner.
+    pub team_uuid: Option<UuidType>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct NewDeckRecordParams {
+    pub owner_uuid: UuidType,
+    pub team_uuid: Option<UuidType>,
+    pub name: String,
+    pub card_set: Vec<CardLabel>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct UpdateDeckRecordParams {
+    pub uuid: UuidType,
+    pub name: String,
+    pub card_set: Vec<CardLabel>,
+}
+
+#[cfg_attr(test, automock)]
+pub trait DeckORM: Send + Sync {
+    /// Create and return deck record in database based on given params.
+    fn create(&self, deck: NewDeckRecordParams) -> CommonResult<DeckRecord>;
+    /// Find deck record by Uuid.
+    fn find_by_uuid(&self, target_uuid: &str) -> CommonResult<Option<DeckRecord>>;
+    /// List deck records owned by the user or shared with the teams of the
+    /// user, ordered by name.
+    fn list_by_member(&self, target_user_uuid: &str) -> CommonResult<Vec<DeckRecord>>;
+    /// Update and return deck record in database based on given params.
+    fn update(&self, deck: UpdateDeckRecordParams) -> CommonResult<DeckRecord>;
+    /// Delete deck record by Uuid.
+    fn delete(&self, target_uuid: &str) -> CommonResult<()>;
+    /// Check whether the user is a member of the team.
+    fn is_team_member(&self, target_team_uuid: &str, target_user_uuid: &str) -> CommonResult<bool>;
+}
+
+#[derive(Clone)]
+pub struct DeckModel {
+    pool: ConnectionPool,
+}
+
+impl DeckORM for DeckModel {
+    fn create(&self, deck: NewDeckRecordParams) -> CommonResult<DeckRecord> {
+        let pool = self.pool.get().context(|| {
+            (
+                ErrorKind::ConnectionPoolError,
+                "Error when getting DB connection from pool",
+            )
+        })?;
+        let conn = &pool;
+
+        let now = SystemTime::now();
+        let new_deck = DeckRecord {
+            uuid: Uuid::new_v4().to_string(),
+            owner_uuid: deck.owner_uuid,
+            name: deck.name,
+            card_set: deck.card_set,
+            created_at: now,
+            last_updated_at: now,
+            team_uuid: deck.team_uuid,
+        };
+        diesel::insert_into(decks::table)
+            .values(&new_deck)
+            .execute(conn)
+            .context(|| {
+                (
+                    ErrorKind::InsertionError,
+                    "Error when inserting deck record into DB",
+                )
+            })?;
+
+        Ok(new_deck)
+    }
+
+    fn find_by_uuid(&self, target_uuid: &str) -> CommonResult<Option<DeckRecord>> {
+        use crate::schema::decks::dsl::decks;
+
+        let pool = self.pool.get().context(|| {
+            (
+                ErrorKind::ConnectionPoolError,
+                "Error when getting DB connection from pool",
+            )
+        })?;
+        let conn = &pool;
+
+        decks
+            .find(target_uuid)
+            .first::<DeckRecord>(conn)
+            .optional()
+            .context(|| {
+                (
+                    ErrorKind::QueryError,
+                    "Error when finding deck record with uuid in DB",
+                )
+            })
+    }
+
+    fn list_by_member(&self, target_user_uuid: &str) -> CommonResult<Vec<DeckRecord>> {
+        let pool = self.pool.get().context(|| {
+            (
+                ErrorKind::ConnectionPoolError,
+                "Error when getting DB connection from pool",
+            )
+        })?;
+        let conn = &pool;
+
+        let team_uuids = team_members::table
+            .filter(team_members::user_uuid.eq(target_user_uuid))
+            .select(team_members::team_uuid.nullable());
+        decks::table
+            .filter(
+                decks::owner_uuid
+                    .eq(target_user_uuid)
+                    .or(decks::team_uuid.eq_any(team_uuids)),
+            )
+            .order(decks::name.asc())
+            .load::<DeckRecord>(conn)
+            .context(|| {
+                (
+                    ErrorKind::QueryError,
+                    "Error when listing deck records of user in DB",
+                )
+            })
+    }
+
+    fn update(&self, deck: UpdateDeckRecordParams) -> CommonResult<DeckRecord> {
+        use crate::schema::decks::dsl::{card_set, decks, last_updated_at, name};
+
+        let pool = self.pool.get().context(|| {
+            (
+                ErrorKind::ConnectionPoolError,
+                "Error when getting DB connection from pool",
+            )
+        })?;
+        let conn = &pool;
+
+        diesel::update(decks.find(deck.uuid))
+            .set((
+                name.eq(deck.name),
+                card_set.eq(deck.card_set),
+                last_updated_at.eq(SystemTime::now()),
+            ))
+            .get_result::<DeckRecord>(conn)
+            .context(|| {
+                (
+                    ErrorKind::UpdateError,
+                    "Error when updating deck record in DB",
+                )
+            })
+    }
+
+    fn delete(&self, target_uuid: &str) -> CommonResult<()> {
+        use crate::schema::decks::dsl::decks;
+
+        let pool = self.pool.get().context(|| {
+            (
+                ErrorKind::ConnectionPoolError,
+                "Error when getting DB connection from pool",
+            )
+        })?;
+        let conn = &pool;
+
+        diesel::delete(decks.find(target_uuid))
+            .execute(conn)
+            .context(|| {
+                (
+                    ErrorKind::DeletionError,
+                    "Error when deleting deck record from DB",
+                )
+            })?;
+
+        Ok(())
+    }
+
+    fn is_team_member(&self, target_team_uuid: &str, target_user_uuid: &str) -> CommonResult<bool> {
+        let pool = self.pool.get().context(|| {
+            (
+                ErrorKind::ConnectionPoolError,
+                "Error when getting DB connection from pool",
+            )
+        })?;
+        let conn = &pool;
+
+        diesel::select(diesel::dsl::exists(
+            team_members::table
+                .filter(team_members::team_uuid.eq(target_team_uuid))
+                .filter(team_members::user_uuid.eq(target_user_uuid)),
+        ))
+        .get_result(conn)
+        .context(|| {
+            (
+                ErrorKind::QueryError,
+                "Error when querying team member record from DB",
+            )
+        })
+    }
+}
+
+impl DeckModel {
+    pub fn new(pool: ConnectionPool) -> DeckModel {
+        DeckModel { pool }
+    }
+}
@@ -2,7 +2,11 @@ use actix::prelude::*;
 
 use crate::client::ClientId;
 use crate::common::error::Result as CommonResult;
+use crate::common::message::request::UpdateRoomConfigParams;
+use crate::common::message::response::RoomConfig;
 use crate::common::message::RequestMessage;
+use crate::common::model::Uuid;
+use crate::poker::backlog::{StoryImportReport, StoryImportRow};
 
 #[derive(Message)]
 #[rtype(result = "CommonResult<()>")]
@@ -10,3 +14,51 @@ pub struct ClientRequestMessage {
     pub client_id: ClientId,
     pub req: RequestMessage,
 }
+
+/// Import stories to the backlog on behalf of the user.
+#[derive(Message)]
+#[rtype(result = "CommonResult<StoryImportReport>")]
+pub struct ImportStoriesMessage {
+    pub user_uuid: Uuid,
+    pub rows: Vec<CommonResult<StoryImportRow>>,
+}
+
+/// Update room configuration on behalf of the user.
+#[derive(Message)]
+#[rtype(result = "CommonResult<RoomConfig>")]
+pub struct UpdateRoomConfigMessage {
+    pub user_uuid: Uuid,
+    pub params: UpdateRoomConfigParams,
+}
+
+/// Close the room on behalf of the user. The room actor stops once the room
+/// is closed.
+#[derive(Message)]
+#[rtype(result = "CommonResult<()>")]
+pub struct CloseRoomMessage {
+    pub user_uuid: Uuid,
+}
+
+/// Notify the room that the websocket client has disconnected without
+/// resuming its session in time.
+#[derive(Message)]
+pub struct LeaveRoomMessage {
+    pub client_id: ClientId,
+    pub player_uuid: Uuid,
+}
+
+/// Keep the seat of the websocket client which has disconnected, until it
+/// resumes the session or leaves the room after the resume grace window.
+#[derive(Message)]
+pub struct DetachClientMessage {
+    pub client_id: ClientId,
+}
+
+/// Attach the websocket client which resumes the session back to its seat,
+/// and replay the room events after the last one it has received.
+#[derive(Message)]
+#[rtype(result = "CommonResult<()>")]
+pub struct ResumeClientMessage {
+    pub client_id: ClientId,
+    pub last_seq: Option<u64>,
+}
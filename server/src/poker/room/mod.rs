@@ -1,5 +1,8 @@
-use std::collections::HashSet;
+use std::cmp;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::marker::PhantomData;
+use std::slice;
+use std::time::{Duration, Instant, SystemTime};
 
 use actix::prelude::*;
 use log::{error, warn};
@@ -7,18 +10,45 @@ use log::{error, warn};
 use crate::client::channel::ClientChannel;
 use crate::client::store::{ClientStore, SharedClientStore};
 use crate::client::ClientId;
+use crate::common::clock::{Clock, SystemClock};
 use crate::common::error::{ContextExt, Error, ErrorKind, Result as CommonResult};
-use crate::common::message::request::JoinRoomParams;
-use crate::common::message::response::CreatedRoom;
+use crate::common::message::request::{
+    AddStoryParams, FinalizeGameParams, NextStoryParams, PlayCardParams, RemoveStoryParams,
+    ReorderBacklogParams, ResetGameParams, StartGameParams, UpdateRoomConfigParams,
+};
+use crate::common::message::response::{
+    CountdownTick, CreatedRoom, FinalizedGame, GameSnapshot, KickedFromRoom, PlayedHand,
+    ResetRound, ResponseFrame, RevealedCards, RoomConfig, RoomSnapshot, StartedGame,
+};
 use crate::common::message::{RequestMessage, ResponseMessage};
 use crate::common::model::Uuid;
-use crate::poker::game::Game;
-use crate::poker::model::{Card, NewRoomRecordParams, RoomORM};
+use crate::poker::backlog::{
+    self, StoryDraft, StoryImportError, StoryImportReport, StoryImportRow,
+};
+use crate::poker::card::{self, Card, CardStats};
+use crate::poker::deck;
+use crate::poker::game::{Game, Story};
+use crate::poker::model::{
+    CardLabel, FinalizeGameRecordParams, NewGameHandRecordParams, NewGameRecordParams,
+    NewRoomBanRecordParams, NewRoomPlayerRecordParams, NewRoomRecordParams, NewStoryRecordParams,
+    RoomORM, RoomOptions, RoomRecord, UpdateRoomRecordParams,
+};
 
-use message::ClientRequestMessage;
+use message::{
+    ClientRequestMessage, CloseRoomMessage, DetachClientMessage, ImportStoriesMessage,
+    LeaveRoomMessage, ResumeClientMessage, UpdateRoomConfigMessage,
+};
 
 pub mod message;
 
+pub const MAX_COUNTDOWN_SECS: u64 = 60 * 60;
+pub const MAX_BACKLOG_LEN: usize = 100;
+/// Number of the latest room events kept to be replayed to the players who
+/// resume their sessions.
+pub const MAX_BUFFERED_EVENTS: usize = 256;
+
+const COUNTDOWN_TICK_INTERVAL: Duration = Duration::from_secs(5);
+
 pub struct Room<R, S, T>
 where
     R: RoomORM,
@@ -28,13 +58,29 @@ where
     room_id: Option<Uuid>,
     passphrase: Option<String>,
     card_set: Vec<Card>,
-    owner_client_id: ClientId,
-    players: HashSet<ClientId>,
+    options: RoomOptions,
+    join_code: String,
+    owner_uuid: Uuid,
+    /// Clients attached to the room by the Uuids of their players. A player
+    /// has one seat and one hand however many tabs or devices it joins from.
+    players: HashMap<Uuid, HashSet<ClientId>>,
+    spectators: HashSet<Uuid>,
+    /// Clients which have disconnected but may still resume their sessions.
+    /// Their seats and hands are kept, but nothing is sent to them.
+    detached_client_ids: HashSet<ClientId>,
+    banned_player_uuids: HashSet<Uuid>,
     current_game: Option<Game>,
+    /// Stories to be estimated after the current game, in order.
+    backlog: Vec<Story>,
+    /// Sequence number of the latest event broadcast in the room.
+    event_seq: u64,
+    /// Latest events of the room, in order of their sequence numbers.
+    events: VecDeque<ResponseFrame>,
 
     room_model: R,
     client_store: SharedClientStore<S, T>,
     client_store_channel_type: PhantomData<T>,
+    clock: Box<dyn Clock>,
 }
 
 impl<R, S, T> Actor for Room<R, S, T>
@@ -55,24 +101,148 @@ where
     type Result = CommonResult<()>;
 
     fn handle(&mut self, msg: ClientRequestMessage, ctx: &mut Context<Self>) -> Self::Result {
-        // TODO: Check for client existence
-        let channel = match self.client_store.get_readable().get(&msg.client_id) {
-            Some(channel) => channel,
-            None => {
-                error!(
-                    "Received request from deleted websocket client {}",
-                    msg.client_id
-                );
-                return Err(Error::from(ErrorKind::MissingClientError));
-            }
+        if !self
+            .client_store
+            .get_readable()
+            .contains_key(&msg.client_id)
+        {
+            error!(
+                "Received request from deleted websocket client {}",
+                msg.client_id
+            );
+            return Err(Error::from(ErrorKind::MissingClientError));
+        }
+
+        let is_timeboxed_game = match msg.req {
+            RequestMessage::StartGame(ref params) => params.countdown_secs.is_some(),
+            RequestMessage::ResetGame(ref params) => params.countdown_secs.is_some(),
+            RequestMessage::NextStory(ref params) => params.countdown_secs.is_some(),
+            _ => false,
         };
 
         self.handle_request_message(msg)?;
 
+        if is_timeboxed_game {
+            self.schedule_countdown_tick(ctx);
+        }
+
+        Ok(())
+    }
+}
+
+impl<R, S, T> Handler<LeaveRoomMessage> for Room<R, S, T>
+where
+    R: RoomORM + 'static,
+    S: ClientStore<T> + 'static,
+    T: ClientChannel + 'static,
+{
+    type Result = ();
+
+    fn handle(&mut self, msg: LeaveRoomMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        self.leave(msg.client_id, msg.player_uuid);
+    }
+}
+
+impl<R, S, T> Handler<DetachClientMessage> for Room<R, S, T>
+where
+    R: RoomORM + 'static,
+    S: ClientStore<T> + 'static,
+    T: ClientChannel + 'static,
+{
+    type Result = ();
+
+    fn handle(&mut self, msg: DetachClientMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        self.detach(msg.client_id);
+    }
+}
+
+impl<R, S, T> Handler<ResumeClientMessage> for Room<R, S, T>
+where
+    R: RoomORM + 'static,
+    S: ClientStore<T> + 'static,
+    T: ClientChannel + 'static,
+{
+    type Result = CommonResult<()>;
+
+    fn handle(&mut self, msg: ResumeClientMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        self.resume(msg.client_id, msg.last_seq)
+    }
+}
+
+impl<R, S, T> Handler<ImportStoriesMessage> for Room<R, S, T>
+where
+    R: RoomORM + 'static,
+    S: ClientStore<T> + 'static,
+    T: ClientChannel + 'static,
+{
+    type Result = CommonResult<StoryImportReport>;
+
+    fn handle(&mut self, msg: ImportStoriesMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        self.import_stories(&msg.user_uuid, msg.rows)
+    }
+}
+
+impl<R, S, T> Handler<UpdateRoomConfigMessage> for Room<R, S, T>
+where
+    R: RoomORM + 'static,
+    S: ClientStore<T> + 'static,
+    T: ClientChannel + 'static,
+{
+    type Result = CommonResult<RoomConfig>;
+
+    fn handle(&mut self, msg: UpdateRoomConfigMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        self.update_config_as_user(&msg.user_uuid, msg.params)
+    }
+}
+
+impl<R, S, T> Handler<CloseRoomMessage> for Room<R, S, T>
+where
+    R: RoomORM + 'static,
+    S: ClientStore<T> + 'static,
+    T: ClientChannel + 'static,
+{
+    type Result = CommonResult<()>;
+
+    fn handle(&mut self, msg: CloseRoomMessage, ctx: &mut Context<Self>) -> Self::Result {
+        self.close(&msg.user_uuid)?;
+        ctx.stop();
+
         Ok(())
     }
 }
 
+impl<R, S, T> Room<R, S, T>
+where
+    R: RoomORM + 'static,
+    S: ClientStore<T> + 'static,
+    T: ClientChannel + 'static,
+{
+    /// Schedule the next countdown tick of the current game. Ticks keep
+    /// rescheduling themselves until the game is revealed, replaced or voted
+    /// again.
+    fn schedule_countdown_tick(&self, ctx: &mut Context<Self>) {
+        let game = match self.current_game.as_ref() {
+            Some(game) => game,
+            None => return,
+        };
+        let time_left = match self.countdown_time_left(game) {
+            Some(time_left) => time_left,
+            None => return,
+        };
+
+        let game_uuid = game.uuid.clone();
+        let deadline = game.deadline;
+        ctx.run_later(
+            cmp::min(time_left, COUNTDOWN_TICK_INTERVAL),
+            move |room, ctx| {
+                if room.tick_countdown(&game_uuid, deadline) {
+                    room.schedule_countdown_tick(ctx);
+                }
+            },
+        );
+    }
+}
+
 impl<R, S, T> Room<R, S, T>
 where
     R: RoomORM,
@@ -89,71 +259,150 @@ where
         let mut room = Room {
             room_id: None,
             passphrase: params.passphrase,
-            card_set: params.card_set,
-            owner_client_id: params.owner_client_id,
-            players: HashSet::new(),
+            card_set: card::cards_from_labels(&params.card_set),
+            options: params.options,
+            join_code: params.join_code,
+            owner_uuid: params.owner_uuid,
+            players: HashMap::new(),
+            spectators: HashSet::new(),
+            detached_client_ids: HashSet::new(),
+            banned_player_uuids: HashSet::new(),
             current_game: None,
+            backlog: vec![],
+            event_seq: 0,
+            events: VecDeque::new(),
 
             room_model,
             client_store,
             client_store_channel_type: PhantomData,
+            clock: Box::new(SystemClock),
         };
 
-        room.players.insert(params.owner_client_id);
+        if let Some(owner_client_id) = params.owner_client_id {
+            room.attach(room.owner_uuid.clone(), owner_client_id);
+        }
 
         room
     }
 
+    /// Instantiate a functional room from its record, such as when the room
+    /// is looked up after the server restarts. Banned players are kept out
+    /// of the room. The room starts without players and games.
+    pub fn load(
+        record: RoomRecord,
+        room_model: R,
+        client_store: SharedClientStore<S, T>,
+    ) -> CommonResult<Self> {
+        let banned_player_uuids = room_model.list_bans(&record.uuid)?;
+        let params = NewRoomParams {
+            passphrase: record.passphrase,
+            card_set: record.card_set,
+            options: record.options,
+            owner_client_id: None,
+            owner_uuid: record.owner_uuid,
+            join_code: record.join_code.unwrap_or_default(),
+        };
+        let mut room = Room::new(params, room_model, client_store);
+        room.room_id = Some(record.uuid);
+        room.banned_player_uuids = banned_player_uuids.into_iter().collect();
+
+        Ok(room)
+    }
+
     pub fn is_private(&self) -> bool {
         self.passphrase.is_some()
     }
 
-    pub fn handle_request_message(&self, req: ClientRequestMessage) -> CommonResult<()> {
-        unreachable!()
+    pub fn handle_request_message(&mut self, msg: ClientRequestMessage) -> CommonResult<()> {
+        match msg.req {
+            RequestMessage::JoinRoom(params) => {
+                self.join(msg.client_id, params.passphrase, params.spectator)
+            }
+            RequestMessage::KickPlayer(params) => {
+                self.kick(msg.client_id, params.player_uuid, false)
+            }
+            RequestMessage::BanPlayer(params) => self.kick(msg.client_id, params.player_uuid, true),
+            RequestMessage::UpdateRoomConfig(params) => self.update_config(msg.client_id, params),
+            RequestMessage::StartGame(params) => self.start_game(msg.client_id, params),
+            RequestMessage::PlayCard(params) => self.play_card(msg.client_id, params),
+            RequestMessage::RevealCards => self.reveal_cards(msg.client_id),
+            RequestMessage::ResetGame(params) => self.reset_game(msg.client_id, params),
+            RequestMessage::FinalizeGame(params) => self.finalize_game(msg.client_id, params),
+            RequestMessage::AddStory(params) => self.add_story(msg.client_id, params),
+            RequestMessage::ReorderBacklog(params) => self.reorder_backlog(msg.client_id, params),
+            RequestMessage::RemoveStory(params) => self.remove_story(msg.client_id, params),
+            RequestMessage::SkipStory => self.skip_story(msg.client_id),
+            RequestMessage::NextStory(params) => self.next_story(msg.client_id, params),
+            _ => Err(Error::new(
+                ErrorKind::BadRequest,
+                "Request cannot be handled by room",
+            )),
+        }
     }
 
     /// Create the room and turn it into functional state. Return the created
     /// room Uuid.
     pub fn create(&mut self) -> CommonResult<Uuid> {
-        let client_store = self.client_store.get_readable();
-        let owner_client = client_store.get(&self.owner_client_id).context(|| {
-            (
-                ErrorKind::MissingClientError,
-                "Missing owner client when creating room",
-            )
-        })?;
-        let owner_uuid = owner_client.user_info.get_readable().uuid.clone();
+        if let Some(owner_client_ids) = self.players.get(&self.owner_uuid) {
+            let client_store = self.client_store.get_readable();
+            if !owner_client_ids
+                .iter()
+                .all(|client_id| client_store.contains_key(client_id))
+            {
+                return Err(Error::new(
+                    ErrorKind::MissingClientError,
+                    "Missing owner client when creating room",
+                ));
+            }
+        }
 
         let params = NewRoomRecordParams {
             passphrase: self.passphrase.clone(),
-            owner_uuid,
-            card_set: self.card_set.clone(),
+            owner_uuid: self.owner_uuid.clone(),
+            card_set: self.card_labels(),
+            options: self.options.clone(),
+            join_code: self.join_code.clone(),
         };
         let room_record = self.room_model.create(params)?;
-
-        let created_room = CreatedRoom {
-            private: self.is_private(),
-            uuid: room_record.uuid.clone(),
-            card_set: room_record.card_set.clone(),
-        };
-        owner_client
-            .channel
-            .do_send(ResponseMessage::RoomCreated(created_room))
-            .unwrap_or_else(|err| {
-                warn!(
-                    "Error when sending RoomCreated response to room owner websocket client {}: {}",
-                    self.owner_client_id, err
-                )
-            });
+        self.room_id = Some(room_record.uuid.clone());
 
         Ok(room_record.uuid)
     }
 
+    /// Details of the created room for its owner, including the join code.
+    pub fn created_room(&self) -> CommonResult<CreatedRoom> {
+        Ok(CreatedRoom {
+            uuid: self.uuid()?,
+            join_code: self.join_code.clone(),
+            private: self.is_private(),
+            card_set: self.card_labels(),
+            cards: self.card_set.clone(),
+            options: self.options.clone(),
+            request_id: None,
+        })
+    }
+
     // TODO: Use SecStr for passphrase
-    fn join(&mut self, joiner_client_id: ClientId, passphrase: Option<String>) -> CommonResult<()> {
-        if self.players.contains(&joiner_client_id) {
+    /// Join the room. Spectators can watch the games but cannot play cards.
+    /// A player who is already in the room joins from another client to the
+    /// same seat, whose spectator flag is kept.
+    fn join(
+        &mut self,
+        joiner_client_id: ClientId,
+        passphrase: Option<String>,
+        spectator: bool,
+    ) -> CommonResult<()> {
+        let joiner_uuid = self
+            .find_player_uuid(joiner_client_id)
+            .context(|| (ErrorKind::MissingClientError, "Missing joiner client"))?;
+        let joined_client_ids = self.players.get(&joiner_uuid);
+        if joined_client_ids
+            .filter(|client_ids| client_ids.contains(&joiner_client_id))
+            .is_some()
+        {
             return Err(Error::from(ErrorKind::AlreadyJoinedError));
         }
+        let is_new_player = joined_client_ids.is_none();
 
         if self.is_private() {
             if self.passphrase != passphrase {
@@ -161,370 +410,3996 @@ where
             }
         }
 
-        self.players.insert(joiner_client_id);
+        if !is_new_player {
+            self.attach(joiner_uuid, joiner_client_id);
+            return Ok(());
+        }
 
-        let client_store = self.client_store.get_readable();
-        for client_id in self.players.iter() {
-            if let Some(client) = client_store.get(client_id) {
-                client
-                    .channel
-                    .do_send(ResponseMessage::UserJoined(String::from("Test")))
-                    .unwrap_or_else(|err| {
-                        warn!(
-                            "Error when sending UserJoined response to room player websocket client {}: {}",
-                            client_id, err
-                        )
-                    });
-            } else {
-                error!(
-                    "Trying to notify user joined to deleted websocket client {}",
-                    client_id
+        if self.options.locked {
+            return Err(Error::new(
+                ErrorKind::RoomLockedError,
+                "Room is locked by room owner",
+            ));
+        }
+
+        if self.banned_player_uuids.contains(&joiner_uuid) {
+            return Err(Error::new(
+                ErrorKind::BannedError,
+                "Player is banned from the room",
+            ));
+        }
+
+        self.room_model.create_player(NewRoomPlayerRecordParams {
+            room_uuid: self.uuid()?,
+            player_uuid: joiner_uuid.clone(),
+        })?;
+
+        self.attach(joiner_uuid.clone(), joiner_client_id);
+        if spectator {
+            self.spectators.insert(joiner_uuid.clone());
+        }
+
+        self.broadcast(ResponseMessage::UserJoined(joiner_uuid));
+
+        Ok(())
+    }
+
+    fn attach(&mut self, player_uuid: Uuid, client_id: ClientId) {
+        self.players
+            .entry(player_uuid)
+            .or_default()
+            .insert(client_id);
+    }
+
+    /// Remove all connections of the player from the room. When ban is true,
+    /// the player is also blocked from rejoining for the room's lifetime.
+    fn kick(
+        &mut self,
+        kicker_client_id: ClientId,
+        player_uuid: Uuid,
+        ban: bool,
+    ) -> CommonResult<()> {
+        if !self.is_owner(kicker_client_id) {
+            return Err(Error::new(
+                ErrorKind::ForbiddenError,
+                "Only room owner can kick or ban players",
+            ));
+        }
+
+        let target_client_ids: Vec<ClientId> = self
+            .players
+            .get(&player_uuid)
+            .map(|client_ids| client_ids.iter().cloned().collect())
+            .unwrap_or_default();
+        if player_uuid == self.owner_uuid {
+            return Err(Error::new(
+                ErrorKind::InvalidParams,
+                "Room owner cannot be kicked or banned",
+            ));
+        }
+        if target_client_ids.is_empty() && !ban {
+            return Err(Error::from(ErrorKind::PlayerNotFound));
+        }
+
+        let room_uuid = self.uuid()?;
+        if ban && !self.banned_player_uuids.contains(&player_uuid) {
+            self.room_model.create_ban(NewRoomBanRecordParams {
+                room_uuid: room_uuid.clone(),
+                user_uuid: player_uuid.clone(),
+            })?;
+            self.banned_player_uuids.insert(player_uuid.clone());
+        }
+
+        if target_client_ids.is_empty() {
+            return Ok(());
+        }
+
+        self.remove_player(&player_uuid);
+        for client_id in target_client_ids.iter() {
+            self.send_to_client(
+                *client_id,
+                ResponseMessage::Kicked(KickedFromRoom {
+                    room_uuid: room_uuid.clone(),
+                    banned: ban,
+                }),
+            );
+        }
+
+        self.broadcast(ResponseMessage::UserLeft(player_uuid));
+        self.reveal_if_all_played();
+
+        Ok(())
+    }
+
+    /// Remove the disconnected client from the room. The player leaves the
+    /// room with its last client, and its hand in the current game is
+    /// discarded so that it does not block the automatic reveal.
+    pub fn leave(&mut self, client_id: ClientId, player_uuid: Uuid) {
+        let client_ids = match self.players.get_mut(&player_uuid) {
+            Some(client_ids) => client_ids,
+            None => return,
+        };
+        if !client_ids.remove(&client_id) {
+            return;
+        }
+        self.detached_client_ids.remove(&client_id);
+        if !client_ids.is_empty() {
+            return;
+        }
+
+        self.remove_player(&player_uuid);
+
+        self.broadcast(ResponseMessage::UserLeft(player_uuid));
+        self.reveal_if_all_played();
+    }
+
+    /// Keep the seat and hand of the disconnected client, so that it can be
+    /// taken back when the client resumes the session.
+    pub fn detach(&mut self, client_id: ClientId) {
+        if self.find_member_uuid(client_id).is_some() {
+            self.detached_client_ids.insert(client_id);
+        }
+    }
+
+    /// Attach the client back to its seat and replay the events after the
+    /// last one it has received. When the events are no longer buffered, the
+    /// current state of the room is sent in place of them.
+    pub fn resume(&mut self, client_id: ClientId, last_seq: Option<u64>) -> CommonResult<()> {
+        if self.find_member_uuid(client_id).is_none() {
+            return Err(Error::new(
+                ErrorKind::PlayerNotFound,
+                "Player has already left the room",
+            ));
+        }
+
+        match last_seq.and_then(|last_seq| self.events_after(last_seq)) {
+            Some(events) => {
+                for frame in events {
+                    self.send_frame(client_id, frame);
+                }
+            }
+            None => {
+                let snapshot = self.snapshot(client_id)?;
+                self.send_to_client(
+                    client_id,
+                    ResponseMessage::SessionResumed(Box::new(snapshot)),
                 );
             }
         }
+        self.detached_client_ids.remove(&client_id);
 
         Ok(())
     }
-}
 
-#[derive(Debug, Clone)]
-pub struct NewRoomParams {
-    pub passphrase: Option<String>,
-    pub card_set: Vec<Card>,
-    pub owner_client_id: ClientId,
-}
+    /// Buffered events after the given sequence number, or None when some of
+    /// them are no longer buffered.
+    fn events_after(&self, last_seq: u64) -> Option<Vec<ResponseFrame>> {
+        let first_buffered_seq = self.event_seq + 1 - self.events.len() as u64;
+        if last_seq > self.event_seq || last_seq + 1 < first_buffered_seq {
+            return None;
+        }
 
-#[cfg(test)]
-mod test {
-    use super::*;
+        Some(
+            self.events
+                .iter()
+                .filter(|frame| frame.seq > Some(last_seq))
+                .cloned()
+                .collect(),
+        )
+    }
 
-    use std::time::SystemTime;
+    /// Remove the player with all its clients from the room.
+    fn remove_player(&mut self, player_uuid: &str) {
+        if let Some(client_ids) = self.players.remove(player_uuid) {
+            for client_id in client_ids.iter() {
+                self.detached_client_ids.remove(client_id);
+            }
+        }
+        self.spectators.remove(player_uuid);
+        if let Some(game) = self.current_game.as_mut() {
+            game.players_hands.remove(player_uuid);
+        }
+    }
 
-    use uuid::Uuid;
+    fn update_config(
+        &mut self,
+        client_id: ClientId,
+        params: UpdateRoomConfigParams,
+    ) -> CommonResult<()> {
+        if !self.is_owner(client_id) {
+            return Err(Error::new(
+                ErrorKind::ForbiddenError,
+                "Only room owner can update room config",
+            ));
+        }
 
-    use crate::client::channel::MockClientChannel;
-    use crate::client::store::DefaultClientStore;
-    use crate::client::Client;
-    use crate::common::error::Error;
-    use crate::common::model::Uuid as UuidType;
-    use crate::poker::model::{MockRoomORM, RoomRecord};
-    use crate::user::info::{SharedUserInfo, UserInfo};
+        self.apply_config(params)
+    }
 
-    type MockRoom = Room<MockRoomORM, MockDefaultClientStore, MockClientChannel>;
-    type MockSharedClientStore = SharedClientStore<MockDefaultClientStore, MockClientChannel>;
-    type MockDefaultClientStore = DefaultClientStore<MockClientChannel>;
-    type MockClient = Client<MockClientChannel>;
+    /// Update room configuration on behalf of the user, who has to be the
+    /// room owner. Return the updated configuration.
+    pub fn update_config_as_user(
+        &mut self,
+        user_uuid: &str,
+        params: UpdateRoomConfigParams,
+    ) -> CommonResult<RoomConfig> {
+        if self.owner_uuid != user_uuid {
+            return Err(Error::new(
+                ErrorKind::ForbiddenError,
+                "Only room owner can update room config",
+            ));
+        }
 
-    const DEFAULT_OWNER_CLIENT_ID: usize = 1;
+        self.apply_config(params)?;
 
-    #[test]
-    fn new_should_instantiate_room_struct_with_given_params() {
-        let (passphrase, card_set, owner_client_id, params) = default_new_room_params();
+        Ok(self.config())
+    }
 
-        let room_model = MockRoomORM::new();
-        let client_store = DefaultClientStore::<MockClientChannel>::default();
-        let shared_client_store = SharedClientStore::new(client_store);
+    /// Update room configuration and notify all players. Card set cannot be
+    /// changed to one which does not contain the cards already played in the
+    /// current game. Anonymous mode cannot be turned off while there is a
+    /// current game, as its hands are kept with the players who played them.
+    fn apply_config(&mut self, params: UpdateRoomConfigParams) -> CommonResult<()> {
+        let passphrase = params.passphrase.unwrap_or_else(|| self.passphrase.clone());
+        let card_set = match (params.card_set, params.deck_preset) {
+            (None, None) => self.card_labels(),
+            (card_set, deck_preset) => deck::resolve_card_set(card_set, deck_preset)?,
+        };
+        let options = params.options.unwrap_or_else(|| self.options.clone());
 
-        let room = Room::new(params, room_model, shared_client_store);
+        if let Some(game) = self.current_game.as_ref() {
+            if self.options.anonymous && !options.anonymous {
+                return Err(Error::new(
+                    ErrorKind::GameInProgressError,
+                    "Anonymous mode cannot be turned off during a game",
+                ));
+            }
+            if !game.revealed && options.anonymous != self.options.anonymous {
+                return Err(Error::new(
+                    ErrorKind::GameInProgressError,
+                    "Anonymous mode cannot be changed before cards are revealed",
+                ));
+            }
+            if game
+                .players_hands
+                .values()
+                .any(|card| !card_set.contains(card))
+            {
+                return Err(Error::new(
+                    ErrorKind::GameInProgressError,
+                    "Card set cannot drop cards played in current game",
+                ));
+            }
+        }
 
-        let mut expected_players = HashSet::new();
-        expected_players.insert(owner_client_id);
-        assert_eq!(room.passphrase, passphrase);
-        assert_eq!(room.card_set, card_set);
-        assert_eq!(room.owner_client_id, owner_client_id);
-        assert_eq!(room.players, expected_players);
+        self.room_model.update(UpdateRoomRecordParams {
+            uuid: self.uuid()?,
+            passphrase: passphrase.clone(),
+            card_set: card_set.clone(),
+            options: options.clone(),
+        })?;
+
+        self.passphrase = passphrase;
+        self.card_set = card::cards_from_labels(&card_set);
+        self.options = options;
+
+        self.broadcast(ResponseMessage::ConfigUpdated(self.config()));
+        self.reveal_if_all_played();
+
+        Ok(())
+    }
+
+    /// Close the room on behalf of the user, who has to be the room owner.
+    /// All players are notified and removed from the room.
+    pub fn close(&mut self, user_uuid: &str) -> CommonResult<()> {
+        if self.owner_uuid != user_uuid {
+            return Err(Error::new(
+                ErrorKind::ForbiddenError,
+                "Only room owner can close the room",
+            ));
+        }
+
+        let room_uuid = self.uuid()?;
+        self.room_model.close(&room_uuid)?;
+
+        self.broadcast(ResponseMessage::RoomClosed(room_uuid));
+        self.players.clear();
+        self.spectators.clear();
+        self.detached_client_ids.clear();
+        self.current_game = None;
+
+        Ok(())
+    }
+
+    /// Start a new game in the room. The previous game has to be revealed
+    /// before a new game can be started. When countdown is given, the cards
+    /// are revealed automatically once it runs out.
+    fn start_game(&mut self, client_id: ClientId, params: StartGameParams) -> CommonResult<()> {
+        if !self.is_owner(client_id) {
+            return Err(Error::new(
+                ErrorKind::ForbiddenError,
+                "Only room owner can start a game",
+            ));
+        }
+        self.check_no_game_in_progress()?;
+
+        let deadline = self.countdown_deadline(params.countdown_secs)?;
+
+        let mut game = Game::new(params.title.clone(), params.description.clone());
+        self.room_model.create_game(NewGameRecordParams {
+            uuid: game.uuid.clone(),
+            room_uuid: self.uuid()?,
+            title: game.title.clone(),
+            description: game.description.clone(),
+        })?;
+        game.deadline = deadline;
+        self.current_game = Some(game);
+
+        self.broadcast(ResponseMessage::GameStarted(StartedGame {
+            title: params.title,
+            description: params.description,
+            link: None,
+            countdown_secs: params.countdown_secs,
+        }));
+
+        Ok(())
     }
 
-    mod is_private {
-        use super::*;
+    fn check_no_game_in_progress(&self) -> CommonResult<()> {
+        match self.current_game.as_ref() {
+            Some(game) if !game.revealed => Err(Error::new(
+                ErrorKind::GameInProgressError,
+                "Current game is not revealed yet",
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Clear the hands of the current game so that it can be voted again.
+    /// Once the cards are revealed, the hands are kept as a numbered attempt
+    /// and the next round of voting starts a new attempt.
+    fn reset_game(&mut self, client_id: ClientId, params: ResetGameParams) -> CommonResult<()> {
+        if !self.is_owner(client_id) {
+            return Err(Error::new(
+                ErrorKind::ForbiddenError,
+                "Only room owner can reset a game",
+            ));
+        }
+        let deadline = self.countdown_deadline(params.countdown_secs)?;
+
+        let game = self.current_game_mut()?;
+        if game.revealed {
+            game.attempt += 1;
+        }
+        game.revealed = false;
+        game.players_hands.clear();
+        game.deadline = deadline;
+        let attempt = game.attempt;
+
+        self.broadcast(ResponseMessage::GameReset(ResetRound {
+            attempt,
+            countdown_secs: params.countdown_secs,
+        }));
+
+        Ok(())
+    }
+
+    /// Store the agreed estimate of the revealed game and advance to the story
+    /// at the top of the backlog. The room is left without a game when the
+    /// backlog is empty. The estimate has to be an estimate card of the card
+    /// set, unless a reason is given for overriding it.
+    fn finalize_game(
+        &mut self,
+        client_id: ClientId,
+        params: FinalizeGameParams,
+    ) -> CommonResult<()> {
+        if !self.is_owner(client_id) {
+            return Err(Error::new(
+                ErrorKind::ForbiddenError,
+                "Only room owner can finalize a game",
+            ));
+        }
+
+        let estimate = String::from(params.estimate.trim());
+        let reason = params
+            .reason
+            .map(|reason| String::from(reason.trim()))
+            .filter(|reason| !reason.is_empty());
+        match (self.find_card(&estimate), reason.as_ref()) {
+            (Some(card), _) if !card.is_estimate() => {
+                return Err(Error::new(
+                    ErrorKind::InvalidParams,
+                    "Final estimate cannot be a special card",
+                ));
+            }
+            (None, None) => {
+                return Err(Error::new(
+                    ErrorKind::InvalidParams,
+                    "Reason is required when final estimate is not in the card set",
+                ));
+            }
+            (None, Some(_)) => {
+                deck::validate_card_set(slice::from_ref(&estimate))?;
+            }
+            _ => (),
+        }
+
+        let game = self
+            .current_game
+            .as_ref()
+            .context(|| (ErrorKind::GameNotFound, "No game in the room"))?;
+        if !game.revealed {
+            return Err(Error::new(
+                ErrorKind::GameInProgressError,
+                "Cards must be revealed before finalizing the game",
+            ));
+        }
+
+        self.room_model.finalize_game(FinalizeGameRecordParams {
+            uuid: game.uuid.clone(),
+            room_uuid: self.uuid()?,
+            final_estimate: estimate.clone(),
+            final_estimate_reason: reason.clone(),
+        })?;
+        let game_uuid = game.uuid.clone();
+        self.current_game = None;
+
+        self.broadcast(ResponseMessage::GameFinalized(FinalizedGame {
+            game_uuid,
+            estimate,
+            reason,
+        }));
+
+        if !self.backlog.is_empty() {
+            self.start_next_story(None)
+                .unwrap_or_else(|err| error!("Error when starting next story: {}", err));
+        }
+
+        Ok(())
+    }
+
+    /// Append a story to the end of the backlog.
+    fn add_story(&mut self, client_id: ClientId, params: AddStoryParams) -> CommonResult<()> {
+        if !self.is_owner(client_id) {
+            return Err(Error::new(
+                ErrorKind::ForbiddenError,
+                "Only room owner can add stories",
+            ));
+        }
+        self.check_backlog_capacity(1)?;
+
+        let draft = StoryDraft::new(params.title, params.description, params.link)?;
+        self.push_story(draft)?;
+
+        self.broadcast_backlog();
+
+        Ok(())
+    }
+
+    /// Import stories to the end of the backlog. Every row is validated
+    /// before any story is added, and rows whose external id is already
+    /// imported to the room are skipped, so that a file can be imported again
+    /// safely.
+    pub fn import_stories(
+        &mut self,
+        user_uuid: &str,
+        rows: Vec<CommonResult<StoryImportRow>>,
+    ) -> CommonResult<StoryImportReport> {
+        if self.owner_uuid != user_uuid {
+            return Err(Error::new(
+                ErrorKind::ForbiddenError,
+                "Only room owner can import stories",
+            ));
+        }
+
+        let mut imported_external_ids: HashSet<String> = self
+            .room_model
+            .find_game_external_ids(&self.uuid()?)?
+            .into_iter()
+            .collect();
+        let mut drafts = vec![];
+        let mut skipped = vec![];
+        let mut errors = vec![];
+        for (index, row) in rows.into_iter().enumerate() {
+            match row.and_then(|row| self.story_draft_from_row(row)) {
+                Ok(draft) => match draft.external_id.clone() {
+                    Some(external_id) if imported_external_ids.contains(&external_id) => {
+                        skipped.push(external_id)
+                    }
+                    external_id => {
+                        imported_external_ids.extend(external_id);
+                        drafts.push(draft);
+                    }
+                },
+                Err(err) => errors.push(StoryImportError {
+                    row: index + 1,
+                    message: String::from(err.message()),
+                }),
+            }
+        }
+
+        if !errors.is_empty() {
+            return Ok(StoryImportReport {
+                imported: vec![],
+                skipped,
+                errors,
+            });
+        }
+        self.check_backlog_capacity(drafts.len())?;
+
+        let mut imported = Vec::with_capacity(drafts.len());
+        for draft in drafts.into_iter() {
+            imported.push(self.push_story(draft)?);
+        }
+        if !imported.is_empty() {
+            self.broadcast_backlog();
+        }
+
+        Ok(StoryImportReport {
+            imported,
+            skipped,
+            errors,
+        })
+    }
+
+    fn story_draft_from_row(&self, row: StoryImportRow) -> CommonResult<StoryDraft> {
+        let draft = StoryDraft::new(row.title, row.description, row.link)?;
+        let pre_estimate = backlog::non_blank(row.estimate);
+        if let Some(pre_estimate) = pre_estimate.as_ref() {
+            match self.find_card(pre_estimate) {
+                Some(card) if card.is_estimate() => (),
+                _ => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidParams,
+                        "Estimate must be an estimate card of the card set",
+                    ))
+                }
+            }
+        }
+
+        Ok(StoryDraft {
+            external_id: backlog::non_blank(row.external_id),
+            pre_estimate,
+            ..draft
+        })
+    }
+
+    fn check_backlog_capacity(&self, new_stories_len: usize) -> CommonResult<()> {
+        if self.backlog.len() + new_stories_len > MAX_BACKLOG_LEN {
+            return Err(Error::new(
+                ErrorKind::InvalidParams,
+                format!("Backlog cannot have more than {} stories", MAX_BACKLOG_LEN),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Persist the story and append it to the end of the backlog.
+    fn push_story(&mut self, draft: StoryDraft) -> CommonResult<Story> {
+        let sequence = self
+            .backlog
+            .last()
+            .map(|story| story.sequence + 1)
+            .unwrap_or(0);
+        let story_record = self.room_model.create_story(NewStoryRecordParams {
+            uuid: uuid::Uuid::new_v4().to_string(),
+            room_uuid: self.uuid()?,
+            sequence,
+            title: draft.title,
+            description: draft.description,
+            link: draft.link,
+            external_id: draft.external_id,
+            pre_estimate: draft.pre_estimate,
+        })?;
+        let story = Story {
+            uuid: story_record.uuid,
+            title: story_record.title,
+            description: story_record.description,
+            link: story_record.link,
+            external_id: story_record.external_id,
+            pre_estimate: story_record.pre_estimate,
+            sequence,
+        };
+        self.backlog.push(story.clone());
+
+        Ok(story)
+    }
+
+    /// Reorder the backlog. The new order has to list every story in the
+    /// backlog exactly once.
+    fn reorder_backlog(
+        &mut self,
+        client_id: ClientId,
+        params: ReorderBacklogParams,
+    ) -> CommonResult<()> {
+        if !self.is_owner(client_id) {
+            return Err(Error::new(
+                ErrorKind::ForbiddenError,
+                "Only room owner can reorder the backlog",
+            ));
+        }
+
+        let mut backlog = Vec::with_capacity(self.backlog.len());
+        for (sequence, story_uuid) in params.story_uuids.iter().enumerate() {
+            let story = self
+                .backlog
+                .iter()
+                .find(|story| &story.uuid == story_uuid)
+                .context(|| (ErrorKind::StoryNotFound, "Story is not in the backlog"))?;
+            if backlog
+                .iter()
+                .any(|ordered: &Story| &ordered.uuid == story_uuid)
+            {
+                return Err(Error::new(
+                    ErrorKind::InvalidParams,
+                    "Story is listed more than once",
+                ));
+            }
+            backlog.push(Story {
+                sequence: sequence as i32,
+                ..story.clone()
+            });
+        }
+        if backlog.len() != self.backlog.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidParams,
+                "New order must list every story in the backlog",
+            ));
+        }
+
+        self.room_model.update_story_sequences(
+            backlog
+                .iter()
+                .map(|story| (story.uuid.clone(), story.sequence))
+                .collect(),
+        )?;
+        self.backlog = backlog;
+
+        self.broadcast_backlog();
+
+        Ok(())
+    }
+
+    fn remove_story(&mut self, client_id: ClientId, params: RemoveStoryParams) -> CommonResult<()> {
+        if !self.is_owner(client_id) {
+            return Err(Error::new(
+                ErrorKind::ForbiddenError,
+                "Only room owner can remove stories",
+            ));
+        }
+        let index = self
+            .backlog
+            .iter()
+            .position(|story| story.uuid == params.story_uuid)
+            .context(|| (ErrorKind::StoryNotFound, "Story is not in the backlog"))?;
+
+        self.room_model.delete_story(&params.story_uuid)?;
+        self.backlog.remove(index);
+
+        self.broadcast_backlog();
+
+        Ok(())
+    }
+
+    /// Move the story at the top of the backlog to its end, so that it is
+    /// estimated later.
+    fn skip_story(&mut self, client_id: ClientId) -> CommonResult<()> {
+        if !self.is_owner(client_id) {
+            return Err(Error::new(
+                ErrorKind::ForbiddenError,
+                "Only room owner can skip stories",
+            ));
+        }
+        let (first, last) = match (self.backlog.first(), self.backlog.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Err(Error::new(ErrorKind::StoryNotFound, "Backlog is empty")),
+        };
+
+        let sequence = last.sequence + 1;
+        self.room_model
+            .update_story_sequences(vec![(first.uuid.clone(), sequence)])?;
+        let mut story = self.backlog.remove(0);
+        story.sequence = sequence;
+        self.backlog.push(story);
+
+        self.broadcast_backlog();
+
+        Ok(())
+    }
+
+    /// Start the story at the top of the backlog as the current game. Same as
+    /// starting a game, the previous game has to be revealed first.
+    fn next_story(&mut self, client_id: ClientId, params: NextStoryParams) -> CommonResult<()> {
+        if !self.is_owner(client_id) {
+            return Err(Error::new(
+                ErrorKind::ForbiddenError,
+                "Only room owner can start the next story",
+            ));
+        }
+        self.check_no_game_in_progress()?;
+
+        self.start_next_story(params.countdown_secs)
+    }
+
+    fn start_next_story(&mut self, countdown_secs: Option<u64>) -> CommonResult<()> {
+        let story = self
+            .backlog
+            .first()
+            .context(|| (ErrorKind::StoryNotFound, "Backlog is empty"))?;
+
+        let deadline = self.countdown_deadline(countdown_secs)?;
+
+        self.room_model.start_story(&story.uuid, &self.uuid()?)?;
+        let mut game = Game::from(self.backlog.remove(0));
+        game.deadline = deadline;
+        let started_game = StartedGame {
+            title: game.title.clone(),
+            description: game.description.clone(),
+            link: game.link.clone(),
+            countdown_secs,
+        };
+        self.current_game = Some(game);
+
+        self.broadcast(ResponseMessage::GameStarted(started_game));
+        self.broadcast_backlog();
+
+        Ok(())
+    }
+
+    fn countdown_deadline(&self, countdown_secs: Option<u64>) -> CommonResult<Option<Instant>> {
+        match countdown_secs {
+            None => Ok(None),
+            Some(countdown_secs) if countdown_secs == 0 || countdown_secs > MAX_COUNTDOWN_SECS => {
+                Err(Error::new(
+                    ErrorKind::InvalidParams,
+                    format!(
+                        "Countdown must be between 1 and {} seconds",
+                        MAX_COUNTDOWN_SECS
+                    ),
+                ))
+            }
+            Some(countdown_secs) => {
+                Ok(Some(self.clock.now() + Duration::from_secs(countdown_secs)))
+            }
+        }
+    }
+
+    /// Play or replace the player's card in the current game. Other players
+    /// are only told that the player has played.
+    fn play_card(&mut self, client_id: ClientId, params: PlayCardParams) -> CommonResult<()> {
+        let player_uuid = self
+            .find_member_uuid(client_id)
+            .context(|| (ErrorKind::MissingClientError, "Missing player client"))?;
+        if self.spectators.contains(&player_uuid) {
+            return Err(Error::new(
+                ErrorKind::ForbiddenError,
+                "Spectators cannot play cards",
+            ));
+        }
+        if !self.card_set.iter().any(|card| card.label == params.card) {
+            return Err(Error::new(
+                ErrorKind::InvalidParams,
+                "Card is not in the card set of the room",
+            ));
+        }
+
+        let game = self.current_game_mut()?;
+        if game.revealed {
+            return Err(Error::new(
+                ErrorKind::GameInProgressError,
+                "Cards of current game are already revealed",
+            ));
+        }
+        game.players_hands.insert(player_uuid.clone(), params.card);
+
+        self.broadcast(ResponseMessage::CardPlayed(player_uuid));
+        self.reveal_if_all_played();
+
+        Ok(())
+    }
+
+    /// Reveal the cards when auto reveal is enabled and every player in the
+    /// room, except spectators, has played in the current game.
+    fn reveal_if_all_played(&mut self) {
+        if !self.options.auto_reveal {
+            return;
+        }
+        let all_played = match self.current_game.as_ref() {
+            Some(game) if !game.revealed && !game.players_hands.is_empty() => self
+                .players
+                .keys()
+                .filter(|player_uuid| !self.spectators.contains(*player_uuid))
+                .all(|player_uuid| game.players_hands.contains_key(player_uuid)),
+            _ => false,
+        };
+
+        if all_played {
+            self.reveal()
+                .unwrap_or_else(|err| error!("Error when revealing cards automatically: {}", err));
+        }
+    }
+
+    /// Reveal the cards played in the current game together with their
+    /// statistics.
+    fn reveal_cards(&mut self, client_id: ClientId) -> CommonResult<()> {
+        if !self.is_owner(client_id) {
+            return Err(Error::new(
+                ErrorKind::ForbiddenError,
+                "Only room owner can reveal cards",
+            ));
+        }
+
+        self.reveal()
+    }
+
+    /// Broadcast the time left of the countdown of the given game, or reveal
+    /// its cards if the countdown has run out. The deadline tells apart the
+    /// countdowns of different voting rounds of the same game. Return whether
+    /// the countdown is still running.
+    fn tick_countdown(&mut self, game_uuid: &str, deadline: Option<Instant>) -> bool {
+        let time_left = match self.current_game.as_ref() {
+            Some(game) if game.uuid == game_uuid && game.deadline == deadline => {
+                self.countdown_time_left(game)
+            }
+            _ => None,
+        };
+
+        match time_left {
+            None => false,
+            Some(time_left) if time_left == Duration::from_secs(0) => {
+                self.reveal().unwrap_or_else(|err| {
+                    error!("Error when revealing cards after countdown: {}", err)
+                });
+                false
+            }
+            Some(time_left) => {
+                // Round up so that zero is only shown when the cards are revealed
+                let secs_left = time_left.as_secs() + u64::from(time_left.subsec_nanos() > 0);
+                self.broadcast_unsequenced(ResponseMessage::CountdownTicked(CountdownTick {
+                    secs_left,
+                }));
+                true
+            }
+        }
+    }
+
+    /// Time left of the game countdown. None when the game is not timeboxed
+    /// or is already revealed.
+    fn countdown_time_left(&self, game: &Game) -> Option<Duration> {
+        if game.revealed {
+            return None;
+        }
+        let now = self.clock.now();
+
+        game.deadline.map(|deadline| {
+            if deadline > now {
+                deadline - now
+            } else {
+                Duration::from_secs(0)
+            }
+        })
+    }
+
+    /// Persist the played hands and broadcast them. In anonymous mode, the
+    /// hands are neither persisted nor broadcast with who played them.
+    fn reveal(&mut self) -> CommonResult<()> {
+        let game = self
+            .current_game
+            .as_ref()
+            .context(|| (ErrorKind::GameNotFound, "No game in the room"))?;
+        if game.revealed {
+            return Err(Error::new(
+                ErrorKind::GameInProgressError,
+                "Cards of current game are already revealed",
+            ));
+        }
+
+        let mut revealed_cards = self.revealed_cards(game);
+        let anonymous = self.options.anonymous;
+        let now = SystemTime::now();
+        let hand_records: Vec<NewGameHandRecordParams> = revealed_cards
+            .hands
+            .iter()
+            .map(|hand| NewGameHandRecordParams {
+                game_uuid: Some(game.uuid.clone()),
+                user_uuid: if anonymous {
+                    None
+                } else {
+                    Some(hand.player_uuid.clone())
+                },
+                card: hand.card.clone(),
+                last_updated_at: now,
+                attempt: game.attempt as i32,
+            })
+            .collect();
+        if !hand_records.is_empty() {
+            self.room_model.create_game_hands(hand_records)?;
+        }
+
+        self.current_game_mut()?.revealed = true;
+
+        if anonymous {
+            revealed_cards.hands.clear();
+        }
+        self.broadcast(ResponseMessage::CardsRevealed(revealed_cards));
+
+        Ok(())
+    }
+
+    fn revealed_cards(&self, game: &Game) -> RevealedCards {
+        let mut hands: Vec<(&Card, PlayedHand)> = game
+            .players_hands
+            .iter()
+            .filter_map(|(player_uuid, label)| {
+                let card = self.find_card(label)?;
+                Some((
+                    card,
+                    PlayedHand {
+                        player_uuid: player_uuid.clone(),
+                        card: label.clone(),
+                    },
+                ))
+            })
+            .collect();
+        hands.sort_by_key(|(card, _)| card.ordinal);
+
+        let cards: Vec<&Card> = hands.iter().map(|(card, _)| *card).collect();
+        let stats = CardStats::from_cards(&cards);
+
+        let hands: Vec<PlayedHand> = hands.into_iter().map(|(_, hand)| hand).collect();
+        let cards = cards.iter().map(|card| card.label.clone()).collect();
+        let mut abstained: Vec<Uuid> = self
+            .players
+            .keys()
+            .filter(|player_uuid| {
+                !game.players_hands.contains_key(*player_uuid)
+                    && !self.spectators.contains(*player_uuid)
+            })
+            .cloned()
+            .collect();
+        abstained.sort();
+
+        RevealedCards {
+            attempt: game.attempt,
+            hands,
+            cards,
+            stats,
+            abstained,
+        }
+    }
+
+    fn snapshot(&self, client_id: ClientId) -> CommonResult<RoomSnapshot> {
+        let player_uuid = self.find_member_uuid(client_id);
+        let game = self.current_game.as_ref().map(|game| {
+            let mut played: Vec<Uuid> = game.players_hands.keys().cloned().collect();
+            played.sort();
+            let revealed_cards = if game.revealed {
+                let mut revealed_cards = self.revealed_cards(game);
+                if self.options.anonymous {
+                    revealed_cards.hands.clear();
+                }
+                Some(revealed_cards)
+            } else {
+                None
+            };
+
+            GameSnapshot {
+                uuid: game.uuid.clone(),
+                title: game.title.clone(),
+                description: game.description.clone(),
+                link: game.link.clone(),
+                attempt: game.attempt,
+                countdown_secs: self
+                    .countdown_time_left(game)
+                    .map(|time_left| time_left.as_secs()),
+                played,
+                own_card: player_uuid
+                    .as_ref()
+                    .and_then(|player_uuid| game.players_hands.get(player_uuid))
+                    .cloned(),
+                revealed_cards,
+            }
+        });
+
+        let mut players: Vec<Uuid> = self.players.keys().cloned().collect();
+        players.sort();
+        let mut spectators: Vec<Uuid> = self.spectators.iter().cloned().collect();
+        spectators.sort();
+
+        Ok(RoomSnapshot {
+            uuid: self.uuid()?,
+            seq: self.event_seq,
+            config: self.config(),
+            players,
+            spectators,
+            game,
+            backlog: self.backlog.clone(),
+        })
+    }
+
+    fn current_game_mut(&mut self) -> CommonResult<&mut Game> {
+        self.current_game
+            .as_mut()
+            .context(|| (ErrorKind::GameNotFound, "No game in the room"))
+    }
+
+    fn find_card(&self, label: &str) -> Option<&Card> {
+        self.card_set.iter().find(|card| card.label == label)
+    }
+
+    fn card_labels(&self) -> Vec<CardLabel> {
+        card::card_labels(&self.card_set)
+    }
+
+    fn config(&self) -> RoomConfig {
+        RoomConfig {
+            private: self.is_private(),
+            card_set: self.card_labels(),
+            cards: self.card_set.clone(),
+            options: self.options.clone(),
+        }
+    }
+
+    fn is_owner(&self, client_id: ClientId) -> bool {
+        self.find_member_uuid(client_id).as_ref() == Some(&self.owner_uuid)
+    }
+
+    fn uuid(&self) -> CommonResult<Uuid> {
+        self.room_id
+            .clone()
+            .context(|| (ErrorKind::InternalServerError, "Room is not created yet"))
+    }
+
+    fn find_player_uuid(&self, client_id: ClientId) -> Option<Uuid> {
+        self.client_store
+            .get_readable()
+            .get(&client_id)
+            .map(|client| client.user_info.get_readable().uuid.clone())
+    }
+
+    /// Uuid of the player, when the client is attached to the room.
+    fn find_member_uuid(&self, client_id: ClientId) -> Option<Uuid> {
+        self.find_player_uuid(client_id).filter(|player_uuid| {
+            self.players
+                .get(player_uuid)
+                .filter(|client_ids| client_ids.contains(&client_id))
+                .is_some()
+        })
+    }
+
+    fn broadcast_backlog(&mut self) {
+        self.broadcast(ResponseMessage::BacklogUpdated(self.backlog.clone()));
+    }
+
+    /// Send response message to every client of the players in the room as
+    /// the next event of the room. Clients which are disconnected receive the
+    /// event when they resume their sessions, as long as it is still buffered.
+    fn broadcast(&mut self, msg: ResponseMessage) {
+        self.event_seq += 1;
+        let frame = ResponseFrame::event(self.event_seq, msg);
+        if self.events.len() == MAX_BUFFERED_EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back(frame.clone());
+
+        self.send_to_attached_clients(frame);
+    }
+
+    /// Send response message to every connected client of the players in the
+    /// room, without numbering or buffering it. Meant for transient messages,
+    /// such as countdown ticks, which would otherwise push the events out of
+    /// the buffer. Resuming clients catch up with them from the snapshot.
+    fn broadcast_unsequenced(&self, msg: ResponseMessage) {
+        self.send_to_attached_clients(ResponseFrame::from(msg));
+    }
+
+    fn send_to_attached_clients(&self, frame: ResponseFrame) {
+        for client_id in self
+            .players
+            .values()
+            .flatten()
+            .filter(|client_id| !self.detached_client_ids.contains(*client_id))
+        {
+            self.send_frame(*client_id, frame.clone());
+        }
+    }
+
+    fn send_to_client(&self, client_id: ClientId, msg: ResponseMessage) {
+        self.send_frame(client_id, ResponseFrame::from(msg));
+    }
+
+    fn send_frame(&self, client_id: ClientId, frame: ResponseFrame) {
+        let client_store = self.client_store.get_readable();
+        if let Some(client) = client_store.get(&client_id) {
+            client.channel.do_send(frame).unwrap_or_else(|err| {
+                warn!(
+                    "Error when sending response to room player websocket client {}: {}",
+                    client_id, err
+                )
+            });
+        } else {
+            error!(
+                "Trying to send response to deleted websocket client {}",
+                client_id
+            );
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct NewRoomParams {
+    pub passphrase: Option<String>,
+    pub card_set: Vec<CardLabel>,
+    pub options: RoomOptions,
+    /// Absent when the room is created over HTTP. The owner takes over the
+    /// room by joining it from a websocket client.
+    pub owner_client_id: Option<ClientId>,
+    pub owner_uuid: Uuid,
+    pub join_code: String,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::time::SystemTime;
+
+    use uuid::Uuid;
+
+    use crate::client::channel::MockClientChannel;
+    use crate::client::store::DefaultClientStore;
+    use crate::client::Client;
+    use crate::common::clock::MockClock;
+    use crate::common::error::Error;
+    use crate::common::model::Uuid as UuidType;
+    use crate::poker::model::{GameRecord, MockRoomORM, RoomBanRecord};
+    use crate::user::info::{SharedUserInfo, UserInfo};
+
+    type MockRoom = Room<MockRoomORM, MockDefaultClientStore, MockClientChannel>;
+    type MockSharedClientStore = SharedClientStore<MockDefaultClientStore, MockClientChannel>;
+    type MockDefaultClientStore = DefaultClientStore<MockClientChannel>;
+    type MockClient = Client<MockClientChannel>;
+
+    const DEFAULT_OWNER_CLIENT_ID: usize = 1;
+    const DEFAULT_JOIN_CODE: &str = "ABCD2345";
+
+    #[test]
+    fn new_should_instantiate_room_struct_with_given_params() {
+        let (passphrase, card_set, owner_client_id, params) =
+            default_new_room_params(Uuid::new_v4().to_string());
+
+        let room_model = MockRoomORM::new();
+        let client_store = DefaultClientStore::<MockClientChannel>::default();
+        let shared_client_store = SharedClientStore::new(client_store);
+
+        let room = Room::new(params, room_model, shared_client_store);
+
+        let mut expected_owner_client_ids = HashSet::new();
+        expected_owner_client_ids.insert(owner_client_id);
+        assert_eq!(room.passphrase, passphrase);
+        assert_eq!(room.card_labels(), card_set);
+        assert_eq!(room.players.len(), 1);
+        assert_eq!(room.players[&room.owner_uuid], expected_owner_client_ids);
+    }
+
+    mod is_private {
+        use super::*;
+
+        #[test]
+        fn should_return_true_when_room_is_private() {
+            let passphrase = Some(String::from("Passphrase"));
+            let room = make_room(passphrase);
+
+            assert!(room.is_private());
+        }
+
+        #[test]
+        fn should_return_false_when_room_is_public() {
+            let passphrase = None;
+            let room = make_room(passphrase);
+
+            assert!(!room.is_private());
+        }
+    }
+
+    mod create {
+        use super::*;
+
+        #[test]
+        fn should_return_error_when_database_persistence_has_error() {
+            let (shared_user_info, owner_uuid, _owner_name) = default_shared_user_info();
+            let (_passphrase, _card_set, owner_client_id, params) =
+                default_new_room_params(owner_uuid);
+            let shared_client_store = make_shared_client_store(vec![(
+                owner_client_id,
+                shared_user_info,
+                default_mock_client_channel(),
+            )]);
+            let mut room_model = MockRoomORM::new();
+            room_model
+                .expect_create()
+                .once()
+                .return_const(Err(Error::from(ErrorKind::ConnectionPoolError)));
+
+            let mut room = Room::new(params, room_model, shared_client_store);
+
+            let err = room.create().unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::ConnectionPoolError);
+        }
+
+        #[test]
+        fn should_persist_the_room_into_database() {
+            let (shared_user_info, owner_uuid, _owner_name) = default_shared_user_info();
+            let (_passphrase, _card_set, owner_client_id, params) =
+                default_new_room_params(owner_uuid.clone());
+            let shared_client_store = make_shared_client_store(vec![(
+                owner_client_id,
+                shared_user_info,
+                default_mock_client_channel(),
+            )]);
+            let (room_model, _created_room_uuid) =
+                make_succeeded_create_room_model(params.clone(), owner_uuid);
+
+            let mut room = Room::new(params, room_model, shared_client_store);
+
+            assert!(room.create().is_ok());
+        }
+
+        #[test]
+        fn should_return_created_room_uuid() {
+            let (shared_user_info, owner_uuid, _owner_name) = default_shared_user_info();
+            let (_passphrase, _card_set, owner_client_id, params) =
+                default_new_room_params(owner_uuid.clone());
+            let shared_client_store = make_shared_client_store(vec![(
+                owner_client_id,
+                shared_user_info,
+                default_mock_client_channel(),
+            )]);
+            let (room_model, created_room_uuid) =
+                make_succeeded_create_room_model(params.clone(), owner_uuid);
+
+            let mut room = Room::new(params, room_model, shared_client_store);
+
+            assert_eq!(room.create().unwrap(), created_room_uuid);
+        }
+
+        #[test]
+        fn should_return_details_of_created_room_with_join_code() {
+            let room = make_room(None);
+
+            let created_room = room.created_room().unwrap();
+            assert_eq!(created_room.uuid, room.room_id.clone().unwrap());
+            assert_eq!(created_room.join_code, DEFAULT_JOIN_CODE);
+            assert_eq!(created_room.request_id, None);
+        }
+    }
+
+    mod load {
+        use super::*;
+
+        #[test]
+        fn should_restore_room_from_record() {
+            let room_record = make_room_record();
+            let mut room_model = MockRoomORM::new();
+            room_model.expect_list_bans().returning(|_| Ok(vec![]));
+
+            let room = Room::load(
+                room_record.clone(),
+                room_model,
+                default_shared_client_store(),
+            )
+            .unwrap();
+
+            let created_room = room.created_room().unwrap();
+            assert_eq!(created_room.uuid, room_record.uuid);
+            assert_eq!(created_room.join_code, DEFAULT_JOIN_CODE);
+            assert_eq!(room.owner_uuid, room_record.owner_uuid);
+            assert_eq!(room.card_labels(), room_record.card_set);
+        }
+
+        #[test]
+        fn should_keep_banned_players_out_of_room() {
+            let room_record = make_room_record();
+            let (banned_user_info, banned_uuid, _banned_name) = default_shared_user_info();
+            let banned_client_id = DEFAULT_OWNER_CLIENT_ID + 1;
+            let shared_client_store = make_shared_client_store(vec![(
+                banned_client_id,
+                banned_user_info,
+                default_mock_client_channel(),
+            )]);
+            let mut room_model = MockRoomORM::new();
+            let room_uuid = room_record.uuid.clone();
+            room_model
+                .expect_list_bans()
+                .withf(move |target_room_uuid| target_room_uuid == room_uuid)
+                .once()
+                .returning(move |_| Ok(vec![banned_uuid.clone()]));
+            room_model.expect_create_player().never();
+
+            let mut room = Room::load(room_record, room_model, shared_client_store).unwrap();
+
+            let join_result = room.join(banned_client_id, None, false);
+            assert_eq!(join_result.unwrap_err().kind(), ErrorKind::BannedError);
+        }
+
+        #[test]
+        fn should_return_error_when_bans_cannot_be_loaded() {
+            let mut room_model = MockRoomORM::new();
+            room_model
+                .expect_list_bans()
+                .returning(|_| Err(Error::from(ErrorKind::QueryError)));
+
+            let load_result = Room::load(
+                make_room_record(),
+                room_model,
+                default_shared_client_store(),
+            );
+            assert_eq!(load_result.err().unwrap().kind(), ErrorKind::QueryError);
+        }
+
+        fn make_room_record() -> RoomRecord {
+            let (_owner_user_info, owner_uuid, _owner_name) = default_shared_user_info();
+            let now = SystemTime::now();
+
+            RoomRecord {
+                uuid: Uuid::new_v4().to_string(),
+                passphrase: None,
+                card_set: default_card_set(),
+                owner_uuid,
+                created_at: now,
+                last_updated_at: now,
+                options: RoomOptions::default(),
+                join_code: Some(String::from(DEFAULT_JOIN_CODE)),
+                closed_at: None,
+            }
+        }
+    }
+
+    mod join {
+        use super::*;
+
+        #[test]
+        fn should_return_already_joined_error_when_already_joined() {
+            let passphrase = None;
+            let mut room = make_room(passphrase.clone());
+
+            let join_result = room.join(DEFAULT_OWNER_CLIENT_ID, passphrase, false);
+            assert!(join_result.is_err());
+            assert_eq!(
+                join_result.unwrap_err().kind(),
+                ErrorKind::AlreadyJoinedError
+            );
+        }
+
+        #[test]
+        fn should_return_ok_when_joined_successfully() {
+            let passphrase = None;
+            let mut room = make_room(passphrase.clone());
+
+            let joiner_client_id = DEFAULT_OWNER_CLIENT_ID + 1;
+            let (joiner_user_info, _joiner_uuid, _joiner_name) = default_shared_user_info();
+            let joiner_client_channel = default_mock_client_channel();
+            {
+                let mut client_store = room.client_store.get_writable();
+                client_store.insert(
+                    joiner_client_id,
+                    make_mock_client(joiner_user_info, joiner_client_channel),
+                );
+            }
+
+            assert!(room.join(joiner_client_id, passphrase, false).is_ok());
+        }
+
+        #[test]
+        fn should_insert_client_id_into_players() {
+            let passphrase = None;
+            let mut room = make_room(passphrase.clone());
+
+            let joiner_client_id = insert_new_client(&room);
+
+            assert!(room.join(joiner_client_id, passphrase, false).is_ok());
+
+            assert_eq!(room.players.len(), 2);
+            assert!(is_member(&room, DEFAULT_OWNER_CLIENT_ID));
+            assert!(is_member(&room, joiner_client_id));
+        }
+
+        mod given_room_is_public {
+            use super::*;
+
+            #[test]
+            fn should_return_ok_when_passphrase_is_provided() {
+                let passphrase = None;
+                let mut room = make_room(passphrase.clone());
+
+                let joiner_client_id = insert_new_client(&room);
+
+                let passphrase = Some(String::from("Passphrase"));
+                assert!(room.join(joiner_client_id, passphrase, false).is_ok());
+            }
+        }
+
+        mod given_room_is_private {
+            use super::*;
+
+            #[test]
+            fn should_return_unauthenticated_error_when_passphrase_is_incorrect() {
+                let passphrase = Some(String::from("Passphrase"));
+                let mut room = make_room(passphrase.clone());
+
+                let joiner_client_id = insert_new_client(&room);
+
+                let passphrase = Some(String::from("Incorrect"));
+                let join_result = room.join(joiner_client_id, passphrase, false);
+                assert!(join_result.is_err());
+                assert_eq!(
+                    join_result.unwrap_err().kind(),
+                    ErrorKind::UnauthenticatedError
+                );
+            }
+
+            #[test]
+            fn should_return_unauthenticated_error_when_passphrase_is_not_provided() {
+                let passphrase = Some(String::from("Passphrase"));
+                let mut room = make_room(passphrase.clone());
+
+                let joiner_client_id = insert_new_client(&room);
+
+                let passphrase = None;
+                let join_result = room.join(joiner_client_id, passphrase, false);
+                assert!(join_result.is_err());
+                assert_eq!(
+                    join_result.unwrap_err().kind(),
+                    ErrorKind::UnauthenticatedError
+                );
+            }
+        }
+
+        #[test]
+        fn should_return_error_when_data_persistence_has_error() {
+            let passphrase = None;
+            let mut room = make_room(passphrase.clone());
+            room.room_model.checkpoint();
+            room.room_model
+                .expect_create_player()
+                .returning(|_| Err(Error::from(ErrorKind::InsertionError)));
+
+            let joiner_client_id = DEFAULT_OWNER_CLIENT_ID + 1;
+            let (joiner_user_info, _joiner_uuid, _joiner_name) = default_shared_user_info();
+            {
+                let mut client_store = room.client_store.get_writable();
+                client_store.insert(
+                    joiner_client_id,
+                    make_mock_client(joiner_user_info, default_mock_client_channel()),
+                );
+            }
+
+            let join_result = room.join(joiner_client_id, passphrase, false);
+            assert_eq!(join_result.unwrap_err().kind(), ErrorKind::InsertionError);
+            assert!(!is_member(&room, joiner_client_id));
+        }
+
+        #[test]
+        fn should_persist_new_player_to_database() {
+            let passphrase = None;
+            let mut room = make_room(passphrase.clone());
+
+            let joiner_client_id = DEFAULT_OWNER_CLIENT_ID + 1;
+            let (joiner_user_info, joiner_uuid, _joiner_name) = default_shared_user_info();
+            {
+                let mut client_store = room.client_store.get_writable();
+                client_store.insert(
+                    joiner_client_id,
+                    make_mock_client(joiner_user_info, default_mock_client_channel()),
+                );
+            }
+
+            let room_uuid = room.room_id.clone().unwrap();
+            room.room_model.checkpoint();
+            room.room_model
+                .expect_create_player()
+                .withf(move |params| {
+                    params.room_uuid == room_uuid && params.player_uuid == joiner_uuid
+                })
+                .once()
+                .returning(|_| Ok(()));
+
+            assert!(room.join(joiner_client_id, passphrase, false).is_ok());
+        }
+
+        #[test]
+        fn should_hand_room_over_to_owner_when_room_is_created_without_owner_client() {
+            let passphrase = None;
+            let mut room = make_room(passphrase.clone());
+            room.players.clear();
+
+            let owner_client_id = DEFAULT_OWNER_CLIENT_ID + 1;
+            let owner_user_info =
+                make_shared_user_info(room.owner_uuid.clone(), String::from("Calvin Lau"));
+            {
+                let mut client_store = room.client_store.get_writable();
+                client_store.insert(
+                    owner_client_id,
+                    make_mock_client(owner_user_info, default_mock_client_channel()),
+                );
+            }
+
+            assert!(room.join(owner_client_id, passphrase, false).is_ok());
+            assert!(room.is_owner(owner_client_id));
+        }
+
+        #[test]
+        fn should_return_missing_client_error_when_client_does_not_exist_in_client_store() {
+            let passphrase = None;
+            let mut room = make_room(passphrase.clone());
+
+            let joiner_client_id = DEFAULT_OWNER_CLIENT_ID + 1;
+
+            let join_result = room.join(joiner_client_id, passphrase, false);
+            assert_eq!(
+                join_result.unwrap_err().kind(),
+                ErrorKind::MissingClientError
+            );
+        }
+
+        #[test]
+        fn should_send_user_joined_message_to_all_players() {
+            let passphrase = None;
+            let mut room = make_room(passphrase.clone());
+
+            let joiner_client_id = DEFAULT_OWNER_CLIENT_ID + 1;
+            let (joiner_user_info, joiner_uuid, _joiner_name) = default_shared_user_info();
+            let joiner_client_channel = default_mock_client_channel();
+
+            {
+                let mut client_store = room.client_store.get_writable();
+                client_store.insert(
+                    joiner_client_id,
+                    make_mock_client(joiner_user_info, joiner_client_channel),
+                );
+            }
+            {
+                let expected_uuid = joiner_uuid.clone();
+                let mut client_store = room.client_store.get_writable();
+                let owner_client_channel = &mut client_store
+                    .get_mut(&DEFAULT_OWNER_CLIENT_ID)
+                    .unwrap()
+                    .channel;
+                owner_client_channel.checkpoint();
+
+                owner_client_channel
+                    .expect_do_send()
+                    .withf(move |res| match &res.msg {
+                        ResponseMessage::UserJoined(player_uuid) => player_uuid == &expected_uuid,
+                        _ => false,
+                    })
+                    .once()
+                    .return_const(Ok(()));
+            }
+            {
+                let mut client_store = room.client_store.get_writable();
+                let joiner_client_channel =
+                    &mut client_store.get_mut(&joiner_client_id).unwrap().channel;
+                joiner_client_channel.checkpoint();
+
+                joiner_client_channel
+                    .expect_do_send()
+                    .withf(move |res| match &res.msg {
+                        ResponseMessage::UserJoined(player_uuid) => player_uuid == &joiner_uuid,
+                        _ => false,
+                    })
+                    .once()
+                    .return_const(Ok(()));
+            }
+
+            assert!(room.join(joiner_client_id, passphrase, false).is_ok());
+        }
+
+        #[test]
+        fn should_return_ok_when_passphrase_is_provided() {
+            let passphrase = None;
+            let mut room = make_room(passphrase.clone());
+
+            let joiner_client_id = insert_new_client(&room);
+
+            let passphrase = Some(String::from("Passphrase"));
+            assert!(room.join(joiner_client_id, passphrase, false).is_ok());
+        }
+
+        #[test]
+        fn should_attach_another_client_of_joined_player_to_same_seat() {
+            let mut room = make_room(None);
+            let owner_uuid = room.owner_uuid.clone();
+
+            let owner_tab_client_id = join_new_client(&mut room, owner_uuid.clone());
+
+            assert_eq!(room.players.len(), 1);
+            assert_eq!(room.players[&owner_uuid].len(), 2);
+            assert!(room.is_owner(owner_tab_client_id));
+        }
+
+        #[test]
+        fn should_not_broadcast_user_joined_when_joined_player_opens_another_client() {
+            let mut room = make_room(None);
+            {
+                let mut client_store = room.client_store.get_writable();
+                let client_channel = &mut client_store
+                    .get_mut(&DEFAULT_OWNER_CLIENT_ID)
+                    .unwrap()
+                    .channel;
+                client_channel.checkpoint();
+                client_channel.expect_do_send().never();
+            }
+
+            let owner_uuid = room.owner_uuid.clone();
+            join_new_client(&mut room, owner_uuid);
+        }
+    }
+
+    mod kick {
+        use super::*;
+
+        #[test]
+        fn should_send_kicked_message_to_every_client_of_kicked_player() {
+            let mut room = make_room(None);
+            let (joiner_client_id, joiner_uuid) = join_new_player(&mut room);
+            let joiner_tab_client_id = join_new_client(&mut room, joiner_uuid.clone());
+            for client_id in [joiner_client_id, joiner_tab_client_id].iter() {
+                expect_response(&room, *client_id, |res| match res {
+                    ResponseMessage::Kicked(_) => true,
+                    _ => false,
+                });
+            }
+
+            assert!(room
+                .kick(DEFAULT_OWNER_CLIENT_ID, joiner_uuid.clone(), false)
+                .is_ok());
+
+            assert!(!room.players.contains_key(&joiner_uuid));
+        }
+
+        #[test]
+        fn should_return_forbidden_error_when_kicker_is_not_room_owner() {
+            let mut room = make_room(None);
+            let (joiner_client_id, joiner_uuid) = join_new_player(&mut room);
+
+            let kick_result = room.kick(joiner_client_id, joiner_uuid, false);
+            assert_eq!(kick_result.unwrap_err().kind(), ErrorKind::ForbiddenError);
+        }
+
+        #[test]
+        fn should_return_player_not_found_error_when_player_is_not_in_room() {
+            let mut room = make_room(None);
+
+            let kick_result = room.kick(DEFAULT_OWNER_CLIENT_ID, Uuid::new_v4().to_string(), false);
+            assert_eq!(kick_result.unwrap_err().kind(), ErrorKind::PlayerNotFound);
+        }
+
+        #[test]
+        fn should_return_invalid_params_error_when_kicking_room_owner() {
+            let mut room = make_room(None);
+            let owner_uuid = room.find_player_uuid(DEFAULT_OWNER_CLIENT_ID).unwrap();
+
+            let kick_result = room.kick(DEFAULT_OWNER_CLIENT_ID, owner_uuid, false);
+            assert_eq!(kick_result.unwrap_err().kind(), ErrorKind::InvalidParams);
+        }
+
+        #[test]
+        fn should_remove_player_from_room() {
+            let mut room = make_room(None);
+            let (joiner_client_id, joiner_uuid) = join_new_player(&mut room);
+
+            assert!(room
+                .kick(DEFAULT_OWNER_CLIENT_ID, joiner_uuid, false)
+                .is_ok());
+
+            assert!(!is_member(&room, joiner_client_id));
+            assert!(is_member(&room, DEFAULT_OWNER_CLIENT_ID));
+        }
+
+        #[test]
+        fn should_send_kicked_message_to_kicked_player() {
+            let mut room = make_room(None);
+            let (joiner_client_id, joiner_uuid) = join_new_player(&mut room);
+            {
+                let mut client_store = room.client_store.get_writable();
+                let joiner_client_channel =
+                    &mut client_store.get_mut(&joiner_client_id).unwrap().channel;
+                joiner_client_channel.checkpoint();
+
+                joiner_client_channel
+                    .expect_do_send()
+                    .withf(|res| match &res.msg {
+                        ResponseMessage::Kicked(kicked) => !kicked.banned,
+                        _ => false,
+                    })
+                    .once()
+                    .return_const(Ok(()));
+            }
+
+            assert!(room
+                .kick(DEFAULT_OWNER_CLIENT_ID, joiner_uuid, false)
+                .is_ok());
+        }
+
+        #[test]
+        fn should_allow_kicked_player_to_rejoin() {
+            let mut room = make_room(None);
+            let (joiner_client_id, joiner_uuid) = join_new_player(&mut room);
+
+            assert!(room
+                .kick(DEFAULT_OWNER_CLIENT_ID, joiner_uuid, false)
+                .is_ok());
+
+            assert!(room.join(joiner_client_id, None, false).is_ok());
+        }
+    }
+
+    mod ban {
+        use super::*;
+
+        #[test]
+        fn should_persist_ban_into_database() {
+            let mut room = make_room(None);
+            let (_joiner_client_id, joiner_uuid) = join_new_player(&mut room);
+
+            let room_uuid = room.room_id.clone().unwrap();
+            let expected_user_uuid = joiner_uuid.clone();
+            room.room_model
+                .expect_create_ban()
+                .withf(move |params| {
+                    params.room_uuid == room_uuid && params.user_uuid == expected_user_uuid
+                })
+                .once()
+                .returning(make_room_ban_record);
+
+            assert!(room
+                .kick(DEFAULT_OWNER_CLIENT_ID, joiner_uuid, true)
+                .is_ok());
+        }
+
+        #[test]
+        fn should_return_error_when_database_persistence_has_error() {
+            let mut room = make_room(None);
+            let (joiner_client_id, joiner_uuid) = join_new_player(&mut room);
+
+            room.room_model
+                .expect_create_ban()
+                .once()
+                .return_const(Err(Error::from(ErrorKind::InsertionError)));
+
+            let ban_result = room.kick(DEFAULT_OWNER_CLIENT_ID, joiner_uuid, true);
+            assert_eq!(ban_result.unwrap_err().kind(), ErrorKind::InsertionError);
+            assert!(is_member(&room, joiner_client_id));
+        }
+
+        #[test]
+        fn should_send_kicked_message_with_banned_flag_to_banned_player() {
+            let mut room = make_room(None);
+            let (joiner_client_id, joiner_uuid) = join_new_player(&mut room);
+            room.room_model
+                .expect_create_ban()
+                .returning(make_room_ban_record);
+            {
+                let mut client_store = room.client_store.get_writable();
+                let joiner_client_channel =
+                    &mut client_store.get_mut(&joiner_client_id).unwrap().channel;
+                joiner_client_channel.checkpoint();
+
+                joiner_client_channel
+                    .expect_do_send()
+                    .withf(|res| match &res.msg {
+                        ResponseMessage::Kicked(kicked) => kicked.banned,
+                        _ => false,
+                    })
+                    .once()
+                    .return_const(Ok(()));
+            }
+
+            assert!(room
+                .kick(DEFAULT_OWNER_CLIENT_ID, joiner_uuid, true)
+                .is_ok());
+        }
+
+        #[test]
+        fn should_reject_banned_player_from_rejoining() {
+            let mut room = make_room(None);
+            let (joiner_client_id, joiner_uuid) = join_new_player(&mut room);
+            room.room_model
+                .expect_create_ban()
+                .returning(make_room_ban_record);
+
+            assert!(room
+                .kick(DEFAULT_OWNER_CLIENT_ID, joiner_uuid, true)
+                .is_ok());
+
+            let join_result = room.join(joiner_client_id, None, false);
+            assert_eq!(join_result.unwrap_err().kind(), ErrorKind::BannedError);
+        }
+
+        #[test]
+        fn should_ban_player_who_is_not_in_room() {
+            let mut room = make_room(None);
+            room.room_model
+                .expect_create_ban()
+                .once()
+                .returning(make_room_ban_record);
+
+            let player_uuid = Uuid::new_v4().to_string();
+            assert!(room
+                .kick(DEFAULT_OWNER_CLIENT_ID, player_uuid.clone(), true)
+                .is_ok());
+            assert!(room.banned_player_uuids.contains(&player_uuid));
+        }
+    }
+
+    mod update_config {
+        use super::*;
+
+        use crate::poker::deck::DeckPreset;
+        use crate::poker::game::Game;
+
+        #[test]
+        fn should_return_forbidden_error_when_updater_is_not_room_owner() {
+            let mut room = make_room(None);
+            let (joiner_client_id, _joiner_uuid) = join_new_player(&mut room);
+
+            let update_result =
+                room.update_config(joiner_client_id, make_update_room_config_params());
+            assert_eq!(update_result.unwrap_err().kind(), ErrorKind::ForbiddenError);
+        }
+
+        #[test]
+        fn should_persist_updated_config_into_database() {
+            let mut room = make_room(None);
+
+            let room_uuid = room.room_id.clone().unwrap();
+            room.room_model
+                .expect_update()
+                .withf(move |params| {
+                    params.uuid == room_uuid
+                        && params.passphrase == Some(String::from("New Passphrase"))
+                        && params.card_set == vec![String::from("S"), String::from("M")]
+                        && params.options.locked
+                })
+                .once()
+                .return_const(Ok(()));
+
+            assert!(room
+                .update_config(DEFAULT_OWNER_CLIENT_ID, make_update_room_config_params())
+                .is_ok());
+            assert!(room.is_private());
+            assert_eq!(
+                room.card_labels(),
+                vec![String::from("S"), String::from("M")]
+            );
+            assert!(room.options.locked);
+        }
+
+        #[test]
+        fn should_keep_absent_fields_unchanged() {
+            let passphrase = Some(String::from("Passphrase"));
+            let mut room = make_room(passphrase.clone());
+
+            room.room_model
+                .expect_update()
+                .withf(move |params| {
+                    params.passphrase == passphrase && params.card_set == default_card_set()
+                })
+                .once()
+                .return_const(Ok(()));
+
+            let params = UpdateRoomConfigParams {
+                passphrase: None,
+                card_set: None,
+                deck_preset: None,
+                options: Some(RoomOptions {
+                    locked: true,
+                    ..RoomOptions::default()
+                }),
+            };
+            assert!(room.update_config(DEFAULT_OWNER_CLIENT_ID, params).is_ok());
+        }
+
+        #[test]
+        fn should_remove_passphrase_when_explicitly_cleared() {
+            let mut room = make_room(Some(String::from("Passphrase")));
+            room.room_model.expect_update().return_const(Ok(()));
+
+            let params = UpdateRoomConfigParams {
+                passphrase: Some(None),
+                card_set: None,
+                deck_preset: None,
+                options: None,
+            };
+            assert!(room.update_config(DEFAULT_OWNER_CLIENT_ID, params).is_ok());
+            assert!(!room.is_private());
+        }
+
+        #[test]
+        fn should_return_error_and_keep_config_when_database_persistence_has_error() {
+            let mut room = make_room(None);
+            room.room_model
+                .expect_update()
+                .return_const(Err(Error::from(ErrorKind::UpdateError)));
+
+            let update_result =
+                room.update_config(DEFAULT_OWNER_CLIENT_ID, make_update_room_config_params());
+            assert_eq!(update_result.unwrap_err().kind(), ErrorKind::UpdateError);
+            assert_eq!(room.card_labels(), default_card_set());
+            assert!(!room.options.locked);
+        }
+
+        #[test]
+        fn should_return_game_in_progress_error_when_played_card_is_removed_from_card_set() {
+            let mut room = make_room(None);
+            room.room_model.expect_update().never().return_const(Ok(()));
+            room.current_game = Some(make_game_with_hand(
+                room.owner_uuid.clone(),
+                String::from("3"),
+            ));
+
+            let update_result =
+                room.update_config(DEFAULT_OWNER_CLIENT_ID, make_update_room_config_params());
+            assert_eq!(
+                update_result.unwrap_err().kind(),
+                ErrorKind::GameInProgressError
+            );
+        }
+
+        #[test]
+        fn should_allow_card_set_change_which_keeps_played_cards() {
+            let mut room = make_room(None);
+            room.room_model.expect_update().return_const(Ok(()));
+            room.current_game = Some(make_game_with_hand(
+                room.owner_uuid.clone(),
+                String::from("3"),
+            ));
+
+            let params = UpdateRoomConfigParams {
+                passphrase: None,
+                card_set: Some(vec![String::from("3"), String::from("8")]),
+                deck_preset: None,
+                options: None,
+            };
+            assert!(room.update_config(DEFAULT_OWNER_CLIENT_ID, params).is_ok());
+        }
+
+        #[test]
+        fn should_store_trimmed_card_labels_which_can_be_played() {
+            let mut room = make_room(None);
+            room.room_model
+                .expect_update()
+                .withf(|params| params.card_set == vec![String::from("3"), String::from("5")])
+                .return_const(Ok(()));
+
+            let params = UpdateRoomConfigParams {
+                passphrase: None,
+                card_set: Some(vec![String::from(" 3"), String::from("5 ")]),
+                deck_preset: None,
+                options: None,
+            };
+            assert!(room.update_config(DEFAULT_OWNER_CLIENT_ID, params).is_ok());
+            assert_eq!(
+                room.card_labels(),
+                vec![String::from("3"), String::from("5")]
+            );
+
+            room.start_game(DEFAULT_OWNER_CLIENT_ID, make_start_game_params())
+                .unwrap();
+            assert!(room
+                .play_card(DEFAULT_OWNER_CLIENT_ID, make_play_card_params("3"))
+                .is_ok());
+        }
+
+        #[test]
+        fn should_send_config_updated_message_to_all_players() {
+            let mut room = make_room(None);
+            let (joiner_client_id, _joiner_uuid) = join_new_player(&mut room);
+            room.room_model.expect_update().return_const(Ok(()));
+            {
+                let mut client_store = room.client_store.get_writable();
+                for client_id in [DEFAULT_OWNER_CLIENT_ID, joiner_client_id].iter() {
+                    let client_channel = &mut client_store.get_mut(client_id).unwrap().channel;
+                    client_channel.checkpoint();
+
+                    client_channel
+                        .expect_do_send()
+                        .withf(|res| match &res.msg {
+                            ResponseMessage::ConfigUpdated(config) => {
+                                config.private && config.options.locked
+                            }
+                            _ => false,
+                        })
+                        .once()
+                        .return_const(Ok(()));
+                }
+            }
+
+            assert!(room
+                .update_config(DEFAULT_OWNER_CLIENT_ID, make_update_room_config_params())
+                .is_ok());
+        }
+
+        #[test]
+        fn should_update_card_set_to_requested_deck_preset() {
+            let mut room = make_room(None);
+            room.room_model
+                .expect_update()
+                .withf(|params| params.card_set == DeckPreset::TShirt.card_set())
+                .once()
+                .return_const(Ok(()));
+
+            let params = UpdateRoomConfigParams {
+                passphrase: None,
+                card_set: None,
+                deck_preset: Some(DeckPreset::TShirt),
+                options: None,
+            };
+            assert!(room.update_config(DEFAULT_OWNER_CLIENT_ID, params).is_ok());
+            assert_eq!(room.card_labels(), DeckPreset::TShirt.card_set());
+        }
+
+        #[test]
+        fn should_return_invalid_card_set_error_when_card_set_is_invalid() {
+            let mut room = make_room(None);
+            room.room_model.expect_update().never().return_const(Ok(()));
+
+            let params = UpdateRoomConfigParams {
+                passphrase: None,
+                card_set: Some(vec![String::from("1"), String::from("1")]),
+                deck_preset: None,
+                options: None,
+            };
+            let update_result = room.update_config(DEFAULT_OWNER_CLIENT_ID, params);
+            assert_eq!(update_result.unwrap_err().kind(), ErrorKind::InvalidCardSet);
+        }
+
+        #[test]
+        fn should_reject_new_players_when_room_is_locked() {
+            let mut room = make_room(None);
+            room.room_model.expect_update().return_const(Ok(()));
+
+            let params = UpdateRoomConfigParams {
+                passphrase: None,
+                card_set: None,
+                deck_preset: None,
+                options: Some(RoomOptions {
+                    locked: true,
+                    ..RoomOptions::default()
+                }),
+            };
+            assert!(room.update_config(DEFAULT_OWNER_CLIENT_ID, params).is_ok());
+
+            let joiner_client_id = insert_new_client(&room);
+            let join_result = room.join(joiner_client_id, None, false);
+            assert_eq!(join_result.unwrap_err().kind(), ErrorKind::RoomLockedError);
+        }
+
+        #[test]
+        fn should_return_forbidden_error_when_updating_user_is_not_room_owner() {
+            let mut room = make_room(None);
+            room.room_model.expect_update().never().return_const(Ok(()));
+
+            let update_result =
+                room.update_config_as_user("another-user-uuid", make_update_room_config_params());
+            assert_eq!(update_result.unwrap_err().kind(), ErrorKind::ForbiddenError);
+        }
+
+        #[test]
+        fn should_return_updated_config_when_updated_by_owner_user() {
+            let mut room = make_room(None);
+            room.room_model.expect_update().once().return_const(Ok(()));
+
+            let owner_uuid = room.owner_uuid.clone();
+            let config = room
+                .update_config_as_user(&owner_uuid, make_update_room_config_params())
+                .unwrap();
+            assert!(config.private);
+            assert_eq!(config.card_set, vec![String::from("S"), String::from("M")]);
+            assert!(config.options.locked);
+        }
+
+        fn make_update_room_config_params() -> UpdateRoomConfigParams {
+            UpdateRoomConfigParams {
+                passphrase: Some(Some(String::from("New Passphrase"))),
+                card_set: Some(vec![String::from("S"), String::from("M")]),
+                deck_preset: None,
+                options: Some(RoomOptions {
+                    locked: true,
+                    ..RoomOptions::default()
+                }),
+            }
+        }
+
+        fn make_game_with_hand(player_uuid: UuidType, card: CardLabel) -> Game {
+            let mut game = Game::new(String::from("Story"), None);
+            game.players_hands.insert(player_uuid, card);
+
+            game
+        }
+    }
+
+    mod close {
+        use super::*;
+
+        #[test]
+        fn should_return_forbidden_error_when_user_is_not_room_owner() {
+            let mut room = make_room(None);
+            room.room_model.expect_close().never().return_const(Ok(()));
+
+            let close_result = room.close("another-user-uuid");
+            assert_eq!(close_result.unwrap_err().kind(), ErrorKind::ForbiddenError);
+        }
+
+        #[test]
+        fn should_persist_closed_room_into_database() {
+            let mut room = make_room(None);
+
+            let room_uuid = room.room_id.clone().unwrap();
+            room.room_model
+                .expect_close()
+                .withf(move |target_uuid| target_uuid == room_uuid)
+                .once()
+                .return_const(Ok(()));
+
+            let owner_uuid = room.owner_uuid.clone();
+            assert!(room.close(&owner_uuid).is_ok());
+        }
+
+        #[test]
+        fn should_notify_and_remove_all_players() {
+            let mut room = make_room(None);
+            let (joiner_client_id, _joiner_uuid) = join_new_player(&mut room);
+            room.room_model.expect_close().return_const(Ok(()));
+
+            let room_uuid = room.room_id.clone().unwrap();
+            expect_response(&room, joiner_client_id, move |res| match res {
+                ResponseMessage::RoomClosed(closed_room_uuid) => closed_room_uuid == &room_uuid,
+                _ => false,
+            });
+
+            let owner_uuid = room.owner_uuid.clone();
+            assert!(room.close(&owner_uuid).is_ok());
+            assert!(room.players.is_empty());
+            assert!(!room.is_owner(DEFAULT_OWNER_CLIENT_ID));
+        }
+    }
+
+    mod start_game {
+        use super::*;
+
+        #[test]
+        fn should_return_forbidden_error_when_starter_is_not_room_owner() {
+            let mut room = make_room(None);
+            let (joiner_client_id, _joiner_uuid) = join_new_player(&mut room);
+
+            let start_result = room.start_game(joiner_client_id, make_start_game_params());
+            assert_eq!(start_result.unwrap_err().kind(), ErrorKind::ForbiddenError);
+        }
+
+        #[test]
+        fn should_return_game_in_progress_error_when_current_game_is_not_revealed() {
+            let mut room = make_room(None);
+            room.start_game(DEFAULT_OWNER_CLIENT_ID, make_start_game_params())
+                .unwrap();
+
+            let start_result = room.start_game(DEFAULT_OWNER_CLIENT_ID, make_start_game_params());
+            assert_eq!(
+                start_result.unwrap_err().kind(),
+                ErrorKind::GameInProgressError
+            );
+        }
+
+        #[test]
+        fn should_start_new_game_when_current_game_is_revealed() {
+            let mut room = make_room(None);
+            room.start_game(DEFAULT_OWNER_CLIENT_ID, make_start_game_params())
+                .unwrap();
+            room.reveal_cards(DEFAULT_OWNER_CLIENT_ID).unwrap();
+
+            assert!(room
+                .start_game(DEFAULT_OWNER_CLIENT_ID, make_start_game_params())
+                .is_ok());
+            assert!(!room.current_game.as_ref().unwrap().revealed);
+        }
+
+        #[test]
+        fn should_persist_game_as_current_game_of_room() {
+            let mut room = make_room(None);
+            let room_uuid = room.uuid().unwrap();
+            room.room_model.checkpoint();
+            room.room_model
+                .expect_create_game()
+                .withf(move |params| params.room_uuid == room_uuid && params.title == "Story")
+                .once()
+                .returning(|params| Ok(make_game_record(params)));
+
+            assert!(room
+                .start_game(DEFAULT_OWNER_CLIENT_ID, make_start_game_params())
+                .is_ok());
+            assert_eq!(
+                room.current_game.as_ref().unwrap().title,
+                String::from("Story")
+            );
+        }
+
+        #[test]
+        fn should_not_start_game_when_it_cannot_be_persisted() {
+            let mut room = make_room(None);
+            room.room_model.checkpoint();
+            room.room_model
+                .expect_create_game()
+                .returning(|_| Err(Error::from(ErrorKind::InsertionError)));
+
+            let start_result = room.start_game(DEFAULT_OWNER_CLIENT_ID, make_start_game_params());
+            assert_eq!(start_result.unwrap_err().kind(), ErrorKind::InsertionError);
+            assert!(room.current_game.is_none());
+        }
+
+        #[test]
+        fn should_broadcast_game_started_to_players() {
+            let mut room = make_room(None);
+            let (joiner_client_id, _joiner_uuid) = join_new_player(&mut room);
+            expect_response(&room, joiner_client_id, |res| match res {
+                ResponseMessage::GameStarted(game) => game.title == "Story",
+                _ => false,
+            });
+
+            assert!(room
+                .start_game(DEFAULT_OWNER_CLIENT_ID, make_start_game_params())
+                .is_ok());
+        }
+    }
+
+    mod play_card {
+        use super::*;
+
+        #[test]
+        fn should_replace_card_played_from_another_client_of_player() {
+            let mut room = make_room_with_game();
+            let owner_uuid = room.owner_uuid.clone();
+            let owner_tab_client_id = join_new_client(&mut room, owner_uuid.clone());
+
+            room.play_card(DEFAULT_OWNER_CLIENT_ID, make_play_card_params("3"))
+                .unwrap();
+            room.play_card(owner_tab_client_id, make_play_card_params("5"))
+                .unwrap();
+
+            let players_hands = &room.current_game.as_ref().unwrap().players_hands;
+            assert_eq!(players_hands.len(), 1);
+            assert_eq!(players_hands[&owner_uuid], "5");
+        }
+
+        #[test]
+        fn should_return_game_not_found_error_when_no_game_is_started() {
+            let mut room = make_room(None);
+
+            let play_result = room.play_card(DEFAULT_OWNER_CLIENT_ID, make_play_card_params("3"));
+            assert_eq!(play_result.unwrap_err().kind(), ErrorKind::GameNotFound);
+        }
+
+        #[test]
+        fn should_return_invalid_params_error_when_card_is_not_in_card_set() {
+            let mut room = make_room_with_game();
+
+            let play_result = room.play_card(DEFAULT_OWNER_CLIENT_ID, make_play_card_params("4"));
+            assert_eq!(play_result.unwrap_err().kind(), ErrorKind::InvalidParams);
+        }
+
+        #[test]
+        fn should_return_game_in_progress_error_when_cards_are_revealed() {
+            let mut room = make_room_with_game();
+            room.reveal_cards(DEFAULT_OWNER_CLIENT_ID).unwrap();
+
+            let play_result = room.play_card(DEFAULT_OWNER_CLIENT_ID, make_play_card_params("3"));
+            assert_eq!(
+                play_result.unwrap_err().kind(),
+                ErrorKind::GameInProgressError
+            );
+        }
+
+        #[test]
+        fn should_replace_previously_played_card() {
+            let mut room = make_room_with_game();
+            room.play_card(DEFAULT_OWNER_CLIENT_ID, make_play_card_params("3"))
+                .unwrap();
+
+            assert!(room
+                .play_card(DEFAULT_OWNER_CLIENT_ID, make_play_card_params("5"))
+                .is_ok());
+
+            let players_hands = &room.current_game.as_ref().unwrap().players_hands;
+            assert_eq!(players_hands.len(), 1);
+            assert_eq!(players_hands[&room.owner_uuid], "5");
+        }
+
+        #[test]
+        fn should_broadcast_player_without_card() {
+            let mut room = make_room_with_game();
+            let (joiner_client_id, joiner_uuid) = join_new_player(&mut room);
+            expect_response(&room, DEFAULT_OWNER_CLIENT_ID, move |res| match res {
+                ResponseMessage::CardPlayed(player_uuid) => player_uuid == &joiner_uuid,
+                _ => false,
+            });
+
+            assert!(room
+                .play_card(joiner_client_id, make_play_card_params("3"))
+                .is_ok());
+        }
+    }
+
+    mod reveal_cards {
+        use super::*;
+
+        #[test]
+        fn should_return_forbidden_error_when_revealer_is_not_room_owner() {
+            let mut room = make_room_with_game();
+            let (joiner_client_id, _joiner_uuid) = join_new_player(&mut room);
+
+            let reveal_result = room.reveal_cards(joiner_client_id);
+            assert_eq!(reveal_result.unwrap_err().kind(), ErrorKind::ForbiddenError);
+        }
+
+        #[test]
+        fn should_return_game_not_found_error_when_no_game_is_started() {
+            let mut room = make_room(None);
+
+            let reveal_result = room.reveal_cards(DEFAULT_OWNER_CLIENT_ID);
+            assert_eq!(reveal_result.unwrap_err().kind(), ErrorKind::GameNotFound);
+        }
+
+        #[test]
+        fn should_broadcast_hands_sorted_by_card_ordinal_with_stats() {
+            let mut room = make_room_with_game();
+            let (joiner_client_id, joiner_uuid) = join_new_player(&mut room);
+            room.play_card(DEFAULT_OWNER_CLIENT_ID, make_play_card_params("5"))
+                .unwrap();
+            room.play_card(joiner_client_id, make_play_card_params("1"))
+                .unwrap();
+            expect_response(&room, DEFAULT_OWNER_CLIENT_ID, move |res| match res {
+                ResponseMessage::CardsRevealed(revealed) => {
+                    revealed.hands.len() == 2
+                        && revealed.hands[0].player_uuid == joiner_uuid
+                        && revealed.hands[0].card == "1"
+                        && revealed.hands[1].card == "5"
+                        && revealed.stats.average == Some(3.0)
+                        && !revealed.stats.consensus
+                }
+                _ => false,
+            });
+
+            assert!(room.reveal_cards(DEFAULT_OWNER_CLIENT_ID).is_ok());
+            assert!(room.current_game.as_ref().unwrap().revealed);
+        }
+    }
+
+    mod countdown {
+        use super::*;
+
+        #[test]
+        fn start_game_should_return_invalid_params_error_when_countdown_is_zero() {
+            let mut room = make_room(None);
+
+            let start_result =
+                room.start_game(DEFAULT_OWNER_CLIENT_ID, make_timeboxed_start_game_params(0));
+            assert_eq!(start_result.unwrap_err().kind(), ErrorKind::InvalidParams);
+        }
+
+        #[test]
+        fn start_game_should_set_deadline_from_clock() {
+            let now = Instant::now();
+            let mut room = make_room(None);
+            room.clock = make_clock(now);
+
+            room.start_game(
+                DEFAULT_OWNER_CLIENT_ID,
+                make_timeboxed_start_game_params(60),
+            )
+            .unwrap();
+
+            assert_eq!(
+                room.current_game.as_ref().unwrap().deadline,
+                Some(now + Duration::from_secs(60))
+            );
+        }
+
+        #[test]
+        fn tick_countdown_should_broadcast_seconds_left() {
+            let now = Instant::now();
+            let (mut room, game_uuid, deadline) = make_room_with_timeboxed_game(now, 60);
+            room.clock = make_clock(now + Duration::from_millis(20_500));
+            expect_response(&room, DEFAULT_OWNER_CLIENT_ID, |res| match res {
+                ResponseMessage::CountdownTicked(tick) => tick.secs_left == 40,
+                _ => false,
+            });
+
+            assert!(room.tick_countdown(&game_uuid, deadline));
+            assert!(!room.current_game.as_ref().unwrap().revealed);
+        }
+
+        #[test]
+        fn tick_countdown_should_reveal_cards_when_countdown_runs_out() {
+            let now = Instant::now();
+            let (mut room, game_uuid, deadline) = make_room_with_timeboxed_game(now, 60);
+            room.clock = make_clock(now + Duration::from_secs(61));
+            expect_response(&room, DEFAULT_OWNER_CLIENT_ID, |res| match res {
+                ResponseMessage::CardsRevealed(_) => true,
+                _ => false,
+            });
+
+            assert!(!room.tick_countdown(&game_uuid, deadline));
+            assert!(room.current_game.as_ref().unwrap().revealed);
+        }
+
+        #[test]
+        fn tick_countdown_should_mark_players_who_never_played_as_abstained() {
+            let now = Instant::now();
+            let (mut room, game_uuid, deadline) = make_room_with_timeboxed_game(now, 60);
+            let (joiner_client_id, joiner_uuid) = join_new_player(&mut room);
+            let owner_uuid = room.find_player_uuid(DEFAULT_OWNER_CLIENT_ID).unwrap();
+            room.play_card(joiner_client_id, make_play_card_params("3"))
+                .unwrap();
+            room.clock = make_clock(now + Duration::from_secs(60));
+            expect_response(&room, joiner_client_id, move |res| match res {
+                ResponseMessage::CardsRevealed(revealed) => {
+                    revealed.hands.len() == 1
+                        && revealed.hands[0].player_uuid == joiner_uuid
+                        && revealed.abstained == vec![owner_uuid.clone()]
+                }
+                _ => false,
+            });
+
+            assert!(!room.tick_countdown(&game_uuid, deadline));
+        }
+
+        #[test]
+        fn tick_countdown_should_stop_when_cards_are_revealed_manually() {
+            let now = Instant::now();
+            let (mut room, game_uuid, deadline) = make_room_with_timeboxed_game(now, 60);
+            room.reveal_cards(DEFAULT_OWNER_CLIENT_ID).unwrap();
+
+            assert!(!room.tick_countdown(&game_uuid, deadline));
+        }
+
+        #[test]
+        fn tick_countdown_should_stop_when_game_is_replaced() {
+            let now = Instant::now();
+            let (mut room, game_uuid, deadline) = make_room_with_timeboxed_game(now, 60);
+            room.reveal_cards(DEFAULT_OWNER_CLIENT_ID).unwrap();
+            room.start_game(
+                DEFAULT_OWNER_CLIENT_ID,
+                make_timeboxed_start_game_params(60),
+            )
+            .unwrap();
+
+            assert!(!room.tick_countdown(&game_uuid, deadline));
+            assert!(!room.current_game.as_ref().unwrap().revealed);
+        }
+
+        #[test]
+        fn tick_countdown_should_stop_countdown_of_previous_attempt() {
+            let now = Instant::now();
+            let (mut room, game_uuid, deadline) = make_room_with_timeboxed_game(now, 60);
+            room.clock = make_clock(now + Duration::from_secs(10));
+            room.reset_game(
+                DEFAULT_OWNER_CLIENT_ID,
+                ResetGameParams {
+                    countdown_secs: Some(60),
+                },
+            )
+            .unwrap();
+            let new_deadline = room.current_game.as_ref().unwrap().deadline;
+
+            assert!(!room.tick_countdown(&game_uuid, deadline));
+            assert!(room.tick_countdown(&game_uuid, new_deadline));
+        }
+
+        #[test]
+        fn tick_countdown_should_not_push_events_out_of_replay_buffer() {
+            let now = Instant::now();
+            let (mut room, game_uuid, deadline) = make_room_with_timeboxed_game(now, 600);
+            let (joiner_client_id, _joiner_uuid) = join_new_player(&mut room);
+            let last_seq = room.event_seq;
+            room.detach(joiner_client_id);
+            room.play_card(DEFAULT_OWNER_CLIENT_ID, make_play_card_params("5"))
+                .unwrap();
+            for _ in 0..MAX_BUFFERED_EVENTS + 1 {
+                assert!(room.tick_countdown(&game_uuid, deadline));
+            }
+            {
+                let mut client_store = room.client_store.get_writable();
+                let client_channel = &mut client_store.get_mut(&joiner_client_id).unwrap().channel;
+                client_channel.checkpoint();
+                client_channel
+                    .expect_do_send()
+                    .withf(move |frame| match frame.msg {
+                        ResponseMessage::CardPlayed(_) => frame.seq == Some(last_seq + 1),
+                        _ => false,
+                    })
+                    .once()
+                    .return_const(Ok(()));
+            }
+
+            assert!(room.resume(joiner_client_id, Some(last_seq)).is_ok());
+            assert_eq!(room.event_seq, last_seq + 1);
+        }
+
+        fn make_room_with_timeboxed_game(
+            now: Instant,
+            countdown_secs: u64,
+        ) -> (
+            MockRoom,
+            UuidType,        // Game Uuid
+            Option<Instant>, // Deadline
+        ) {
+            let mut room = make_room(None);
+            room.clock = make_clock(now);
+            room.start_game(
+                DEFAULT_OWNER_CLIENT_ID,
+                make_timeboxed_start_game_params(countdown_secs),
+            )
+            .expect("Game should be started");
+            let game = room.current_game.as_ref().unwrap();
+            let (game_uuid, deadline) = (game.uuid.clone(), game.deadline);
+
+            (room, game_uuid, deadline)
+        }
+
+        fn make_timeboxed_start_game_params(countdown_secs: u64) -> StartGameParams {
+            StartGameParams {
+                countdown_secs: Some(countdown_secs),
+                ..make_start_game_params()
+            }
+        }
+
+        fn make_clock(now: Instant) -> Box<dyn Clock> {
+            let mut clock = MockClock::new();
+            clock.expect_now().return_const(now);
+
+            Box::new(clock)
+        }
+    }
+
+    mod auto_reveal {
+        use super::*;
+
+        #[test]
+        fn should_count_player_with_multiple_clients_once() {
+            let mut room = make_auto_reveal_room_with_game();
+            let owner_uuid = room.owner_uuid.clone();
+            join_new_client(&mut room, owner_uuid);
+
+            room.play_card(DEFAULT_OWNER_CLIENT_ID, make_play_card_params("3"))
+                .unwrap();
+
+            assert!(room.current_game.as_ref().unwrap().revealed);
+        }
+
+        #[test]
+        fn should_reveal_cards_when_every_player_has_played() {
+            let mut room = make_auto_reveal_room_with_game();
+            let (joiner_client_id, _joiner_uuid) = join_new_player(&mut room);
+            room.play_card(DEFAULT_OWNER_CLIENT_ID, make_play_card_params("3"))
+                .unwrap();
+            assert!(!room.current_game.as_ref().unwrap().revealed);
+
+            room.play_card(joiner_client_id, make_play_card_params("5"))
+                .unwrap();
+
+            assert!(room.current_game.as_ref().unwrap().revealed);
+        }
+
+        #[test]
+        fn should_not_reveal_cards_when_option_is_disabled() {
+            let mut room = make_room_with_game();
+
+            room.play_card(DEFAULT_OWNER_CLIENT_ID, make_play_card_params("3"))
+                .unwrap();
+
+            assert!(!room.current_game.as_ref().unwrap().revealed);
+        }
+
+        #[test]
+        fn should_not_wait_for_spectators() {
+            let mut room = make_auto_reveal_room_with_game();
+            join_new_spectator(&mut room);
+
+            room.play_card(DEFAULT_OWNER_CLIENT_ID, make_play_card_params("3"))
+                .unwrap();
+
+            assert!(room.current_game.as_ref().unwrap().revealed);
+        }
+
+        #[test]
+        fn should_reveal_cards_when_last_player_who_has_not_played_leaves() {
+            let mut room = make_auto_reveal_room_with_game();
+            let (joiner_client_id, joiner_uuid) = join_new_player(&mut room);
+            room.play_card(DEFAULT_OWNER_CLIENT_ID, make_play_card_params("3"))
+                .unwrap();
+
+            room.leave(joiner_client_id, joiner_uuid);
+
+            assert!(room.current_game.as_ref().unwrap().revealed);
+        }
+
+        #[test]
+        fn should_not_reveal_cards_when_every_player_leaves_without_playing() {
+            let mut room = make_auto_reveal_room_with_game();
+            let (joiner_client_id, joiner_uuid) = join_new_player(&mut room);
+            let owner_uuid = room.find_player_uuid(DEFAULT_OWNER_CLIENT_ID).unwrap();
+
+            room.leave(joiner_client_id, joiner_uuid);
+            room.leave(DEFAULT_OWNER_CLIENT_ID, owner_uuid);
+
+            assert!(!room.current_game.as_ref().unwrap().revealed);
+        }
+
+        fn make_auto_reveal_room_with_game() -> MockRoom {
+            let mut room = make_room_with_game();
+            room.options.auto_reveal = true;
+
+            room
+        }
+    }
+
+    mod spectator {
+        use super::*;
+
+        #[test]
+        fn play_card_should_return_forbidden_error_when_player_is_spectator() {
+            let mut room = make_room_with_game();
+            let spectator_client_id = join_new_spectator(&mut room);
+
+            let play_result = room.play_card(spectator_client_id, make_play_card_params("3"));
+            assert_eq!(play_result.unwrap_err().kind(), ErrorKind::ForbiddenError);
+        }
+
+        #[test]
+        fn reveal_cards_should_not_mark_spectator_as_abstained() {
+            let mut room = make_room_with_game();
+            let spectator_client_id = join_new_spectator(&mut room);
+            let (_joiner_client_id, joiner_uuid) = join_new_player(&mut room);
+            let owner_uuid = room.find_player_uuid(DEFAULT_OWNER_CLIENT_ID).unwrap();
+            room.play_card(DEFAULT_OWNER_CLIENT_ID, make_play_card_params("3"))
+                .unwrap();
+            expect_response(&room, spectator_client_id, move |res| match res {
+                ResponseMessage::CardsRevealed(revealed) => {
+                    revealed.hands.len() == 1
+                        && revealed.hands[0].player_uuid == owner_uuid
+                        && revealed.abstained == vec![joiner_uuid.clone()]
+                }
+                _ => false,
+            });
+
+            assert!(room.reveal_cards(DEFAULT_OWNER_CLIENT_ID).is_ok());
+        }
+
+        #[test]
+        fn leave_should_remove_spectator() {
+            let mut room = make_room(None);
+            let spectator_client_id = join_new_spectator(&mut room);
+            let spectator_uuid = room.find_player_uuid(spectator_client_id).unwrap();
+
+            room.leave(spectator_client_id, spectator_uuid);
+
+            assert!(!is_member(&room, spectator_client_id));
+            assert!(room.spectators.is_empty());
+        }
+    }
+
+    mod leave {
+        use super::*;
+
+        #[test]
+        fn should_remove_player_and_discard_hand() {
+            let mut room = make_room_with_game();
+            let (joiner_client_id, joiner_uuid) = join_new_player(&mut room);
+            room.play_card(joiner_client_id, make_play_card_params("3"))
+                .unwrap();
+
+            room.leave(joiner_client_id, joiner_uuid);
+
+            assert!(!is_member(&room, joiner_client_id));
+            assert!(room.current_game.as_ref().unwrap().players_hands.is_empty());
+        }
+
+        #[test]
+        fn should_broadcast_user_left_to_remaining_players() {
+            let mut room = make_room(None);
+            let (joiner_client_id, joiner_uuid) = join_new_player(&mut room);
+            let expected_uuid = joiner_uuid.clone();
+            expect_response(&room, DEFAULT_OWNER_CLIENT_ID, move |res| match res {
+                ResponseMessage::UserLeft(player_uuid) => player_uuid == &expected_uuid,
+                _ => false,
+            });
+
+            room.leave(joiner_client_id, joiner_uuid);
+        }
+
+        #[test]
+        fn should_keep_player_and_hand_while_another_client_is_attached() {
+            let mut room = make_room_with_game();
+            let (joiner_client_id, joiner_uuid) = join_new_player(&mut room);
+            let joiner_tab_client_id = join_new_client(&mut room, joiner_uuid.clone());
+            room.play_card(joiner_client_id, make_play_card_params("3"))
+                .unwrap();
+            {
+                let mut client_store = room.client_store.get_writable();
+                let client_channel = &mut client_store
+                    .get_mut(&DEFAULT_OWNER_CLIENT_ID)
+                    .unwrap()
+                    .channel;
+                client_channel.checkpoint();
+                client_channel.expect_do_send().never();
+            }
+
+            room.leave(joiner_client_id, joiner_uuid.clone());
+
+            assert!(!is_member(&room, joiner_client_id));
+            assert!(is_member(&room, joiner_tab_client_id));
+            assert_eq!(
+                room.current_game
+                    .as_ref()
+                    .unwrap()
+                    .players_hands
+                    .get(&joiner_uuid),
+                Some(&String::from("3"))
+            );
+        }
+    }
+
+    mod detach {
+        use super::*;
+
+        #[test]
+        fn should_keep_seat_and_hand_of_player() {
+            let mut room = make_room_with_game();
+            let (joiner_client_id, joiner_uuid) = join_new_player(&mut room);
+            room.play_card(joiner_client_id, make_play_card_params("3"))
+                .unwrap();
+
+            room.detach(joiner_client_id);
+
+            assert!(is_member(&room, joiner_client_id));
+            assert_eq!(
+                room.current_game
+                    .as_ref()
+                    .unwrap()
+                    .players_hands
+                    .get(&joiner_uuid),
+                Some(&String::from("3"))
+            );
+        }
+
+        #[test]
+        fn should_not_broadcast_to_detached_player() {
+            let mut room = make_room_with_game();
+            let (joiner_client_id, _joiner_uuid) = join_new_player(&mut room);
+            room.detach(joiner_client_id);
+            {
+                let mut client_store = room.client_store.get_writable();
+                let client_channel = &mut client_store.get_mut(&joiner_client_id).unwrap().channel;
+                client_channel.checkpoint();
+                client_channel.expect_do_send().never();
+            }
+
+            room.play_card(DEFAULT_OWNER_CLIENT_ID, make_play_card_params("5"))
+                .unwrap();
+        }
+    }
+
+    mod resume {
+        use super::*;
+
+        #[test]
+        fn should_send_snapshot_with_own_hand() {
+            let mut room = make_room_with_game();
+            let (joiner_client_id, joiner_uuid) = join_new_player(&mut room);
+            room.play_card(joiner_client_id, make_play_card_params("3"))
+                .unwrap();
+            room.detach(joiner_client_id);
+            expect_response(&room, joiner_client_id, move |res| match res {
+                ResponseMessage::SessionResumed(snapshot) => match snapshot.game.as_ref() {
+                    Some(game) => {
+                        game.own_card == Some(String::from("3"))
+                            && game.played == vec![joiner_uuid.clone()]
+                            && game.revealed_cards.is_none()
+                            && snapshot.players.contains(&joiner_uuid)
+                    }
+                    None => false,
+                },
+                _ => false,
+            });
+
+            assert!(room.resume(joiner_client_id, None).is_ok());
+            assert!(!room.detached_client_ids.contains(&joiner_client_id));
+        }
+
+        #[test]
+        fn should_send_revealed_cards_in_snapshot_after_reveal() {
+            let mut room = make_room_with_game();
+            let (joiner_client_id, _joiner_uuid) = join_new_player(&mut room);
+            room.play_card(joiner_client_id, make_play_card_params("3"))
+                .unwrap();
+            room.detach(joiner_client_id);
+            room.reveal_cards(DEFAULT_OWNER_CLIENT_ID).unwrap();
+            expect_response(&room, joiner_client_id, |res| match res {
+                ResponseMessage::SessionResumed(snapshot) => snapshot
+                    .game
+                    .as_ref()
+                    .and_then(|game| game.revealed_cards.as_ref())
+                    .filter(|revealed| revealed.cards == vec![String::from("3")])
+                    .is_some(),
+                _ => false,
+            });
+
+            assert!(room.resume(joiner_client_id, None).is_ok());
+        }
+
+        #[test]
+        fn should_replay_events_after_last_received_event() {
+            let mut room = make_room_with_game();
+            let (joiner_client_id, _joiner_uuid) = join_new_player(&mut room);
+            let last_seq = room.event_seq;
+            room.detach(joiner_client_id);
+            room.play_card(DEFAULT_OWNER_CLIENT_ID, make_play_card_params("5"))
+                .unwrap();
+            {
+                let mut client_store = room.client_store.get_writable();
+                let client_channel = &mut client_store.get_mut(&joiner_client_id).unwrap().channel;
+                client_channel.checkpoint();
+                client_channel
+                    .expect_do_send()
+                    .withf(move |frame| match frame.msg {
+                        ResponseMessage::CardPlayed(_) => frame.seq == Some(last_seq + 1),
+                        _ => false,
+                    })
+                    .once()
+                    .return_const(Ok(()));
+            }
+
+            assert!(room.resume(joiner_client_id, Some(last_seq)).is_ok());
+        }
+
+        #[test]
+        fn should_send_snapshot_when_missed_events_are_no_longer_buffered() {
+            let mut room = make_room_with_game();
+            let (joiner_client_id, _joiner_uuid) = join_new_player(&mut room);
+            room.detach(joiner_client_id);
+            for _ in 0..MAX_BUFFERED_EVENTS {
+                room.broadcast(ResponseMessage::CountdownTicked(CountdownTick {
+                    secs_left: 1,
+                }));
+            }
+            let expected_seq = room.event_seq;
+            expect_response(&room, joiner_client_id, move |res| match res {
+                ResponseMessage::SessionResumed(snapshot) => snapshot.seq == expected_seq,
+                _ => false,
+            });
+
+            assert!(room.resume(joiner_client_id, Some(1)).is_ok());
+        }
+
+        #[test]
+        fn should_return_player_not_found_error_when_player_has_left() {
+            let mut room = make_room(None);
+            let (joiner_client_id, joiner_uuid) = join_new_player(&mut room);
+            room.detach(joiner_client_id);
+            room.leave(joiner_client_id, joiner_uuid);
+
+            let err = room.resume(joiner_client_id, None).unwrap_err();
+
+            assert_eq!(err.kind(), ErrorKind::PlayerNotFound);
+            assert!(!room.detached_client_ids.contains(&joiner_client_id));
+        }
+    }
+
+    mod broadcast {
+        use super::*;
+
+        #[test]
+        fn should_send_events_to_every_client_of_player() {
+            let mut room = make_room(None);
+            let owner_uuid = room.owner_uuid.clone();
+            let owner_tab_client_id = join_new_client(&mut room, owner_uuid);
+            for client_id in [DEFAULT_OWNER_CLIENT_ID, owner_tab_client_id].iter() {
+                expect_response(&room, *client_id, |res| match res {
+                    ResponseMessage::CountdownTicked(_) => true,
+                    _ => false,
+                });
+            }
+
+            room.broadcast(ResponseMessage::CountdownTicked(CountdownTick {
+                secs_left: 1,
+            }));
+        }
+
+        #[test]
+        fn should_send_events_with_consecutive_sequence_numbers() {
+            let mut room = make_room(None);
+            {
+                let mut client_store = room.client_store.get_writable();
+                let client_channel = &mut client_store
+                    .get_mut(&DEFAULT_OWNER_CLIENT_ID)
+                    .unwrap()
+                    .channel;
+                client_channel.checkpoint();
+                for expected_seq in 1..=2 {
+                    client_channel
+                        .expect_do_send()
+                        .withf(move |frame| frame.seq == Some(expected_seq))
+                        .once()
+                        .return_const(Ok(()));
+                }
+            }
+
+            room.broadcast(ResponseMessage::CountdownTicked(CountdownTick {
+                secs_left: 2,
+            }));
+            room.broadcast(ResponseMessage::CountdownTicked(CountdownTick {
+                secs_left: 1,
+            }));
+        }
+
+        #[test]
+        fn should_keep_only_latest_events() {
+            let mut room = make_room(None);
+
+            for _ in 0..MAX_BUFFERED_EVENTS + 10 {
+                room.broadcast(ResponseMessage::CountdownTicked(CountdownTick {
+                    secs_left: 1,
+                }));
+            }
+
+            assert_eq!(room.events.len(), MAX_BUFFERED_EVENTS);
+            assert_eq!(room.events.front().unwrap().seq, Some(11));
+            assert_eq!(
+                room.events.back().unwrap().seq,
+                Some(MAX_BUFFERED_EVENTS as u64 + 10)
+            );
+        }
+    }
+
+    mod anonymous {
+        use super::*;
+
+        #[test]
+        fn reveal_cards_should_broadcast_cards_without_players() {
+            let mut room = make_anonymous_room_with_game();
+            let (joiner_client_id, _joiner_uuid) = join_new_player(&mut room);
+            room.play_card(DEFAULT_OWNER_CLIENT_ID, make_play_card_params("5"))
+                .unwrap();
+            room.play_card(joiner_client_id, make_play_card_params("1"))
+                .unwrap();
+            expect_response(&room, joiner_client_id, |res| match res {
+                ResponseMessage::CardsRevealed(revealed) => {
+                    revealed.hands.is_empty()
+                        && revealed.cards == vec![String::from("1"), String::from("5")]
+                        && revealed.stats.average == Some(3.0)
+                }
+                _ => false,
+            });
+
+            assert!(room.reveal_cards(DEFAULT_OWNER_CLIENT_ID).is_ok());
+        }
+
+        #[test]
+        fn reveal_cards_should_persist_hands_without_user() {
+            let mut room = make_anonymous_room_with_game();
+            room.play_card(DEFAULT_OWNER_CLIENT_ID, make_play_card_params("3"))
+                .unwrap();
+            room.room_model.checkpoint();
+            room.room_model
+                .expect_create_game_hands()
+                .withf(|hands| {
+                    hands.len() == 1 && hands[0].user_uuid.is_none() && hands[0].card == "3"
+                })
+                .once()
+                .returning(|_| Ok(()));
+
+            assert!(room.reveal_cards(DEFAULT_OWNER_CLIENT_ID).is_ok());
+        }
+
+        #[test]
+        fn reveal_cards_should_persist_hands_with_user_when_not_anonymous() {
+            let mut room = make_room_with_game();
+            let owner_uuid = room.find_player_uuid(DEFAULT_OWNER_CLIENT_ID).unwrap();
+            room.play_card(DEFAULT_OWNER_CLIENT_ID, make_play_card_params("3"))
+                .unwrap();
+            room.room_model.checkpoint();
+            room.room_model
+                .expect_create_game_hands()
+                .withf(move |hands| {
+                    hands.len() == 1 && hands[0].user_uuid.as_ref() == Some(&owner_uuid)
+                })
+                .once()
+                .returning(|_| Ok(()));
+
+            assert!(room.reveal_cards(DEFAULT_OWNER_CLIENT_ID).is_ok());
+        }
+
+        #[test]
+        fn reveal_cards_should_not_reveal_when_hands_cannot_be_persisted() {
+            let mut room = make_room_with_game();
+            room.play_card(DEFAULT_OWNER_CLIENT_ID, make_play_card_params("3"))
+                .unwrap();
+            room.room_model.checkpoint();
+            room.room_model
+                .expect_create_game_hands()
+                .returning(|_| Err(Error::from(ErrorKind::InsertionError)));
+
+            let reveal_result = room.reveal_cards(DEFAULT_OWNER_CLIENT_ID);
+            assert_eq!(reveal_result.unwrap_err().kind(), ErrorKind::InsertionError);
+            assert!(!room.current_game.as_ref().unwrap().revealed);
+        }
+
+        #[test]
+        fn update_config_should_return_game_in_progress_error_when_toggled_before_reveal() {
+            let mut room = make_anonymous_room_with_game();
+
+            let update_result = room.update_config(
+                DEFAULT_OWNER_CLIENT_ID,
+                UpdateRoomConfigParams {
+                    passphrase: None,
+                    card_set: None,
+                    deck_preset: None,
+                    options: Some(RoomOptions::default()),
+                },
+            );
+            assert_eq!(
+                update_result.unwrap_err().kind(),
+                ErrorKind::GameInProgressError
+            );
+        }
+
+        #[test]
+        fn update_config_should_keep_hands_out_of_snapshot_when_toggled_off_after_reveal() {
+            let mut room = make_anonymous_room_with_game();
+            let (joiner_client_id, _joiner_uuid) = join_new_player(&mut room);
+            room.play_card(DEFAULT_OWNER_CLIENT_ID, make_play_card_params("5"))
+                .unwrap();
+            room.play_card(joiner_client_id, make_play_card_params("1"))
+                .unwrap();
+            room.reveal_cards(DEFAULT_OWNER_CLIENT_ID).unwrap();
+
+            let update_result = room.update_config(
+                DEFAULT_OWNER_CLIENT_ID,
+                UpdateRoomConfigParams {
+                    passphrase: None,
+                    card_set: None,
+                    deck_preset: None,
+                    options: Some(RoomOptions::default()),
+                },
+            );
+            assert_eq!(
+                update_result.unwrap_err().kind(),
+                ErrorKind::GameInProgressError
+            );
+
+            let snapshot = room.snapshot(joiner_client_id).unwrap();
+            let revealed_cards = snapshot.game.unwrap().revealed_cards.unwrap();
+            assert!(revealed_cards.hands.is_empty());
+            assert_eq!(
+                revealed_cards.cards,
+                vec![String::from("1"), String::from("5")]
+            );
+        }
+
+        fn make_anonymous_room_with_game() -> MockRoom {
+            let mut room = make_room_with_game();
+            room.options.anonymous = true;
+
+            room
+        }
+    }
+
+    mod reset_game {
+        use super::*;
+
+        #[test]
+        fn should_return_forbidden_error_when_resetter_is_not_room_owner() {
+            let mut room = make_room_with_game();
+            let (joiner_client_id, _joiner_uuid) = join_new_player(&mut room);
+
+            let reset_result = room.reset_game(joiner_client_id, make_reset_game_params());
+            assert_eq!(reset_result.unwrap_err().kind(), ErrorKind::ForbiddenError);
+        }
+
+        #[test]
+        fn should_return_game_not_found_error_when_no_game_is_started() {
+            let mut room = make_room(None);
+
+            let reset_result = room.reset_game(DEFAULT_OWNER_CLIENT_ID, make_reset_game_params());
+            assert_eq!(reset_result.unwrap_err().kind(), ErrorKind::GameNotFound);
+        }
+
+        #[test]
+        fn should_start_new_attempt_when_cards_are_revealed() {
+            let mut room = make_room_with_game();
+            room.play_card(DEFAULT_OWNER_CLIENT_ID, make_play_card_params("3"))
+                .unwrap();
+            room.reveal_cards(DEFAULT_OWNER_CLIENT_ID).unwrap();
+            expect_response(&room, DEFAULT_OWNER_CLIENT_ID, |res| match res {
+                ResponseMessage::GameReset(reset) => reset.attempt == 2,
+                _ => false,
+            });
+
+            assert!(room
+                .reset_game(DEFAULT_OWNER_CLIENT_ID, make_reset_game_params())
+                .is_ok());
+
+            let game = room.current_game.as_ref().unwrap();
+            assert_eq!(game.attempt, 2);
+            assert!(!game.revealed);
+            assert!(game.players_hands.is_empty());
+        }
+
+        #[test]
+        fn should_keep_attempt_when_cards_are_not_revealed() {
+            let mut room = make_room_with_game();
+            room.play_card(DEFAULT_OWNER_CLIENT_ID, make_play_card_params("3"))
+                .unwrap();
+
+            assert!(room
+                .reset_game(DEFAULT_OWNER_CLIENT_ID, make_reset_game_params())
+                .is_ok());
+
+            let game = room.current_game.as_ref().unwrap();
+            assert_eq!(game.attempt, 1);
+            assert!(game.players_hands.is_empty());
+        }
+
+        #[test]
+        fn should_persist_hands_of_new_attempt_under_same_game() {
+            let mut room = make_room_with_game();
+            let game_uuid = room.current_game.as_ref().unwrap().uuid.clone();
+            room.reveal_cards(DEFAULT_OWNER_CLIENT_ID).unwrap();
+            room.reset_game(DEFAULT_OWNER_CLIENT_ID, make_reset_game_params())
+                .unwrap();
+            room.play_card(DEFAULT_OWNER_CLIENT_ID, make_play_card_params("5"))
+                .unwrap();
+            room.room_model.checkpoint();
+            room.room_model
+                .expect_create_game_hands()
+                .withf(move |hands| {
+                    hands.len() == 1
+                        && hands[0].game_uuid.as_ref() == Some(&game_uuid)
+                        && hands[0].attempt == 2
+                })
+                .once()
+                .returning(|_| Ok(()));
+
+            assert!(room.reveal_cards(DEFAULT_OWNER_CLIENT_ID).is_ok());
+        }
+
+        fn make_reset_game_params() -> ResetGameParams {
+            ResetGameParams {
+                countdown_secs: None,
+            }
+        }
+    }
+
+    mod finalize_game {
+        use super::*;
+
+        #[test]
+        fn should_return_forbidden_error_when_finalizer_is_not_room_owner() {
+            let mut room = make_revealed_room();
+            let (joiner_client_id, _joiner_uuid) = join_new_player(&mut room);
+
+            let finalize_result =
+                room.finalize_game(joiner_client_id, make_finalize_game_params("3", None));
+            assert_eq!(
+                finalize_result.unwrap_err().kind(),
+                ErrorKind::ForbiddenError
+            );
+        }
+
+        #[test]
+        fn should_return_game_in_progress_error_when_cards_are_not_revealed() {
+            let mut room = make_room_with_game();
+
+            let finalize_result = room.finalize_game(
+                DEFAULT_OWNER_CLIENT_ID,
+                make_finalize_game_params("3", None),
+            );
+            assert_eq!(
+                finalize_result.unwrap_err().kind(),
+                ErrorKind::GameInProgressError
+            );
+        }
+
+        #[test]
+        fn should_return_invalid_params_error_when_override_has_no_reason() {
+            let mut room = make_revealed_room();
+
+            let finalize_result = room.finalize_game(
+                DEFAULT_OWNER_CLIENT_ID,
+                make_finalize_game_params("4", Some("  ")),
+            );
+            assert_eq!(
+                finalize_result.unwrap_err().kind(),
+                ErrorKind::InvalidParams
+            );
+        }
+
+        #[test]
+        fn should_return_invalid_params_error_when_estimate_is_special_card() {
+            let mut room = make_revealed_room();
+            room.card_set = card::cards_from_labels(&[String::from("3"), String::from("?")]);
+
+            let finalize_result = room.finalize_game(
+                DEFAULT_OWNER_CLIENT_ID,
+                make_finalize_game_params("?", None),
+            );
+            assert_eq!(
+                finalize_result.unwrap_err().kind(),
+                ErrorKind::InvalidParams
+            );
+        }
+
+        #[test]
+        fn should_persist_estimate_and_end_current_game() {
+            let mut room = make_revealed_room();
+            let game_uuid = room.current_game.as_ref().unwrap().uuid.clone();
+            room.room_model.checkpoint();
+            room.room_model
+                .expect_finalize_game()
+                .withf(move |params| {
+                    params.uuid == game_uuid
+                        && params.final_estimate == "3"
+                        && params.final_estimate_reason.is_none()
+                })
+                .once()
+                .returning(|_| Ok(()));
+
+            assert!(room
+                .finalize_game(
+                    DEFAULT_OWNER_CLIENT_ID,
+                    make_finalize_game_params("3", None)
+                )
+                .is_ok());
+            assert!(room.current_game.is_none());
+        }
+
+        #[test]
+        fn should_allow_override_with_reason() {
+            let mut room = make_revealed_room();
+            room.room_model.checkpoint();
+            room.room_model
+                .expect_finalize_game()
+                .withf(|params| {
+                    params.final_estimate == "4"
+                        && params.final_estimate_reason == Some(String::from("Split story"))
+                })
+                .once()
+                .returning(|_| Ok(()));
+
+            assert!(room
+                .finalize_game(
+                    DEFAULT_OWNER_CLIENT_ID,
+                    make_finalize_game_params("4", Some(" Split story ")),
+                )
+                .is_ok());
+        }
+
+        #[test]
+        fn should_broadcast_finalized_game() {
+            let mut room = make_revealed_room();
+            let (joiner_client_id, _joiner_uuid) = join_new_player(&mut room);
+            expect_response(&room, joiner_client_id, |res| match res {
+                ResponseMessage::GameFinalized(game) => game.estimate == "5",
+                _ => false,
+            });
+
+            assert!(room
+                .finalize_game(
+                    DEFAULT_OWNER_CLIENT_ID,
+                    make_finalize_game_params("5", None)
+                )
+                .is_ok());
+        }
+
+        #[test]
+        fn should_keep_current_game_when_estimate_cannot_be_persisted() {
+            let mut room = make_revealed_room();
+            room.room_model.checkpoint();
+            room.room_model
+                .expect_finalize_game()
+                .returning(|_| Err(Error::from(ErrorKind::UpdateError)));
+
+            let finalize_result = room.finalize_game(
+                DEFAULT_OWNER_CLIENT_ID,
+                make_finalize_game_params("3", None),
+            );
+            assert_eq!(finalize_result.unwrap_err().kind(), ErrorKind::UpdateError);
+            assert!(room.current_game.is_some());
+        }
+
+        #[test]
+        fn should_start_first_story_of_backlog() {
+            let mut room = make_revealed_room();
+            room.add_story(
+                DEFAULT_OWNER_CLIENT_ID,
+                AddStoryParams {
+                    title: String::from("Next story"),
+                    description: None,
+                    link: None,
+                },
+            )
+            .expect("Story should be added");
+            let story_uuid = room.backlog[0].uuid.clone();
+            expect_response(&room, DEFAULT_OWNER_CLIENT_ID, |res| match res {
+                ResponseMessage::GameStarted(game) => game.title == "Next story",
+                _ => false,
+            });
+
+            assert!(room
+                .finalize_game(
+                    DEFAULT_OWNER_CLIENT_ID,
+                    make_finalize_game_params("5", None)
+                )
+                .is_ok());
+            assert_eq!(room.current_game.as_ref().unwrap().uuid, story_uuid);
+            assert!(room.backlog.is_empty());
+        }
+
+        #[test]
+        fn should_leave_room_without_game_when_backlog_is_empty() {
+            let mut room = make_revealed_room();
+
+            assert!(room
+                .finalize_game(
+                    DEFAULT_OWNER_CLIENT_ID,
+                    make_finalize_game_params("5", None)
+                )
+                .is_ok());
+            assert!(room.current_game.is_none());
+        }
+
+        fn make_revealed_room() -> MockRoom {
+            let mut room = make_room_with_game();
+            room.reveal_cards(DEFAULT_OWNER_CLIENT_ID)
+                .expect("Cards should be revealed");
+
+            room
+        }
+
+        fn make_finalize_game_params(estimate: &str, reason: Option<&str>) -> FinalizeGameParams {
+            FinalizeGameParams {
+                estimate: String::from(estimate),
+                reason: reason.map(String::from),
+            }
+        }
+    }
+
+    mod backlog {
+        use super::*;
+
+        #[test]
+        fn add_story_should_return_forbidden_error_when_adder_is_not_room_owner() {
+            let mut room = make_room(None);
+            let (joiner_client_id, _joiner_uuid) = join_new_player(&mut room);
+
+            let add_result = room.add_story(joiner_client_id, make_add_story_params("Story"));
+            assert_eq!(add_result.unwrap_err().kind(), ErrorKind::ForbiddenError);
+        }
+
+        #[test]
+        fn add_story_should_return_invalid_params_error_when_title_is_blank() {
+            let mut room = make_room(None);
+
+            let add_result = room.add_story(DEFAULT_OWNER_CLIENT_ID, make_add_story_params("  "));
+            assert_eq!(add_result.unwrap_err().kind(), ErrorKind::InvalidParams);
+        }
+
+        #[test]
+        fn add_story_should_return_invalid_params_error_when_link_is_not_http_url() {
+            let mut room = make_room(None);
+
+            let add_result = room.add_story(
+                DEFAULT_OWNER_CLIENT_ID,
+                AddStoryParams {
+                    link: Some(String::from("javascript:alert(1)")),
+                    ..make_add_story_params("Story")
+                },
+            );
+            assert_eq!(add_result.unwrap_err().kind(), ErrorKind::InvalidParams);
+        }
+
+        #[test]
+        fn add_story_should_persist_story_after_last_story() {
+            let mut room = make_room_with_backlog(&["Login"]);
+            room.room_model.checkpoint();
+            room.room_model
+                .expect_create_story()
+                .withf(|params| {
+                    params.title == "Logout"
+                        && params.sequence == 1
+                        && params.link == Some(String::from("https://example.com/issues/2"))
+                })
+                .once()
+                .returning(|params| Ok(make_story_record(params)));
+
+            assert!(room
+                .add_story(
+                    DEFAULT_OWNER_CLIENT_ID,
+                    AddStoryParams {
+                        link: Some(String::from(" https://example.com/issues/2 ")),
+                        ..make_add_story_params("Logout")
+                    },
+                )
+                .is_ok());
+            assert_eq!(backlog_titles(&room), vec!["Login", "Logout"]);
+        }
+
+        #[test]
+        fn add_story_should_broadcast_backlog_to_players() {
+            let mut room = make_room(None);
+            let (joiner_client_id, _joiner_uuid) = join_new_player(&mut room);
+            expect_response(&room, joiner_client_id, |res| match res {
+                ResponseMessage::BacklogUpdated(backlog) => {
+                    backlog.len() == 1 && backlog[0].title == "Story"
+                }
+                _ => false,
+            });
+
+            assert!(room
+                .add_story(DEFAULT_OWNER_CLIENT_ID, make_add_story_params("Story"))
+                .is_ok());
+        }
+
+        #[test]
+        fn reorder_backlog_should_persist_new_sequences() {
+            let mut room = make_room_with_backlog(&["A", "B", "C"]);
+            let story_uuids = backlog_uuids(&room);
+            let expected_sequences = vec![
+                (story_uuids[2].clone(), 0),
+                (story_uuids[0].clone(), 1),
+                (story_uuids[1].clone(), 2),
+            ];
+            room.room_model.checkpoint();
+            room.room_model
+                .expect_update_story_sequences()
+                .withf(move |sequences| sequences == expected_sequences.as_slice())
+                .once()
+                .returning(|_| Ok(()));
+
+            assert!(room
+                .reorder_backlog(
+                    DEFAULT_OWNER_CLIENT_ID,
+                    ReorderBacklogParams {
+                        story_uuids: vec![
+                            story_uuids[2].clone(),
+                            story_uuids[0].clone(),
+                            story_uuids[1].clone(),
+                        ],
+                    },
+                )
+                .is_ok());
+            assert_eq!(backlog_titles(&room), vec!["C", "A", "B"]);
+        }
+
+        #[test]
+        fn reorder_backlog_should_return_invalid_params_error_when_story_is_missing() {
+            let mut room = make_room_with_backlog(&["A", "B"]);
+            let story_uuids = backlog_uuids(&room);
+
+            let reorder_result = room.reorder_backlog(
+                DEFAULT_OWNER_CLIENT_ID,
+                ReorderBacklogParams {
+                    story_uuids: vec![story_uuids[1].clone()],
+                },
+            );
+            assert_eq!(reorder_result.unwrap_err().kind(), ErrorKind::InvalidParams);
+            assert_eq!(backlog_titles(&room), vec!["A", "B"]);
+        }
+
+        #[test]
+        fn reorder_backlog_should_return_invalid_params_error_when_story_is_duplicated() {
+            let mut room = make_room_with_backlog(&["A", "B"]);
+            let story_uuids = backlog_uuids(&room);
+
+            let reorder_result = room.reorder_backlog(
+                DEFAULT_OWNER_CLIENT_ID,
+                ReorderBacklogParams {
+                    story_uuids: vec![story_uuids[0].clone(), story_uuids[0].clone()],
+                },
+            );
+            assert_eq!(reorder_result.unwrap_err().kind(), ErrorKind::InvalidParams);
+        }
 
         #[test]
-        fn should_return_true_when_room_is_private() {
-            let passphrase = Some(String::from("Passphrase"));
-            let room = make_room(passphrase);
+        fn remove_story_should_delete_story_from_backlog() {
+            let mut room = make_room_with_backlog(&["A", "B"]);
+            let story_uuid = backlog_uuids(&room)[0].clone();
+            let expected_story_uuid = story_uuid.clone();
+            room.room_model.checkpoint();
+            room.room_model
+                .expect_delete_story()
+                .withf(move |target_uuid| target_uuid == expected_story_uuid)
+                .once()
+                .returning(|_| Ok(()));
 
-            assert!(room.is_private());
+            assert!(room
+                .remove_story(DEFAULT_OWNER_CLIENT_ID, RemoveStoryParams { story_uuid })
+                .is_ok());
+            assert_eq!(backlog_titles(&room), vec!["B"]);
         }
 
         #[test]
-        fn should_return_false_when_room_is_public() {
-            let passphrase = None;
-            let room = make_room(passphrase);
+        fn remove_story_should_return_story_not_found_error_when_story_is_not_in_backlog() {
+            let mut room = make_room_with_backlog(&["A"]);
 
-            assert!(!room.is_private());
+            let remove_result = room.remove_story(
+                DEFAULT_OWNER_CLIENT_ID,
+                RemoveStoryParams {
+                    story_uuid: Uuid::new_v4().to_string(),
+                },
+            );
+            assert_eq!(remove_result.unwrap_err().kind(), ErrorKind::StoryNotFound);
         }
-    }
 
-    mod create {
-        use super::*;
+        #[test]
+        fn skip_story_should_move_first_story_after_last_story() {
+            let mut room = make_room_with_backlog(&["A", "B", "C"]);
+            let expected_sequences = vec![(backlog_uuids(&room)[0].clone(), 3)];
+            room.room_model.checkpoint();
+            room.room_model
+                .expect_update_story_sequences()
+                .withf(move |sequences| sequences == expected_sequences.as_slice())
+                .once()
+                .returning(|_| Ok(()));
+
+            assert!(room.skip_story(DEFAULT_OWNER_CLIENT_ID).is_ok());
+            assert_eq!(backlog_titles(&room), vec!["B", "C", "A"]);
+        }
 
         #[test]
-        fn should_return_error_when_database_persistence_has_error() {
-            let (_passphrase, _card_set, owner_client_id, params) = default_new_room_params();
+        fn skip_story_should_return_story_not_found_error_when_backlog_is_empty() {
+            let mut room = make_room(None);
 
-            let (shared_user_info, _owner_uuid, _owner_name) = default_shared_user_info();
-            let shared_client_store = make_shared_client_store(vec![(
-                owner_client_id,
-                shared_user_info,
-                default_mock_client_channel(),
-            )]);
-            let mut room_model = MockRoomORM::new();
-            room_model
-                .expect_create()
-                .once()
-                .return_const(Err(Error::from(ErrorKind::ConnectionPoolError)));
+            let skip_result = room.skip_story(DEFAULT_OWNER_CLIENT_ID);
+            assert_eq!(skip_result.unwrap_err().kind(), ErrorKind::StoryNotFound);
+        }
 
-            let mut room = Room::new(params, room_model, shared_client_store);
+        #[test]
+        fn next_story_should_start_first_story_as_current_game() {
+            let mut room = make_room_with_backlog(&["A", "B"]);
+            let story_uuid = backlog_uuids(&room)[0].clone();
+            let expected_story_uuid = story_uuid.clone();
+            room.room_model.checkpoint();
+            room.room_model
+                .expect_start_story()
+                .withf(move |target_uuid, _room_uuid| target_uuid == expected_story_uuid)
+                .once()
+                .returning(|_, _| Ok(()));
 
-            let err = room.create().unwrap_err();
-            assert_eq!(err.kind(), ErrorKind::ConnectionPoolError);
+            assert!(room
+                .next_story(DEFAULT_OWNER_CLIENT_ID, make_next_story_params())
+                .is_ok());
+            let game = room.current_game.as_ref().unwrap();
+            assert_eq!(game.uuid, story_uuid);
+            assert_eq!(game.title, "A");
+            assert_eq!(backlog_titles(&room), vec!["B"]);
         }
 
         #[test]
-        fn should_persist_the_room_into_database() {
-            let (_passphrase, _card_set, owner_client_id, params) = default_new_room_params();
+        fn next_story_should_return_game_in_progress_error_when_current_game_is_not_revealed() {
+            let mut room = make_room_with_game();
+            room.add_story(DEFAULT_OWNER_CLIENT_ID, make_add_story_params("A"))
+                .expect("Story should be added");
 
-            let (shared_user_info, owner_uuid, _owner_name) = default_shared_user_info();
-            let shared_client_store = make_shared_client_store(vec![(
-                owner_client_id,
-                shared_user_info,
-                default_mock_client_channel(),
-            )]);
-            let (room_model, _created_room_uuid) =
-                make_succeeded_create_room_model(params.clone(), owner_uuid);
+            let next_result = room.next_story(DEFAULT_OWNER_CLIENT_ID, make_next_story_params());
+            assert_eq!(
+                next_result.unwrap_err().kind(),
+                ErrorKind::GameInProgressError
+            );
+            assert_eq!(backlog_titles(&room), vec!["A"]);
+        }
 
-            let mut room = Room::new(params, room_model, shared_client_store);
+        #[test]
+        fn next_story_should_return_story_not_found_error_when_backlog_is_empty() {
+            let mut room = make_room(None);
 
-            assert!(room.create().is_ok());
+            let next_result = room.next_story(DEFAULT_OWNER_CLIENT_ID, make_next_story_params());
+            assert_eq!(next_result.unwrap_err().kind(), ErrorKind::StoryNotFound);
         }
 
         #[test]
-        fn should_return_created_room_uuid() {
-            let (_passphrase, _card_set, owner_client_id, params) = default_new_room_params();
+        fn next_story_should_broadcast_started_game_with_link() {
+            let mut room = make_room(None);
+            room.add_story(
+                DEFAULT_OWNER_CLIENT_ID,
+                AddStoryParams {
+                    link: Some(String::from("https://example.com/issues/1")),
+                    ..make_add_story_params("A")
+                },
+            )
+            .expect("Story should be added");
+            let (joiner_client_id, _joiner_uuid) = join_new_player(&mut room);
+            expect_response(&room, joiner_client_id, |res| match res {
+                ResponseMessage::GameStarted(game) => {
+                    game.title == "A"
+                        && game.link == Some(String::from("https://example.com/issues/1"))
+                }
+                _ => false,
+            });
 
-            let (shared_user_info, owner_uuid, _owner_name) = default_shared_user_info();
-            let shared_client_store = make_shared_client_store(vec![(
-                owner_client_id,
-                shared_user_info,
-                default_mock_client_channel(),
-            )]);
-            let (room_model, created_room_uuid) =
-                make_succeeded_create_room_model(params.clone(), owner_uuid);
+            assert!(room
+                .next_story(DEFAULT_OWNER_CLIENT_ID, make_next_story_params())
+                .is_ok());
+        }
 
-            let mut room = Room::new(params, room_model, shared_client_store);
+        fn make_room_with_backlog(titles: &[&str]) -> MockRoom {
+            let mut room = make_room(None);
+            for title in titles.iter() {
+                room.add_story(DEFAULT_OWNER_CLIENT_ID, make_add_story_params(title))
+                    .expect("Story should be added");
+            }
 
-            assert_eq!(room.create().unwrap(), created_room_uuid);
+            room
         }
 
-        #[test]
-        fn should_send_room_created_response_to_room_owner() {
-            let (_passphrase, _card_set, owner_client_id, params) = default_new_room_params();
+        fn make_add_story_params(title: &str) -> AddStoryParams {
+            AddStoryParams {
+                title: String::from(title),
+                description: None,
+                link: None,
+            }
+        }
 
-            let mut owner_client_channel = MockClientChannel::new();
-            owner_client_channel
-                .expect_do_send()
-                .withf(|res| match res {
-                    ResponseMessage::RoomCreated(_) => true,
-                    _ => false,
-                })
-                .once()
-                .return_const(Ok(()));
-            let (shared_user_info, owner_uuid, _owner_name) = default_shared_user_info();
-            let shared_client_store = make_shared_client_store(vec![(
-                owner_client_id,
-                shared_user_info,
-                owner_client_channel,
-            )]);
-            let (room_model, _created_room_uuid) =
-                make_succeeded_create_room_model(params.clone(), owner_uuid);
+        fn make_next_story_params() -> NextStoryParams {
+            NextStoryParams {
+                countdown_secs: None,
+            }
+        }
 
-            let mut room = Room::new(params, room_model, shared_client_store);
+        fn backlog_titles(room: &MockRoom) -> Vec<&str> {
+            room.backlog
+                .iter()
+                .map(|story| story.title.as_str())
+                .collect()
+        }
 
-            assert!(room.create().is_ok());
+        fn backlog_uuids(room: &MockRoom) -> Vec<UuidType> {
+            room.backlog
+                .iter()
+                .map(|story| story.uuid.clone())
+                .collect()
         }
     }
 
-    mod join {
+    mod import_stories {
         use super::*;
 
         #[test]
-        fn should_return_already_joined_error_when_already_joined() {
-            let passphrase = None;
-            let mut room = make_room(passphrase.clone());
+        fn should_return_forbidden_error_when_importer_is_not_room_owner() {
+            let mut room = make_room(None);
 
-            let join_result = room.join(DEFAULT_OWNER_CLIENT_ID, passphrase);
-            assert!(join_result.is_err());
+            let import_result = room.import_stories("another-user", vec![Ok(make_row("A", None))]);
+            assert_eq!(import_result.unwrap_err().kind(), ErrorKind::ForbiddenError);
+        }
+
+        #[test]
+        fn should_report_every_invalid_row_without_importing_any_story() {
+            let mut room = make_room(None);
+            let owner_uuid = room.owner_uuid.clone();
+            room.room_model.checkpoint();
+            room.room_model
+                .expect_find_game_external_ids()
+                .returning(|_| Ok(vec![]));
+            room.room_model.expect_create_story().never();
+
+            let report = room
+                .import_stories(
+                    &owner_uuid,
+                    vec![
+                        Ok(make_row("A", None)),
+                        Ok(make_row(" ", None)),
+                        Err(Error::new(ErrorKind::InvalidParams, "Missing title")),
+                    ],
+                )
+                .unwrap();
+            assert!(report.imported.is_empty());
             assert_eq!(
-                join_result.unwrap_err().kind(),
-                ErrorKind::AlreadyJoinedError
+                report.errors,
+                vec![
+                    StoryImportError {
+                        row: 2,
+                        message: String::from("Story title cannot be empty"),
+                    },
+                    StoryImportError {
+                        row: 3,
+                        message: String::from("Missing title"),
+                    },
+                ]
             );
+            assert!(room.backlog.is_empty());
         }
 
         #[test]
-        fn should_return_ok_when_joined_successfully() {
-            let passphrase = None;
-            let mut room = make_room(passphrase.clone());
-
-            let joiner_client_id = DEFAULT_OWNER_CLIENT_ID + 1;
-            let (joiner_user_info, _joiner_uuid, _joiner_name) = default_shared_user_info();
-            let joiner_client_channel = default_mock_client_channel();
-            {
-                let mut client_store = room.client_store.get_writable();
-                client_store.insert(
-                    joiner_client_id,
-                    make_mock_client(joiner_user_info, joiner_client_channel),
-                );
-            }
+        fn should_report_estimate_which_is_not_in_card_set() {
+            let mut room = make_room(None);
+            let owner_uuid = room.owner_uuid.clone();
 
-            assert!(room.join(joiner_client_id, passphrase).is_ok());
+            let report = room
+                .import_stories(
+                    &owner_uuid,
+                    vec![Ok(StoryImportRow {
+                        estimate: Some(String::from("4")),
+                        ..make_row("A", None)
+                    })],
+                )
+                .unwrap();
+            assert_eq!(report.errors.len(), 1);
+            assert_eq!(report.errors[0].row, 1);
         }
 
         #[test]
-        fn should_insert_client_id_into_players() {
-            let passphrase = None;
-            let mut room = make_room(passphrase.clone());
+        fn should_persist_stories_with_pre_estimate_and_external_id() {
+            let mut room = make_room(None);
+            let owner_uuid = room.owner_uuid.clone();
+            room.room_model.checkpoint();
+            room.room_model
+                .expect_find_game_external_ids()
+                .returning(|_| Ok(vec![]));
+            room.room_model
+                .expect_create_story()
+                .withf(|params| {
+                    params.title == "A"
+                        && params.sequence == 0
+                        && params.pre_estimate == Some(String::from("3"))
+                        && params.external_id == Some(String::from("JIRA-1"))
+                })
+                .once()
+                .returning(|params| Ok(make_story_record(params)));
 
-            let joiner_client_id = DEFAULT_OWNER_CLIENT_ID + 1;
+            let report = room
+                .import_stories(
+                    &owner_uuid,
+                    vec![Ok(StoryImportRow {
+                        estimate: Some(String::from(" 3 ")),
+                        ..make_row("A", Some("JIRA-1"))
+                    })],
+                )
+                .unwrap();
+            assert_eq!(report.imported.len(), 1);
+            assert_eq!(room.backlog.len(), 1);
+        }
 
-            assert!(room.join(joiner_client_id, passphrase).is_ok());
+        #[test]
+        fn should_skip_stories_which_are_already_imported() {
+            let mut room = make_room(None);
+            let owner_uuid = room.owner_uuid.clone();
+            room.room_model.checkpoint();
+            room.room_model
+                .expect_find_game_external_ids()
+                .returning(|_| Ok(vec![String::from("JIRA-1")]));
+            room.room_model
+                .expect_create_story()
+                .withf(|params| params.external_id == Some(String::from("JIRA-2")))
+                .once()
+                .returning(|params| Ok(make_story_record(params)));
 
-            assert_eq!(room.players.len(), 2);
-            assert!(room.players.contains(&DEFAULT_OWNER_CLIENT_ID));
-            assert!(room.players.contains(&joiner_client_id));
+            let report = room
+                .import_stories(
+                    &owner_uuid,
+                    vec![
+                        Ok(make_row("A", Some("JIRA-1"))),
+                        Ok(make_row("B", Some("JIRA-2"))),
+                        Ok(make_row("B again", Some("JIRA-2"))),
+                    ],
+                )
+                .unwrap();
+            assert_eq!(
+                report.skipped,
+                vec![String::from("JIRA-1"), String::from("JIRA-2")]
+            );
+            assert_eq!(report.imported.len(), 1);
+            assert_eq!(report.imported[0].title, "B");
         }
 
-        mod given_room_is_public {
-            use super::*;
-
-            #[test]
-            fn should_return_ok_when_passphrase_is_provided() {
-                let passphrase = None;
-                let mut room = make_room(passphrase.clone());
+        #[test]
+        fn should_broadcast_backlog_to_players() {
+            let mut room = make_room(None);
+            let owner_uuid = room.owner_uuid.clone();
+            let (joiner_client_id, _joiner_uuid) = join_new_player(&mut room);
+            expect_response(&room, joiner_client_id, |res| match res {
+                ResponseMessage::BacklogUpdated(backlog) => backlog.len() == 2,
+                _ => false,
+            });
 
-                let joiner_client_id = DEFAULT_OWNER_CLIENT_ID + 1;
+            assert!(room
+                .import_stories(
+                    &owner_uuid,
+                    vec![Ok(make_row("A", None)), Ok(make_row("B", None))],
+                )
+                .is_ok());
+        }
 
-                let passphrase = Some(String::from("Passphrase"));
-                assert!(room.join(joiner_client_id, passphrase).is_ok());
+        fn make_row(title: &str, external_id: Option<&str>) -> StoryImportRow {
+            StoryImportRow {
+                title: String::from(title),
+                description: None,
+                link: None,
+                estimate: None,
+                external_id: external_id.map(String::from),
             }
         }
+    }
 
-        mod given_room_is_private {
-            use super::*;
+    fn make_room_with_game() -> MockRoom {
+        let mut room = make_room(None);
+        room.start_game(DEFAULT_OWNER_CLIENT_ID, make_start_game_params())
+            .expect("Game should be started");
 
-            #[test]
-            fn should_return_unauthenticated_error_when_passphrase_is_incorrect() {
-                let passphrase = Some(String::from("Passphrase"));
-                let mut room = make_room(passphrase.clone());
+        room
+    }
 
-                let joiner_client_id = DEFAULT_OWNER_CLIENT_ID + 1;
+    fn make_start_game_params() -> StartGameParams {
+        StartGameParams {
+            title: String::from("Story"),
+            description: None,
+            countdown_secs: None,
+        }
+    }
 
-                let passphrase = Some(String::from("Incorrect"));
-                let join_result = room.join(joiner_client_id, passphrase);
-                assert!(join_result.is_err());
-                assert_eq!(
-                    join_result.unwrap_err().kind(),
-                    ErrorKind::UnauthenticatedError
-                );
-            }
+    fn make_play_card_params(card: &str) -> PlayCardParams {
+        PlayCardParams {
+            card: String::from(card),
+        }
+    }
 
-            #[test]
-            fn should_return_unauthenticated_error_when_passphrase_is_not_provided() {
-                let passphrase = Some(String::from("Passphrase"));
-                let mut room = make_room(passphrase.clone());
+    /// Replace the expectation of the client channel with one which expects
+    /// the given response exactly once.
+    fn expect_response<F>(room: &MockRoom, client_id: ClientId, matcher: F)
+    where
+        F: Fn(&ResponseMessage) -> bool + Send + 'static,
+    {
+        let mut client_store = room.client_store.get_writable();
+        let client_channel = &mut client_store.get_mut(&client_id).unwrap().channel;
+        client_channel.checkpoint();
 
-                let joiner_client_id = DEFAULT_OWNER_CLIENT_ID + 1;
+        client_channel
+            .expect_do_send()
+            .withf(move |frame| matcher(&frame.msg))
+            .once()
+            .return_const(Ok(()));
+        client_channel.expect_do_send().return_const(Ok(()));
+    }
 
-                let passphrase = None;
-                let join_result = room.join(joiner_client_id, passphrase);
-                assert!(join_result.is_err());
-                assert_eq!(
-                    join_result.unwrap_err().kind(),
-                    ErrorKind::UnauthenticatedError
-                );
-            }
+    /// Join the room with another client of the player, as if it is opened
+    /// in another tab.
+    fn join_new_client(room: &mut MockRoom, player_uuid: UuidType) -> ClientId {
+        let client_id = next_client_id(room);
+        {
+            let mut client_store = room.client_store.get_writable();
+            client_store.insert(
+                client_id,
+                make_mock_client(
+                    make_shared_user_info(player_uuid, String::from("Player")),
+                    default_mock_client_channel(),
+                ),
+            );
         }
+        room.join(client_id, room.passphrase.clone(), false)
+            .expect("Client should join the room");
 
-        fn should_return_error_when_data_persistence_has_error() {}
-        fn should_persist_new_player_to_database() {}
-
-        #[test]
-        fn should_return_ok_when_client_does_not_exist_in_client_store() {
-            let passphrase = None;
-            let mut room = make_room(passphrase.clone());
-
-            let joiner_client_id = DEFAULT_OWNER_CLIENT_ID + 1;
+        client_id
+    }
 
-            assert!(room.join(joiner_client_id, passphrase).is_ok());
-        }
+    /// Insert a client of a new player into the client store, without joining
+    /// the room.
+    fn insert_new_client(room: &MockRoom) -> ClientId {
+        let client_id = next_client_id(room);
+        let (user_info, _uuid, _name) = default_shared_user_info();
+        let mut client_store = room.client_store.get_writable();
+        client_store.insert(
+            client_id,
+            make_mock_client(user_info, default_mock_client_channel()),
+        );
 
-        #[test]
-        fn should_send_user_joined_message_to_all_players() {
-            let passphrase = None;
-            let mut room = make_room(passphrase.clone());
+        client_id
+    }
 
-            let joiner_client_id = DEFAULT_OWNER_CLIENT_ID + 1;
-            let (joiner_user_info, _joiner_uuid, _joiner_name) = default_shared_user_info();
-            let joiner_client_channel = default_mock_client_channel();
+    fn next_client_id(room: &MockRoom) -> ClientId {
+        room.players.values().map(HashSet::len).sum::<usize>() + DEFAULT_OWNER_CLIENT_ID
+    }
 
-            {
-                let mut client_store = room.client_store.get_writable();
-                client_store.insert(
-                    joiner_client_id,
-                    make_mock_client(joiner_user_info, joiner_client_channel),
-                );
-            }
-            {
-                let mut client_store = room.client_store.get_writable();
-                let owner_client_channel = &mut client_store
-                    .get_mut(&DEFAULT_OWNER_CLIENT_ID)
-                    .unwrap()
-                    .channel;
-                owner_client_channel.checkpoint();
+    fn is_member(room: &MockRoom, client_id: ClientId) -> bool {
+        room.find_member_uuid(client_id).is_some()
+    }
 
-                owner_client_channel
-                    .expect_do_send()
-                    .withf(|res| match res {
-                        ResponseMessage::UserJoined(_) => true,
-                        _ => false,
-                    })
-                    .once()
-                    .return_const(Ok(()));
-            }
-            {
-                let mut client_store = room.client_store.get_writable();
-                let joiner_client_channel =
-                    &mut client_store.get_mut(&joiner_client_id).unwrap().channel;
-                joiner_client_channel.checkpoint();
+    fn join_new_player(room: &mut MockRoom) -> (ClientId, UuidType) {
+        let joiner_client_id = next_client_id(room);
+        let (joiner_user_info, joiner_uuid, _joiner_name) = default_shared_user_info();
+        {
+            let mut client_store = room.client_store.get_writable();
+            client_store.insert(
+                joiner_client_id,
+                make_mock_client(joiner_user_info, default_mock_client_channel()),
+            );
+        }
+        room.join(joiner_client_id, room.passphrase.clone(), false)
+            .expect("Player should join the room");
 
-                joiner_client_channel
-                    .expect_do_send()
-                    .withf(|res| match res {
-                        ResponseMessage::UserJoined(_) => true,
-                        _ => false,
-                    })
-                    .once()
-                    .return_const(Ok(()));
-            }
+        (joiner_client_id, joiner_uuid)
+    }
 
-            assert!(room.join(joiner_client_id, passphrase).is_ok());
+    fn join_new_spectator(room: &mut MockRoom) -> ClientId {
+        let spectator_client_id = next_client_id(room);
+        let (spectator_user_info, _spectator_uuid, _spectator_name) = default_shared_user_info();
+        {
+            let mut client_store = room.client_store.get_writable();
+            client_store.insert(
+                spectator_client_id,
+                make_mock_client(spectator_user_info, default_mock_client_channel()),
+            );
         }
+        room.join(spectator_client_id, room.passphrase.clone(), true)
+            .expect("Spectator should join the room");
 
-        #[test]
-        fn should_return_ok_when_passphrase_is_provided() {
-            let passphrase = None;
-            let mut room = make_room(passphrase.clone());
+        spectator_client_id
+    }
 
-            let joiner_client_id = DEFAULT_OWNER_CLIENT_ID + 1;
+    fn make_game_record(params: NewGameRecordParams) -> GameRecord {
+        let now = SystemTime::now();
+        GameRecord {
+            uuid: params.uuid,
+            room_uuid: Some(params.room_uuid),
+            sequence: None,
+            title: params.title,
+            description: params.description,
+            created_at: now,
+            last_updated_at: now,
+            final_estimate: None,
+            final_estimate_reason: None,
+            link: None,
+            external_id: None,
+            pre_estimate: None,
+            started_at: Some(now),
+        }
+    }
 
-            let passphrase = Some(String::from("Passphrase"));
-            assert!(room.join(joiner_client_id, passphrase).is_ok());
+    fn make_story_record(params: NewStoryRecordParams) -> GameRecord {
+        let now = SystemTime::now();
+        GameRecord {
+            uuid: params.uuid,
+            room_uuid: Some(params.room_uuid),
+            sequence: Some(params.sequence),
+            title: params.title,
+            description: params.description,
+            created_at: now,
+            last_updated_at: now,
+            final_estimate: None,
+            final_estimate_reason: None,
+            link: params.link,
+            external_id: params.external_id,
+            pre_estimate: params.pre_estimate,
+            started_at: None,
         }
     }
 
+    fn make_room_ban_record(params: NewRoomBanRecordParams) -> CommonResult<RoomBanRecord> {
+        Ok(RoomBanRecord {
+            room_uuid: params.room_uuid,
+            user_uuid: params.user_uuid,
+            banned_at: SystemTime::now(),
+        })
+    }
+
     fn make_room(passphrase: Option<String>) -> MockRoom {
         let owner_client_id = DEFAULT_OWNER_CLIENT_ID;
-        let new_room_params = make_new_room_params(passphrase, default_card_set(), owner_client_id);
         let (shared_user_info, owner_uuid, _owner_name) = default_shared_user_info();
+        let new_room_params = make_new_room_params(
+            passphrase,
+            default_card_set(),
+            owner_client_id,
+            owner_uuid.clone(),
+        );
         let owner_client_channel = default_mock_client_channel();
         let shared_client_store = make_shared_client_store(vec![(
             owner_client_id,
@@ -540,7 +4415,9 @@ mod test {
         room
     }
 
-    fn default_new_room_params() -> (Option<String>, Vec<Card>, ClientId, NewRoomParams) {
+    fn default_new_room_params(
+        owner_uuid: UuidType,
+    ) -> (Option<String>, Vec<CardLabel>, ClientId, NewRoomParams) {
         let passphrase = Some(String::from("passphrase"));
         let card_set = default_card_set();
         let owner_client_id = DEFAULT_OWNER_CLIENT_ID;
@@ -548,25 +4425,32 @@ mod test {
         let params = NewRoomParams {
             passphrase: passphrase.clone(),
             card_set: card_set.clone(),
-            owner_client_id,
+            options: RoomOptions::default(),
+            owner_client_id: Some(owner_client_id),
+            owner_uuid,
+            join_code: String::from(DEFAULT_JOIN_CODE),
         };
 
         (passphrase, card_set, owner_client_id, params)
     }
 
-    fn default_card_set() -> Vec<Card> {
+    fn default_card_set() -> Vec<CardLabel> {
         vec![String::from("1"), String::from("3"), String::from("5")]
     }
 
     fn make_new_room_params(
         passphrase: Option<String>,
-        card_set: Vec<Card>,
+        card_set: Vec<CardLabel>,
         owner_client_id: ClientId,
+        owner_uuid: UuidType,
     ) -> NewRoomParams {
         NewRoomParams {
             passphrase: passphrase,
             card_set: card_set,
-            owner_client_id,
+            options: RoomOptions::default(),
+            owner_client_id: Some(owner_client_id),
+            owner_uuid,
+            join_code: String::from(DEFAULT_JOIN_CODE),
         }
     }
 
@@ -635,6 +4519,7 @@ mod test {
             .withf(move |params| {
                 return params.passphrase == new_room_params.passphrase
                     && params.card_set == new_room_params.card_set
+                    && params.options == new_room_params.options
                     && params.owner_uuid == owner_uuid;
             })
             .once()
@@ -647,9 +4532,29 @@ mod test {
                     owner_uuid: params.owner_uuid,
                     created_at: now,
                     last_updated_at: now,
+                    options: params.options,
+                    join_code: Some(params.join_code),
+                    closed_at: None,
                 };
                 Ok(room_record)
             });
+        room_model
+            .expect_create_game()
+            .returning(|params| Ok(make_game_record(params)));
+        room_model.expect_create_game_hands().returning(|_| Ok(()));
+        room_model.expect_finalize_game().returning(|_| Ok(()));
+        room_model
+            .expect_create_story()
+            .returning(|params| Ok(make_story_record(params)));
+        room_model
+            .expect_update_story_sequences()
+            .returning(|_| Ok(()));
+        room_model.expect_delete_story().returning(|_| Ok(()));
+        room_model.expect_start_story().returning(|_, _| Ok(()));
+        room_model
+            .expect_find_game_external_ids()
+            .returning(|_| Ok(vec![]));
+        room_model.expect_create_player().returning(|_| Ok(()));
 
         (room_model, created_room_uuid)
     }
@@ -0,0 +1,980 @@
+use std::marker::PhantomData;
+use std::time::{Duration, UNIX_EPOCH};
+
+use actix::prelude::*;
+use actix_web::error::BlockingError;
+use actix_web::http::header;
+use actix_web::{web, Error as ActixWebError, HttpRequest, HttpResponse};
+use futures::future::{self, Either};
+use futures::Future;
+use serde::{Deserialize, Serialize};
+
+use crate::client::channel::DefaultClientChannel;
+use crate::client::store::DefaultClientStore;
+use crate::common::error::{flatten_mailbox_result, Error, ErrorKind};
+use crate::common::http::error_response;
+use crate::common::message::request::{CreateRoomParams, UpdateRoomConfigParams};
+use crate::common::model::Uuid;
+use crate::poker::room::message::{ImportStoriesMessage, UpdateRoomConfigMessage};
+use crate::server::message::{CloseRoomMessage, CreateRoomForUserMessage, LookupRoomMessage};
+use crate::user::auth::AuthenticatedUser;
+use crate::AppServer;
+
+use super::backlog::{self, ImportFormat};
+use super::card::Card;
+use super::deck::{self, DeckPreset};
+use super::export::{self, ExportFormat};
+use super::history::{self, PageQuery, Paging};
+use super::model::{
+    CardLabel, DeckORM, FindGameResultsParams, NewDeckRecordParams, RoomModel, RoomORM,
+    UpdateDeckRecordParams,
+};
+
+type AppLookupRoomServerMessage =
+    LookupRoomMessage<RoomModel, DefaultClientStore<DefaultClientChannel>, DefaultClientChannel>;
+
+pub fn list_deck_presets() -> HttpResponse {
+    let presets: Vec<DeckPresetRes> = DeckPreset::all()
+        .into_iter()
+        .map(|preset| DeckPresetRes {
+            preset,
+            cards: preset.cards(),
+        })
+        .collect();
+
+    HttpResponse::Ok().json(presets)
+}
+
+pub fn create_deck<D>(
+    user: AuthenticatedUser,
+    req: web::Json<DeckReq>,
+    deck_model: web::Data<D>,
+) -> impl Future<Item = HttpResponse, Error = ActixWebError>
+where
+    D: DeckORM + 'static,
+{
+    web::block(move || {
+        let req = req.into_inner();
+        let card_set = deck::validate_deck(&req.name, &req.card_set)?;
+        if let Some(team_uuid) = req.team_uuid.as_ref() {
+            if !deck_model.is_team_member(team_uuid, &user.uuid)? {
+                return Err(Error::new(
+                    ErrorKind::ForbiddenError,
+                    "User is not a member of the team",
+                ));
+            }
+        }
+
+        deck_model.create(NewDeckRecordParams {
+            owner_uuid: user.uuid,
+            team_uuid: req.team_uuid,
+            name: String::from(req.name.trim()),
+            card_set,
+        })
+    })
+    .then(|deck_result| match deck_result {
+        Ok(deck) => Ok(HttpResponse::Created().json(deck)),
+        Err(err) => Ok(error_response(err)),
+    })
+}
+
+pub fn list_decks<D>(
+    user: AuthenticatedUser,
+    deck_model: web::Data<D>,
+) -> impl Future<Item = HttpResponse, Error = ActixWebError>
+where
+    D: DeckORM + 'static,
+{
+    web::block(move || deck_model.list_by_member(&user.uuid)).then(
+        |decks_result| match decks_result {
+            Ok(decks) => Ok(HttpResponse::Ok().json(decks)),
+            Err(err) => Ok(error_response(err)),
+        },
+    )
+}
+
+pub fn update_deck<D>(
+    user: AuthenticatedUser,
+    deck_uuid: web::Path<Uuid>,
+    req: web::Json<DeckReq>,
+    deck_model: web::Data<D>,
+) -> impl Future<Item = HttpResponse, Error = ActixWebError>
+where
+    D: DeckORM + 'static,
+{
+    web::block(move || {
+        let req = req.into_inner();
+        let card_set = deck::validate_deck(&req.name, &req.card_set)?;
+        deck::find_accessible_deck(deck_model.get_ref(), &deck_uuid, &user.uuid)?;
+
+        deck_model.update(UpdateDeckRecordParams {
+            uuid: deck_uuid.into_inner(),
+            name: String::from(req.name.trim()),
+            card_set,
+        })
+    })
+    .then(|deck_result| match deck_result {
+        Ok(deck) => Ok(HttpResponse::Ok().json(deck)),
+        Err(err) => Ok(error_response(err)),
+    })
+}
+
+pub fn delete_deck<D>(
+    user: AuthenticatedUser,
+    deck_uuid: web::Path<Uuid>,
+    deck_model: web::Data<D>,
+) -> impl Future<Item = HttpResponse, Error = ActixWebError>
+where
+    D: DeckORM + 'static,
+{
+    web::block(move || {
+        deck::find_accessible_deck(deck_model.get_ref(), &deck_uuid, &user.uuid)?;
+
+        deck_model.delete(&deck_uuid)
+    })
+    .then(|delete_result| match delete_result {
+        Ok(_) => Ok(HttpResponse::NoContent().finish()),
+        Err(err) => Ok(error_response(err)),
+    })
+}
+
+/// Create a room owned by the user. The owner takes over the room by joining
+/// it from a websocket client.
+pub fn create_room(
+    user: AuthenticatedUser,
+    req: web::Json<CreateRoomParams>,
+    server: web::Data<Addr<AppServer>>,
+) -> impl Future<Item = HttpResponse, Error = ActixWebError> {
+    server
+        .send(CreateRoomForUserMessage {
+            owner_uuid: user.uuid,
+            params: req.into_inner(),
+        })
+        .then(flatten_mailbox_result)
+        .then(|create_result| match create_result {
+            Ok(created_room) => Ok(HttpResponse::Created().json(created_room)),
+            Err(err) => Ok(error_response(BlockingError::Error(err))),
+        })
+}
+
+pub fn update_room(
+    user: AuthenticatedUser,
+    room_uuid: web::Path<Uuid>,
+    req: web::Json<UpdateRoomConfigParams>,
+    server: web::Data<Addr<AppServer>>,
+) -> impl Future<Item = HttpResponse, Error = ActixWebError> {
+    server
+        .send(AppLookupRoomServerMessage {
+            room_uuid: room_uuid.into_inner(),
+            room_orm_type: PhantomData,
+            client_store_type: PhantomData,
+            client_channel_type: PhantomData,
+        })
+        .then(flatten_mailbox_result)
+        .and_then(move |room_addr| {
+            room_addr
+                .send(UpdateRoomConfigMessage {
+                    user_uuid: user.uuid,
+                    params: req.into_inner(),
+                })
+                .then(flatten_mailbox_result)
+        })
+        .then(|update_result| match update_result {
+            Ok(config) => Ok(HttpResponse::Ok().json(config)),
+            Err(err) => Ok(error_response(BlockingError::Error(err))),
+        })
+}
+
+/// Close the room. Players in the room are notified and removed from it.
+pub fn close_room(
+    user: AuthenticatedUser,
+    room_uuid: web::Path<Uuid>,
+    server: web::Data<Addr<AppServer>>,
+) -> impl Future<Item = HttpResponse, Error = ActixWebError> {
+    server
+        .send(CloseRoomMessage {
+            user_uuid: user.uuid,
+            room_uuid: room_uuid.into_inner(),
+        })
+        .then(flatten_mailbox_result)
+        .then(|close_result| match close_result {
+            Ok(()) => Ok(HttpResponse::NoContent().finish()),
+            Err(err) => Ok(error_response(BlockingError::Error(err))),
+        })
+}
+
+/// Import stories from a CSV or JSON file, chosen by the Content-Type, to the
+/// backlog of the room. Responds with bad request and the errors of every
+/// invalid row when nothing is imported.
+pub fn import_stories(
+    user: AuthenticatedUser,
+    room_uuid: web::Path<Uuid>,
+    req: HttpRequest,
+    body: web::Bytes,
+    server: web::Data<Addr<AppServer>>,
+) -> impl Future<Item = HttpResponse, Error = ActixWebError> {
+    let content_type = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .unwrap_or("");
+    let rows = match ImportFormat::from_content_type(content_type)
+        .and_then(|format| backlog::parse_import(format, &body))
+    {
+        Ok(rows) => rows,
+        Err(err) => return Either::A(future::ok(error_response(BlockingError::Error(err)))),
+    };
+
+    Either::B(
+        server
+            .send(AppLookupRoomServerMessage {
+                room_uuid: room_uuid.into_inner(),
+                room_orm_type: PhantomData,
+                client_store_type: PhantomData,
+                client_channel_type: PhantomData,
+            })
+            .then(flatten_mailbox_result)
+            .and_then(move |room_addr| {
+                room_addr
+                    .send(ImportStoriesMessage {
+                        user_uuid: user.uuid,
+                        rows,
+                    })
+                    .then(flatten_mailbox_result)
+            })
+            .then(|import_result| match import_result {
+                Ok(report) if report.errors.is_empty() => Ok(HttpResponse::Ok().json(report)),
+                Ok(report) => Ok(HttpResponse::BadRequest().json(report)),
+                Err(err) => Ok(error_response(BlockingError::Error(err))),
+            }),
+    )
+}
+
+/// Export the played games of the rooms owned by the user, optionally of a
+/// single room and started within a time range given in Unix timestamps.
+pub fn export_results<R>(
+    user: AuthenticatedUser,
+    query: web::Query<ExportQuery>,
+    room_model: web::Data<R>,
+) -> impl Future<Item = HttpResponse, Error = ActixWebError>
+where
+    R: RoomORM + 'static,
+{
+    web::block(move || {
+        let query = query.into_inner();
+        let format = query.format.unwrap_or(ExportFormat::Json);
+        if let (Some(from), Some(to)) = (query.from, query.to) {
+            if from >= to {
+                return Err(Error::new(
+                    ErrorKind::InvalidParams,
+                    "Start of time range must be before its end",
+                ));
+            }
+        }
+
+        let records = room_model.find_game_results(FindGameResultsParams {
+            owner_uuid: user.uuid,
+            room_uuid: query.room_uuid,
+            from: query
+                .from
+                .map(|secs| UNIX_EPOCH + Duration::from_secs(secs)),
+            to: query.to.map(|secs| UNIX_EPOCH + Duration::from_secs(secs)),
+        })?;
+        let body = export::render(format, &export::game_results(records))?;
+
+        Ok((format, body))
+    })
+    .then(|export_result| match export_result {
+        Ok((format, body)) => Ok(HttpResponse::Ok()
+            .content_type(format.content_type())
+            .header(
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"results.{}\"", format.extension()),
+            )
+            .body(body)),
+        Err(err) => Ok(error_response(err)),
+    })
+}
+
+pub fn list_rooms<R>(
+    user: AuthenticatedUser,
+    query: web::Query<PageQuery>,
+    room_model: web::Data<R>,
+) -> impl Future<Item = HttpResponse, Error = ActixWebError>
+where
+    R: RoomORM + 'static,
+{
+    web::block(move || {
+        let paging = Paging::from_query(query.into_inner())?;
+
+        history::list_rooms(room_model.get_ref(), &user.uuid, paging)
+    })
+    .then(|list_result| match list_result {
+        Ok(page) => Ok(HttpResponse::Ok().json(page)),
+        Err(err) => Ok(error_response(err)),
+    })
+}
+
+pub fn get_room<R>(
+    user: AuthenticatedUser,
+    room_uuid: web::Path<Uuid>,
+    query: web::Query<PageQuery>,
+    room_model: web::Data<R>,
+) -> impl Future<Item = HttpResponse, Error = ActixWebError>
+where
+    R: RoomORM + 'static,
+{
+    web::block(move || {
+        let paging = Paging::from_query(query.into_inner())?;
+
+        history::find_room(room_model.get_ref(), &user.uuid, &room_uuid, paging)
+    })
+    .then(|find_result| match find_result {
+        Ok(room) => Ok(HttpResponse::Ok().json(room)),
+        Err(err) => Ok(error_response(err)),
+    })
+}
+
+pub fn get_game<R>(
+    user: AuthenticatedUser,
+    game_uuid: web::Path<Uuid>,
+    room_model: web::Data<R>,
+) -> impl Future<Item = HttpResponse, Error = ActixWebError>
+where
+    R: RoomORM + 'static,
+{
+    web::block(move || history::find_game(room_model.get_ref(), &user.uuid, &game_uuid)).then(
+        |find_result| match find_result {
+            Ok(game) => Ok(HttpResponse::Ok().json(game)),
+            Err(err) => Ok(error_response(err)),
+        },
+    )
+}
+
+#[derive(Deserialize)]
+pub struct ExportQuery {
+    pub room_uuid: Option<Uuid>,
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+    /// JSON when absent.
+    pub format: Option<ExportFormat>,
+}
+
+#[derive(Serialize)]
+pub struct DeckPresetRes {
+    preset: DeckPreset,
+    cards: Vec<Card>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct DeckReq {
+    pub name: String,
+    pub card_set: Vec<CardLabel>,
+    /// Team to share a new deck with. The team of a saved deck is kept when
+    /// the deck is updated.
+    #[serde(default)]
+    pub team_uuid: Option<Uuid>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::time::SystemTime;
+
+    use actix_web::dev::ServiceResponse;
+    use actix_web::http::{header, StatusCode};
+    use actix_web::test::TestRequest;
+    use actix_web::{test, web, App};
+    use serde_json::Value;
+
+    use crate::poker::model::{
+        DeckRecord, GameRecord, GameResultRecord, MockDeckORM, MockRoomORM, RoomOptions, RoomRecord,
+    };
+    use crate::user::auth::token::DEFAULT_TOKEN_TTL;
+    use crate::user::auth::AuthTokenService;
+
+    const USER_UUID: &str = "8a0e6f54-1a2b-4c3d-9e8f-0123456789ab";
+    const DECK_UUID: &str = "4f1c2d3e-5a6b-4c7d-8e9f-0123456789ab";
+    const TEAM_UUID: &str = "c3d4e5f6-7a8b-4c9d-8e0f-0123456789ab";
+
+    #[test]
+    fn list_deck_presets_should_return_all_presets_with_cards() {
+        let mut app = test::init_service(App::new().route("/", web::get().to(list_deck_presets)));
+        let req = TestRequest::get().uri("/").to_request();
+
+        let resp: Value = test::read_response_json(&mut app, req);
+
+        let presets = resp.as_array().unwrap();
+        assert_eq!(presets.len(), DeckPreset::all().len());
+        assert_eq!(presets[0]["preset"], "Fibonacci");
+        assert_eq!(presets[0]["cards"][1]["label"], "1");
+        assert_eq!(presets[0]["cards"][1]["value"], 1.0);
+        assert_eq!(presets[0]["cards"][1]["ordinal"], 1);
+        assert_eq!(presets[0]["cards"][1]["kind"], "Number");
+    }
+
+    mod create_deck {
+        use super::*;
+
+        #[test]
+        fn should_return_unauthorized_when_token_is_missing() {
+            let mut deck_model = MockDeckORM::new();
+            deck_model.expect_create().never();
+
+            let req = TestRequest::post().uri("/deck").set_json(&make_deck_req());
+
+            let resp = call_deck_route(deck_model, req);
+            assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        }
+
+        #[test]
+        fn should_return_bad_request_when_card_set_is_invalid() {
+            let mut deck_model = MockDeckORM::new();
+            deck_model.expect_create().never();
+
+            let req = with_token(TestRequest::post().uri("/deck")).set_json(&DeckReq {
+                name: String::from("Team Deck"),
+                card_set: vec![String::from("1"), String::from("1")],
+                team_uuid: None,
+            });
+
+            let resp = call_deck_route(deck_model, req);
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        }
+
+        #[test]
+        fn should_create_deck_owned_by_authenticated_user() {
+            let mut deck_model = MockDeckORM::new();
+            deck_model
+                .expect_create()
+                .withf(|params| {
+                    params.owner_uuid == USER_UUID
+                        && params.name == "Team Deck"
+                        && params.card_set == make_deck_req().card_set
+                })
+                .once()
+                .returning(|params| {
+                    Ok(make_deck_record(
+                        params.owner_uuid,
+                        params.name,
+                        params.card_set,
+                    ))
+                });
+
+            let req = with_token(TestRequest::post().uri("/deck")).set_json(&DeckReq {
+                name: String::from("  Team Deck "),
+                card_set: make_deck_req().card_set,
+                team_uuid: None,
+            });
+
+            let resp = call_deck_route(deck_model, req);
+            assert_eq!(resp.status(), StatusCode::CREATED);
+        }
+
+        #[test]
+        fn should_create_deck_with_trimmed_card_labels() {
+            let mut deck_model = MockDeckORM::new();
+            deck_model
+                .expect_create()
+                .withf(|params| params.card_set == vec![String::from("3"), String::from("5")])
+                .once()
+                .returning(|params| {
+                    Ok(make_deck_record(
+                        params.owner_uuid,
+                        params.name,
+                        params.card_set,
+                    ))
+                });
+
+            let req = with_token(TestRequest::post().uri("/deck")).set_json(&DeckReq {
+                card_set: vec![String::from(" 3"), String::from("5 ")],
+                ..make_deck_req()
+            });
+
+            let resp = call_deck_route(deck_model, req);
+            assert_eq!(resp.status(), StatusCode::CREATED);
+        }
+
+        #[test]
+        fn should_return_forbidden_when_user_is_not_member_of_team() {
+            let mut deck_model = MockDeckORM::new();
+            deck_model
+                .expect_is_team_member()
+                .withf(|team_uuid, user_uuid| team_uuid == TEAM_UUID && user_uuid == USER_UUID)
+                .returning(|_, _| Ok(false));
+            deck_model.expect_create().never();
+
+            let req = with_token(TestRequest::post().uri("/deck")).set_json(&make_team_deck_req());
+
+            let resp = call_deck_route(deck_model, req);
+            assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        }
+
+        #[test]
+        fn should_create_deck_shared_with_team_of_user() {
+            let mut deck_model = MockDeckORM::new();
+            deck_model
+                .expect_is_team_member()
+                .returning(|_, _| Ok(true));
+            deck_model
+                .expect_create()
+                .withf(|params| {
+                    params.owner_uuid == USER_UUID
+                        && params.team_uuid == Some(String::from(TEAM_UUID))
+                })
+                .once()
+                .returning(|params| {
+                    Ok(make_deck_record(
+                        params.owner_uuid,
+                        params.name,
+                        params.card_set,
+                    ))
+                });
+
+            let req = with_token(TestRequest::post().uri("/deck")).set_json(&make_team_deck_req());
+
+            let resp = call_deck_route(deck_model, req);
+            assert_eq!(resp.status(), StatusCode::CREATED);
+        }
+    }
+
+    mod list_decks {
+        use super::*;
+
+        #[test]
+        fn should_return_decks_owned_by_authenticated_user() {
+            let mut deck_model = MockDeckORM::new();
+            deck_model
+                .expect_list_by_member()
+                .withf(|user_uuid| user_uuid == USER_UUID)
+                .once()
+                .returning(|owner_uuid| Ok(vec![make_owned_deck_record(owner_uuid)]));
+
+            let req = with_token(TestRequest::get().uri("/deck"));
+
+            let resp = call_deck_route(deck_model, req);
+            let decks: Vec<DeckRecord> = serde_json::from_slice(&test::read_body(resp)).unwrap();
+            assert_eq!(decks.len(), 1);
+            assert_eq!(decks[0].owner_uuid, USER_UUID);
+        }
+    }
+
+    mod update_deck {
+        use super::*;
+
+        #[test]
+        fn should_return_not_found_when_deck_does_not_exist() {
+            let mut deck_model = MockDeckORM::new();
+            deck_model.expect_find_by_uuid().returning(|_| Ok(None));
+            deck_model.expect_update().never();
+
+            let req = with_token(TestRequest::put().uri(&format!("/deck/{}", DECK_UUID)))
+                .set_json(&make_deck_req());
+
+            let resp = call_deck_route(deck_model, req);
+            assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        }
+
+        #[test]
+        fn should_return_forbidden_when_deck_is_owned_by_another_user() {
+            let mut deck_model = MockDeckORM::new();
+            deck_model
+                .expect_find_by_uuid()
+                .returning(|_| Ok(Some(make_owned_deck_record("another-user"))));
+            deck_model.expect_update().never();
+
+            let req = with_token(TestRequest::put().uri(&format!("/deck/{}", DECK_UUID)))
+                .set_json(&make_deck_req());
+
+            let resp = call_deck_route(deck_model, req);
+            assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        }
+
+        #[test]
+        fn should_update_deck_owned_by_authenticated_user() {
+            let mut deck_model = MockDeckORM::new();
+            deck_model
+                .expect_find_by_uuid()
+                .returning(|_| Ok(Some(make_owned_deck_record(USER_UUID))));
+            deck_model
+                .expect_update()
+                .withf(|params| params.uuid == DECK_UUID && params.name == "Team Deck")
+                .once()
+                .returning(|params| {
+                    Ok(make_deck_record(
+                        String::from(USER_UUID),
+                        params.name,
+                        params.card_set,
+                    ))
+                });
+
+            let req = with_token(TestRequest::put().uri(&format!("/deck/{}", DECK_UUID)))
+                .set_json(&make_deck_req());
+
+            let resp = call_deck_route(deck_model, req);
+            assert_eq!(resp.status(), StatusCode::OK);
+        }
+
+        #[test]
+        fn should_return_forbidden_when_user_is_not_member_of_deck_team() {
+            let mut deck_model = MockDeckORM::new();
+            deck_model
+                .expect_find_by_uuid()
+                .returning(|_| Ok(Some(make_team_deck_record("another-user"))));
+            deck_model
+                .expect_is_team_member()
+                .returning(|_, _| Ok(false));
+            deck_model.expect_update().never();
+
+            let req = with_token(TestRequest::put().uri(&format!("/deck/{}", DECK_UUID)))
+                .set_json(&make_deck_req());
+
+            let resp = call_deck_route(deck_model, req);
+            assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        }
+
+        #[test]
+        fn should_update_deck_shared_with_team_of_user() {
+            let mut deck_model = MockDeckORM::new();
+            deck_model
+                .expect_find_by_uuid()
+                .returning(|_| Ok(Some(make_team_deck_record("another-user"))));
+            deck_model
+                .expect_is_team_member()
+                .withf(|team_uuid, user_uuid| team_uuid == TEAM_UUID && user_uuid == USER_UUID)
+                .returning(|_, _| Ok(true));
+            deck_model.expect_update().once().returning(|params| {
+                Ok(make_deck_record(
+                    String::from("another-user"),
+                    params.name,
+                    params.card_set,
+                ))
+            });
+
+            let req = with_token(TestRequest::put().uri(&format!("/deck/{}", DECK_UUID)))
+                .set_json(&make_deck_req());
+
+            let resp = call_deck_route(deck_model, req);
+            assert_eq!(resp.status(), StatusCode::OK);
+        }
+    }
+
+    mod delete_deck {
+        use super::*;
+
+        #[test]
+        fn should_return_forbidden_when_deck_is_owned_by_another_user() {
+            let mut deck_model = MockDeckORM::new();
+            deck_model
+                .expect_find_by_uuid()
+                .returning(|_| Ok(Some(make_owned_deck_record("another-user"))));
+            deck_model.expect_delete().never();
+
+            let req = with_token(TestRequest::delete().uri(&format!("/deck/{}", DECK_UUID)));
+
+            let resp = call_deck_route(deck_model, req);
+            assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        }
+
+        #[test]
+        fn should_delete_deck_owned_by_authenticated_user() {
+            let mut deck_model = MockDeckORM::new();
+            deck_model
+                .expect_find_by_uuid()
+                .returning(|_| Ok(Some(make_owned_deck_record(USER_UUID))));
+            deck_model
+                .expect_delete()
+                .withf(|deck_uuid| deck_uuid == DECK_UUID)
+                .once()
+                .returning(|_| Ok(()));
+
+            let req = with_token(TestRequest::delete().uri(&format!("/deck/{}", DECK_UUID)));
+
+            let resp = call_deck_route(deck_model, req);
+            assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+        }
+    }
+
+    mod export_results {
+        use super::*;
+
+        #[test]
+        fn should_return_unauthorized_when_token_is_missing() {
+            let mut room_model = MockRoomORM::new();
+            room_model.expect_find_game_results().never();
+
+            let req = TestRequest::get().uri("/export");
+
+            let resp = call_export_route(room_model, req);
+            assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        }
+
+        #[test]
+        fn should_return_bad_request_when_time_range_is_reversed() {
+            let mut room_model = MockRoomORM::new();
+            room_model.expect_find_game_results().never();
+
+            let req = with_token(TestRequest::get().uri("/export?from=200&to=100"));
+
+            let resp = call_export_route(room_model, req);
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        }
+
+        #[test]
+        fn should_export_results_of_rooms_owned_by_authenticated_user_as_csv() {
+            let mut room_model = MockRoomORM::new();
+            room_model
+                .expect_find_game_results()
+                .withf(|params| {
+                    params.owner_uuid == USER_UUID
+                        && params.room_uuid == Some(String::from("room-uuid"))
+                        && params.from == Some(UNIX_EPOCH + Duration::from_secs(100))
+                        && params.to.is_none()
+                })
+                .once()
+                .returning(|_| Ok(vec![make_game_result_record()]));
+
+            let req = with_token(
+                TestRequest::get().uri("/export?room_uuid=room-uuid&from=100&format=csv"),
+            );
+
+            let resp = call_export_route(room_model, req);
+            assert_eq!(resp.status(), StatusCode::OK);
+            assert_eq!(
+                resp.headers().get(header::CONTENT_TYPE).unwrap(),
+                "text/csv; charset=utf-8"
+            );
+            let body = String::from_utf8(test::read_body(resp).to_vec()).unwrap();
+            assert!(body.lines().nth(1).unwrap().contains(",Login,"));
+        }
+
+        fn call_export_route(room_model: MockRoomORM, req: TestRequest) -> ServiceResponse {
+            let mut app = test::init_service(
+                App::new()
+                    .data(room_model)
+                    .data(make_auth_token_service())
+                    .service(
+                        web::resource("/export")
+                            .route(web::get().to_async(export_results::<MockRoomORM>)),
+                    ),
+            );
+
+            test::call_service(&mut app, req.to_request())
+        }
+
+        fn make_game_result_record() -> GameResultRecord {
+            let now = SystemTime::now();
+            GameResultRecord {
+                room: RoomRecord {
+                    uuid: String::from("room-uuid"),
+                    passphrase: None,
+                    card_set: vec![String::from("1"), String::from("3")],
+                    owner_uuid: String::from(USER_UUID),
+                    created_at: now,
+                    last_updated_at: now,
+                    options: RoomOptions::default(),
+                    join_code: None,
+                    closed_at: None,
+                },
+                game: GameRecord {
+                    uuid: String::from("game-uuid"),
+                    room_uuid: Some(String::from("room-uuid")),
+                    sequence: None,
+                    title: String::from("Login"),
+                    description: None,
+                    created_at: now,
+                    last_updated_at: now,
+                    final_estimate: Some(String::from("3")),
+                    final_estimate_reason: None,
+                    link: None,
+                    external_id: None,
+                    pre_estimate: None,
+                    started_at: Some(now),
+                },
+                hands: vec![],
+            }
+        }
+    }
+
+    mod history {
+        use super::*;
+
+        #[test]
+        fn list_rooms_should_return_unauthorized_when_token_is_missing() {
+            let mut room_model = MockRoomORM::new();
+            room_model.expect_list_by_member().never();
+
+            let req = TestRequest::get().uri("/room");
+
+            let resp = call_history_route(room_model, req);
+            assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        }
+
+        #[test]
+        fn list_rooms_should_return_bad_request_when_limit_is_too_large() {
+            let mut room_model = MockRoomORM::new();
+            room_model.expect_list_by_member().never();
+
+            let req = with_token(TestRequest::get().uri("/room?limit=1000"));
+
+            let resp = call_history_route(room_model, req);
+            assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        }
+
+        #[test]
+        fn list_rooms_should_return_page_of_rooms_of_authenticated_user() {
+            let mut room_model = MockRoomORM::new();
+            room_model
+                .expect_list_by_member()
+                .withf(|user_uuid, offset, limit| {
+                    user_uuid == USER_UUID && *offset == 10 && *limit == 6
+                })
+                .once()
+                .returning(|_, _, _| Ok(vec![make_room_record()]));
+
+            let req = with_token(TestRequest::get().uri("/room?offset=10&limit=5"));
+
+            let resp = call_history_route(room_model, req);
+            assert_eq!(resp.status(), StatusCode::OK);
+            let body: Value = serde_json::from_slice(&test::read_body(resp)).unwrap();
+            assert_eq!(body["items"][0]["uuid"], "room-uuid");
+            assert_eq!(body["offset"], 10);
+            assert_eq!(body["has_more"], false);
+        }
+
+        #[test]
+        fn get_room_should_return_forbidden_when_user_has_not_joined_room() {
+            let mut room_model = MockRoomORM::new();
+            room_model.expect_find_by_uuid().returning(|_| {
+                let mut room_record = make_room_record();
+                room_record.owner_uuid = String::from("another-owner-uuid");
+                Ok(Some(room_record))
+            });
+            room_model.expect_is_player().returning(|_, _| Ok(false));
+            room_model.expect_list_games().never();
+
+            let req = with_token(TestRequest::get().uri("/room/room-uuid"));
+
+            let resp = call_history_route(room_model, req);
+            assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        }
+
+        #[test]
+        fn get_game_should_return_not_found_when_game_does_not_exist() {
+            let mut room_model = MockRoomORM::new();
+            room_model.expect_find_game().returning(|_| Ok(None));
+
+            let req = with_token(TestRequest::get().uri("/game/game-uuid"));
+
+            let resp = call_history_route(room_model, req);
+            assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        }
+
+        fn call_history_route(room_model: MockRoomORM, req: TestRequest) -> ServiceResponse {
+            let mut app = test::init_service(
+                App::new()
+                    .data(room_model)
+                    .data(make_auth_token_service())
+                    .service(
+                        web::resource("/room")
+                            .route(web::get().to_async(list_rooms::<MockRoomORM>)),
+                    )
+                    .service(
+                        web::resource("/room/{uuid}")
+                            .route(web::get().to_async(get_room::<MockRoomORM>)),
+                    )
+                    .service(
+                        web::resource("/game/{uuid}")
+                            .route(web::get().to_async(get_game::<MockRoomORM>)),
+                    ),
+            );
+
+            test::call_service(&mut app, req.to_request())
+        }
+
+        fn make_room_record() -> RoomRecord {
+            let now = SystemTime::now();
+            RoomRecord {
+                uuid: String::from("room-uuid"),
+                passphrase: None,
+                card_set: vec![String::from("1"), String::from("3")],
+                owner_uuid: String::from(USER_UUID),
+                created_at: now,
+                last_updated_at: now,
+                options: RoomOptions::default(),
+                join_code: None,
+                closed_at: None,
+            }
+        }
+    }
+
+    fn call_deck_route(deck_model: MockDeckORM, req: TestRequest) -> ServiceResponse {
+        let mut app = test::init_service(
+            App::new()
+                .data(deck_model)
+                .data(make_auth_token_service())
+                .service(
+                    web::resource("/deck")
+                        .route(web::get().to_async(list_decks::<MockDeckORM>))
+                        .route(web::post().to_async(create_deck::<MockDeckORM>)),
+                )
+                .service(
+                    web::resource("/deck/{uuid}")
+                        .route(web::put().to_async(update_deck::<MockDeckORM>))
+                        .route(web::delete().to_async(delete_deck::<MockDeckORM>)),
+                ),
+        );
+
+        test::call_service(&mut app, req.to_request())
+    }
+
+    fn with_token(req: TestRequest) -> TestRequest {
+        let token = make_auth_token_service().issue(USER_UUID);
+        req.header(header::AUTHORIZATION, format!("Bearer {}", token))
+    }
+
+    fn make_auth_token_service() -> AuthTokenService {
+        AuthTokenService::new(String::from("secret"), DEFAULT_TOKEN_TTL)
+    }
+
+    fn make_deck_req() -> DeckReq {
+        DeckReq {
+            name: String::from("Team Deck"),
+            card_set: vec![String::from("0.5"), String::from("13"), String::from("🤷")],
+            team_uuid: None,
+        }
+    }
+
+    fn make_team_deck_req() -> DeckReq {
+        DeckReq {
+            team_uuid: Some(String::from(TEAM_UUID)),
+            ..make_deck_req()
+        }
+    }
+
+    fn make_team_deck_record(owner_uuid: &str) -> DeckRecord {
+        DeckRecord {
+            team_uuid: Some(String::from(TEAM_UUID)),
+            ..make_owned_deck_record(owner_uuid)
+        }
+    }
+
+    fn make_owned_deck_record(owner_uuid: &str) -> DeckRecord {
+        let deck_req = make_deck_req();
+        make_deck_record(String::from(owner_uuid), deck_req.name, deck_req.card_set)
+    }
+
+    fn make_deck_record(owner_uuid: String, name: String, card_set: Vec<CardLabel>) -> DeckRecord {
+        let now = SystemTime::now();
+        DeckRecord {
+            uuid: String::from(DECK_UUID),
+            owner_uuid,
+            name,
+            card_set,
+            created_at: now,
+            last_updated_at: now,
+            team_uuid: None,
+        }
+    }
+}
@@ -1,3 +1,15 @@
+table! {
+    decks (uuid) {
+        uuid -> Varchar,
+        owner_uuid -> Varchar,
+        name -> Varchar,
+        card_set -> Array<Text>,
+        created_at -> Timestamp,
+        last_updated_at -> Timestamp,
+        team_uuid -> Nullable<Varchar>,
+    }
+}
+
 table! {
     game_hands (id) {
         id -> Int4,
@@ -5,6 +17,7 @@ table! {
         user_uuid -> Nullable<Varchar>,
         card -> Varchar,
         last_updated_at -> Timestamp,
+        attempt -> Int4,
     }
 }
 
@@ -17,12 +30,26 @@ table! {
         description -> Nullable<Varchar>,
         created_at -> Timestamp,
         last_updated_at -> Timestamp,
+        final_estimate -> Nullable<Varchar>,
+        final_estimate_reason -> Nullable<Varchar>,
+        link -> Nullable<Varchar>,
+        external_id -> Nullable<Varchar>,
+        pre_estimate -> Nullable<Varchar>,
+        started_at -> Nullable<Timestamp>,
     }
 }
 
 table! {
-    room_players (player_uuid) {
-        room_uuid -> Nullable<Varchar>,
+    room_bans (room_uuid, user_uuid) {
+        room_uuid -> Varchar,
+        user_uuid -> Varchar,
+        banned_at -> Timestamp,
+    }
+}
+
+table! {
+    room_players (room_uuid, player_uuid) {
+        room_uuid -> Varchar,
         player_uuid -> Varchar,
         joined_at -> Timestamp,
         left_at -> Nullable<Timestamp>,
@@ -38,6 +65,26 @@ table! {
         owner_uuid -> Varchar,
         created_at -> Timestamp,
         last_updated_at -> Timestamp,
+        options -> Jsonb,
+        join_code -> Nullable<Varchar>,
+        closed_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    team_members (team_uuid, user_uuid) {
+        team_uuid -> Varchar,
+        user_uuid -> Varchar,
+        joined_at -> Timestamp,
+    }
+}
+
+table! {
+    teams (uuid) {
+        uuid -> Varchar,
+        name -> Varchar,
+        created_at -> Timestamp,
+        last_updated_at -> Timestamp,
     }
 }
 
@@ -51,9 +98,24 @@ table! {
     }
 }
 
+joinable!(decks -> teams (team_uuid));
+joinable!(decks -> users (owner_uuid));
 joinable!(game_hands -> games (game_uuid));
 joinable!(game_hands -> users (user_uuid));
+joinable!(room_bans -> rooms (room_uuid));
 joinable!(room_players -> rooms (room_uuid));
 joinable!(room_players -> users (player_uuid));
+joinable!(team_members -> teams (team_uuid));
+joinable!(team_members -> users (user_uuid));
 
-allow_tables_to_appear_in_same_query!(game_hands, games, room_players, rooms, users,);
+allow_tables_to_appear_in_same_query!(
+    decks,
+    game_hands,
+    games,
+    room_bans,
+    room_players,
+    rooms,
+    team_members,
+    teams,
+    users,
+);
@@ -0,0 +1,74 @@
+use awc::http::StatusCode;
+use awc::Client;
+use futures::future::{self, Either};
+use futures::Future;
+
+use crate::common::error::{Error, ErrorKind};
+use crate::user::auth::Params;
+use crate::user::route::{LoginUserReq, LoginUserRes};
+
+use super::connection::{self, ConnectionConfig, ConnectionHandle, EventStream, ReconnectPolicy};
+
+/// Client of the scrum poker server, such as `http://localhost`.
+#[derive(Clone)]
+pub struct ApiClient {
+    base_url: String,
+}
+
+impl ApiClient {
+    pub fn new<S: Into<String>>(base_url: S) -> Self {
+        ApiClient {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Login with the authentication provider, or as a guest with
+    /// `Params::Guest`. The returned token is used to connect to the server.
+    pub fn login(
+        &self,
+        provider_id: &str,
+        params: Params,
+    ) -> impl Future<Item = LoginUserRes, Error = Error> {
+        Client::new()
+            .post(format!("{}/user/login", self.base_url))
+            .send_json(&LoginUserReq {
+                provider_id: String::from(provider_id),
+                params,
+            })
+            .map_err(|err| {
+                Error::new(
+                    ErrorKind::RemoteServerError,
+                    format!("Error when sending login request: {}", err),
+                )
+            })
+            .and_then(|mut res| match res.status() {
+                StatusCode::OK => Either::A(res.json::<LoginUserRes>().map_err(|err| {
+                    Error::new(
+                        ErrorKind::RemoteServerError,
+                        format!("Error when reading login response: {}", err),
+                    )
+                })),
+                StatusCode::UNAUTHORIZED => {
+                    Either::B(future::err(Error::from(ErrorKind::UnauthorizedError)))
+                }
+                status => Either::B(future::err(Error::new(
+                    ErrorKind::RemoteServerError,
+                    format!("Login request failed with status {}", status),
+                ))),
+            })
+    }
+
+    /// Connect to the websocket endpoint of the server with the token issued
+    /// on login.
+    pub fn connect(
+        &self,
+        token: &str,
+        reconnect: ReconnectPolicy,
+    ) -> impl Future<Item = (ConnectionHandle, EventStream), Error = Error> {
+        connection::connect(ConnectionConfig {
+            url: format!("{}/ws/", self.base_url),
+            token: Some(String::from(token)),
+            reconnect,
+        })
+    }
+}
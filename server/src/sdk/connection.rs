@@ -0,0 +1,522 @@
+use std::time::Duration;
+
+use actix::io::{SinkWrite, WriteHandler};
+use actix::prelude::*;
+use actix_codec::Framed;
+use awc::error::WsProtocolError;
+use awc::ws::{CloseCode, CloseReason, Codec, Frame, Message as WsMessage};
+use awc::BoxedSocket;
+use futures::stream::SplitSink;
+use futures::sync::mpsc;
+use futures::{future, Future, Stream};
+use log::{info, warn};
+
+use crate::common::error::{flatten_mailbox_result, Error, ErrorKind, Result as CommonResult};
+use crate::common::message::request::{
+    CreateRoomParams, JoinRoomParams, RequestFrame, ResumeSessionParams,
+};
+use crate::common::message::response::{CreatedRoom, ResponseFrame};
+use crate::common::message::{RequestMessage, ResponseMessage};
+use crate::websocket::protocol::{Encoding, Protocol, ProtocolVersion};
+
+use super::pending::{PendingRequests, Reply};
+
+type WsFramed = Framed<BoxedSocket, Codec>;
+
+/// Protocol spoken by the client. Replies are only correlated since V2.
+const PROTOCOL: Protocol = Protocol {
+    version: ProtocolVersion::V2,
+    encoding: Encoding::Json,
+};
+
+/// ID of the request resuming the session after reconnecting, which never
+/// collides with the numeric IDs of other requests.
+const RESUME_REQUEST_ID: &str = "resume";
+
+#[derive(Debug, Clone)]
+pub struct ConnectionConfig {
+    /// URL of the websocket endpoint, such as `http://localhost/ws/`.
+    pub url: String,
+    /// Bearer token issued on login.
+    pub token: Option<String>,
+    pub reconnect: ReconnectPolicy,
+}
+
+/// Reconnect with exponential backoff after the connection is lost.
+#[derive(Debug, Clone)]
+pub struct ReconnectPolicy {
+    /// Give up after the given number of consecutive failed attempts. Retry
+    /// forever when absent.
+    pub max_attempts: Option<u32>,
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl ReconnectPolicy {
+    /// Never reconnect, and close the connection once it is lost.
+    pub fn disabled() -> Self {
+        ReconnectPolicy {
+            max_attempts: Some(0),
+            ..ReconnectPolicy::default()
+        }
+    }
+
+    /// Delay before the attempt, starting from 0, or None when the client
+    /// should give up.
+    pub fn delay(&self, attempt: u32) -> Option<Duration> {
+        if let Some(max_attempts) = self.max_attempts {
+            if attempt >= max_attempts {
+                return None;
+            }
+        }
+
+        let delay = self
+            .initial_delay
+            .checked_mul(2u32.saturating_pow(attempt))
+            .unwrap_or(self.max_delay);
+        Some(delay.min(self.max_delay))
+    }
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        ReconnectPolicy {
+            max_attempts: Some(10),
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Event of the connection, received from the event stream.
+#[derive(Clone)]
+pub enum ConnectionEvent {
+    /// Message which is not a direct reply to a request, such as events of
+    /// the room and errors of requests without ID.
+    Message(ResponseMessage),
+    /// Connection is lost and the client is reconnecting.
+    Disconnected,
+    /// Connection is established again. The session is resumed with the
+    /// seat in the room joined before, which is followed by the room events
+    /// missed while disconnected, or by a `SessionResumed` message when they
+    /// are no longer available. When the session cannot be resumed, the room
+    /// is joined again instead.
+    Reconnected,
+    /// Connection is closed and no more event will be received.
+    Closed,
+}
+
+pub type EventStream = mpsc::UnboundedReceiver<ConnectionEvent>;
+
+/// Connect to the server, and return the handle to send requests with and
+/// the stream of events of the connection.
+pub fn connect(
+    config: ConnectionConfig,
+) -> impl Future<Item = (ConnectionHandle, EventStream), Error = Error> {
+    open(&config).map(move |framed| {
+        let (events, event_stream) = mpsc::unbounded();
+        let addr = Connection::create(move |ctx| {
+            let mut connection = Connection {
+                config,
+                writer: None,
+                pending: PendingRequests::default(),
+                events,
+                joining_room: None,
+                joined_room: None,
+                resume_token: None,
+                resuming_session: false,
+                last_seq: None,
+                reconnect_attempt: 0,
+                closing: false,
+            };
+            connection.attach(framed, ctx);
+            connection
+        });
+
+        (ConnectionHandle { addr }, event_stream)
+    })
+}
+
+fn open(config: &ConnectionConfig) -> impl Future<Item = WsFramed, Error = Error> {
+    let mut request = awc::Client::new()
+        .ws(config.url.as_str())
+        .protocols(&[PROTOCOL.name()]);
+    if let Some(token) = config.token.as_ref() {
+        request = request.bearer_auth(token);
+    }
+
+    request.connect().map(|(_, framed)| framed).map_err(|err| {
+        Error::new(
+            ErrorKind::RemoteServerError,
+            format!("Error when connecting to websocket server: {}", err),
+        )
+    })
+}
+
+/// Handle of the connection which can be cloned and shared.
+#[derive(Clone)]
+pub struct ConnectionHandle {
+    addr: Addr<Connection>,
+}
+
+impl ConnectionHandle {
+    /// Send the request and resolve with the reply of the server.
+    pub fn request(&self, req: RequestMessage) -> impl Future<Item = Reply, Error = Error> {
+        self.addr
+            .send(SendRequestMessage { req })
+            .then(flatten_mailbox_result)
+    }
+
+    pub fn create_room(
+        &self,
+        params: CreateRoomParams,
+    ) -> impl Future<Item = CreatedRoom, Error = Error> {
+        self.request(RequestMessage::CreateRoom(params))
+            .and_then(|reply| match reply {
+                Reply::RoomCreated(created_room) => Ok(created_room),
+                Reply::Acknowledged => Err(Error::new(
+                    ErrorKind::RemoteServerError,
+                    "Server acknowledged create room request without room details",
+                )),
+            })
+    }
+
+    pub fn join_room(&self, params: JoinRoomParams) -> impl Future<Item = (), Error = Error> {
+        self.request(RequestMessage::JoinRoom(params)).map(|_| ())
+    }
+
+    /// Close the connection without reconnecting.
+    pub fn close(&self) {
+        self.addr.do_send(CloseMessage);
+    }
+}
+
+pub struct Connection {
+    config: ConnectionConfig,
+    writer: Option<SinkWrite<SplitSink<WsFramed>>>,
+    pending: PendingRequests,
+    events: mpsc::UnboundedSender<ConnectionEvent>,
+    /// Join room request waiting for its reply, with its ID.
+    joining_room: Option<(String, JoinRoomParams)>,
+    /// Room to join again after reconnecting.
+    joined_room: Option<JoinRoomParams>,
+    /// Token of the latest connection to resume the session with.
+    resume_token: Option<String>,
+    /// Whether the resume session request is waiting for its reply.
+    resuming_session: bool,
+    /// Sequence number of the latest event received from the room.
+    last_seq: Option<u64>,
+    reconnect_attempt: u32,
+    closing: bool,
+}
+
+impl Actor for Connection {
+    type Context = Context<Self>;
+}
+
+impl Connection {
+    fn attach(&mut self, framed: WsFramed, ctx: &mut Context<Self>) {
+        let (sink, stream) = framed.split();
+        Self::add_stream(stream, ctx);
+        self.writer = Some(SinkWrite::new(sink, ctx));
+    }
+
+    fn write(&mut self, msg: WsMessage) -> CommonResult<()> {
+        let writer = self.writer.as_mut().ok_or_else(|| {
+            Error::new(
+                ErrorKind::SendMessageError,
+                "Connection is lost and the client is reconnecting",
+            )
+        })?;
+
+        writer.write(msg).map(|_| ()).map_err(|err| {
+            Error::new(
+                ErrorKind::SendMessageError,
+                format!("Error when sending message to server: {}", err),
+            )
+        })
+    }
+
+    fn write_request(&mut self, frame: &RequestFrame) -> CommonResult<()> {
+        let msg_text = serde_json::to_string(frame).expect("Error when serializing request");
+        self.write(WsMessage::Text(msg_text))
+    }
+
+    fn emit(&self, event: ConnectionEvent) {
+        // Events are dropped when nobody listens to them
+        let _ = self.events.unbounded_send(event);
+    }
+
+    fn handle_response(&mut self, frame: ResponseFrame) {
+        let msg = frame.msg;
+        if frame.seq.is_some() {
+            self.last_seq = frame.seq;
+        }
+        if let ResponseMessage::Acknowledged(ref ack) = msg {
+            match self.joining_room.take() {
+                Some((request_id, params)) if request_id == ack.request_id => {
+                    self.joined_room = Some(params);
+                }
+                joining_room => self.joining_room = joining_room,
+            }
+        }
+        if self.resuming_session && self.handle_resume_reply(&msg) {
+            return;
+        }
+        match msg {
+            ResponseMessage::Kicked(_) | ResponseMessage::RoomClosed(_) => {
+                self.joined_room = None;
+                self.last_seq = None;
+            }
+            ResponseMessage::SessionResumed(ref snapshot) => {
+                self.last_seq = Some(snapshot.seq);
+            }
+            ResponseMessage::SessionStarted(ref started) => {
+                self.resume_token = Some(started.resume_token.clone());
+                return;
+            }
+            _ => (),
+        }
+
+        if let Some(msg) = self.pending.resolve(msg) {
+            self.emit(ConnectionEvent::Message(msg));
+        }
+    }
+
+    /// Handle the reply to the resume session request, and return whether the
+    /// message is the reply. The room is joined again when the session cannot
+    /// be resumed.
+    fn handle_resume_reply(&mut self, msg: &ResponseMessage) -> bool {
+        match msg {
+            ResponseMessage::Acknowledged(ack) if ack.request_id == RESUME_REQUEST_ID => {
+                self.resuming_session = false;
+                true
+            }
+            ResponseMessage::Error(err) if err.request_id.as_deref() == Some(RESUME_REQUEST_ID) => {
+                warn!("Error when resuming session: {}", err.message);
+                self.resuming_session = false;
+                self.rejoin_room();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn handle_disconnect(&mut self, ctx: &mut Context<Self>) {
+        self.writer = None;
+        self.joining_room = None;
+        self.resuming_session = false;
+        self.pending.fail_all();
+
+        if self.closing {
+            info!("Connection to websocket server is closed");
+            self.emit(ConnectionEvent::Closed);
+            ctx.stop();
+            return;
+        }
+
+        warn!("Connection to websocket server is lost");
+        self.emit(ConnectionEvent::Disconnected);
+        self.schedule_reconnect(ctx);
+    }
+
+    fn schedule_reconnect(&mut self, ctx: &mut Context<Self>) {
+        match self.config.reconnect.delay(self.reconnect_attempt) {
+            Some(delay) => {
+                ctx.run_later(delay, |actor, ctx| actor.reconnect(ctx));
+            }
+            None => {
+                warn!("Giving up reconnecting to websocket server");
+                self.emit(ConnectionEvent::Closed);
+                ctx.stop();
+            }
+        }
+    }
+
+    fn reconnect(&mut self, ctx: &mut Context<Self>) {
+        self.reconnect_attempt += 1;
+
+        open(&self.config)
+            .into_actor(self)
+            .then(|framed_result, actor, ctx| {
+                match framed_result {
+                    Ok(framed) => {
+                        info!("Reconnected to websocket server");
+                        actor.reconnect_attempt = 0;
+                        actor.attach(framed, ctx);
+                        actor.emit(ConnectionEvent::Reconnected);
+                        actor.resume_session();
+                    }
+                    Err(err) => {
+                        warn!("{}", err);
+                        actor.schedule_reconnect(ctx);
+                    }
+                };
+                fut::ok(())
+            })
+            .spawn(ctx);
+    }
+
+    /// Resume the session of the previous connection to take back its seat in
+    /// the room, or join the room again when there is no session to resume.
+    fn resume_session(&mut self) {
+        if self.joined_room.is_none() {
+            return;
+        }
+        let resume_token = match self.resume_token.clone() {
+            Some(resume_token) => resume_token,
+            None => return self.rejoin_room(),
+        };
+
+        let frame = RequestFrame {
+            request_id: Some(String::from(RESUME_REQUEST_ID)),
+            req: RequestMessage::ResumeSession(ResumeSessionParams {
+                resume_token,
+                last_seq: self.last_seq,
+            }),
+        };
+        match self.write_request(&frame) {
+            Ok(_) => self.resuming_session = true,
+            Err(err) => warn!("Error when resuming session: {}", err),
+        }
+    }
+
+    /// Join the room joined before reconnecting. Errors are reported through
+    /// the event stream, as there is no request waiting for them.
+    fn rejoin_room(&mut self) {
+        let params = match self.joined_room.clone() {
+            Some(params) => params,
+            None => return,
+        };
+
+        let frame = RequestFrame {
+            request_id: None,
+            req: RequestMessage::JoinRoom(params),
+        };
+        if let Err(err) = self.write_request(&frame) {
+            warn!("Error when joining room again: {}", err);
+        }
+    }
+}
+
+#[derive(Message)]
+#[rtype(result = "CommonResult<Reply>")]
+pub struct SendRequestMessage {
+    pub req: RequestMessage,
+}
+
+impl Handler<SendRequestMessage> for Connection {
+    type Result = ResponseFuture<Reply, Error>;
+
+    fn handle(&mut self, msg: SendRequestMessage, _: &mut Self::Context) -> Self::Result {
+        let (request_id, reply) = self.pending.register();
+        if let RequestMessage::JoinRoom(ref params) = msg.req {
+            self.joining_room = Some((request_id.clone(), params.clone()));
+            // Sequence numbers of the events differ from room to room
+            self.last_seq = None;
+        }
+
+        let frame = RequestFrame {
+            request_id: Some(request_id.clone()),
+            req: msg.req,
+        };
+        match self.write_request(&frame) {
+            Ok(_) => Box::new(reply),
+            Err(err) => {
+                self.pending.remove(&request_id);
+                Box::new(future::err(err))
+            }
+        }
+    }
+}
+
+#[derive(Message)]
+pub struct CloseMessage;
+
+impl Handler<CloseMessage> for Connection {
+    type Result = ();
+
+    fn handle(&mut self, _: CloseMessage, ctx: &mut Self::Context) {
+        self.closing = true;
+        match self.writer.as_mut() {
+            Some(writer) => {
+                let _ = writer.write(WsMessage::Close(Some(CloseReason::from(CloseCode::Normal))));
+                writer.close();
+            }
+            None => {
+                self.emit(ConnectionEvent::Closed);
+                ctx.stop();
+            }
+        }
+    }
+}
+
+impl StreamHandler<Frame, WsProtocolError> for Connection {
+    fn handle(&mut self, frame: Frame, _: &mut Self::Context) {
+        match frame {
+            Frame::Text(Some(msg_bytes)) => {
+                match serde_json::from_slice::<ResponseFrame>(&msg_bytes) {
+                    Ok(frame) => self.handle_response(frame),
+                    Err(err) => warn!("Unrecognized message from websocket server: {}", err),
+                }
+            }
+            Frame::Ping(msg) => {
+                let _ = self.write(WsMessage::Pong(msg));
+            }
+            Frame::Close(reason) => info!("Websocket server closed connection: {:?}", reason),
+            _ => (),
+        }
+    }
+
+    fn error(&mut self, err: WsProtocolError, _: &mut Self::Context) -> Running {
+        warn!("Error in connection to websocket server: {}", err);
+        Running::Stop
+    }
+
+    fn finished(&mut self, ctx: &mut Self::Context) {
+        self.handle_disconnect(ctx);
+    }
+}
+
+impl WriteHandler<WsProtocolError> for Connection {
+    fn error(&mut self, err: WsProtocolError, _: &mut Self::Context) -> Running {
+        warn!("Error when writing to websocket server: {}", err);
+        Running::Stop
+    }
+
+    /// The connection is handled as lost when the read stream finishes.
+    fn finished(&mut self, _: &mut Self::Context) {}
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    mod reconnect_policy {
+        use super::*;
+
+        #[test]
+        fn should_double_delay_until_max_delay() {
+            let policy = ReconnectPolicy {
+                max_attempts: None,
+                initial_delay: Duration::from_secs(1),
+                max_delay: Duration::from_secs(5),
+            };
+
+            assert_eq!(policy.delay(0), Some(Duration::from_secs(1)));
+            assert_eq!(policy.delay(1), Some(Duration::from_secs(2)));
+            assert_eq!(policy.delay(2), Some(Duration::from_secs(4)));
+            assert_eq!(policy.delay(3), Some(Duration::from_secs(5)));
+            assert_eq!(policy.delay(64), Some(Duration::from_secs(5)));
+        }
+
+        #[test]
+        fn should_give_up_after_max_attempts() {
+            let policy = ReconnectPolicy::default();
+
+            assert!(policy.delay(9).is_some());
+            assert!(policy.delay(10).is_none());
+            assert!(ReconnectPolicy::disabled().delay(0).is_none());
+        }
+    }
+}
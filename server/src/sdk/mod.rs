@@ -0,0 +1,17 @@
+//! Client of the scrum poker server for bots and integration tests. It logs
+//! in over HTTP, then connects to the websocket endpoint, where each request
+//! resolves with the reply correlated by its request ID, and events of the
+//! room are delivered through an event stream. Lost connections are
+//! reconnected with backoff, and the joined room is joined again.
+//!
+//! The client runs inside an actix system, the same as the server.
+
+mod api;
+mod connection;
+mod pending;
+
+pub use api::ApiClient;
+pub use connection::{
+    connect, ConnectionConfig, ConnectionEvent, ConnectionHandle, EventStream, ReconnectPolicy,
+};
+pub use pending::Reply;
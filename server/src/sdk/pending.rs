@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+
+use futures::sync::oneshot;
+use futures::Future;
+
+use crate::common::error::{Error, ErrorKind, Result as CommonResult};
+use crate::common::message::response::{CreatedRoom, RequestError};
+use crate::common::message::ResponseMessage;
+
+/// Direct reply of the server to a request.
+#[derive(Debug, Clone)]
+pub enum Reply {
+    Acknowledged,
+    RoomCreated(CreatedRoom),
+}
+
+/// Requests waiting for their replies, keyed by the IDs sent along with them.
+#[derive(Default)]
+pub struct PendingRequests {
+    next_id: u64,
+    senders: HashMap<String, oneshot::Sender<CommonResult<Reply>>>,
+}
+
+impl PendingRequests {
+    /// Allocate an ID for a new request, and return it with the future of the
+    /// reply to the request.
+    pub fn register(&mut self) -> (String, impl Future<Item = Reply, Error = Error>) {
+        self.next_id += 1;
+        let request_id = self.next_id.to_string();
+        let (sender, receiver) = oneshot::channel();
+        self.senders.insert(request_id.clone(), sender);
+
+        let reply = receiver.then(|reply_result| match reply_result {
+            Ok(reply) => reply,
+            Err(_) => Err(Error::new(
+                ErrorKind::SendMessageError,
+                "Connection is closed before the reply is received",
+            )),
+        });
+
+        (request_id, reply)
+    }
+
+    pub fn remove(&mut self, request_id: &str) {
+        self.senders.remove(request_id);
+    }
+
+    /// Resolve the request which the message replies to. The message is
+    /// returned when it is not a reply to any pending request.
+    pub fn resolve(&mut self, msg: ResponseMessage) -> Option<ResponseMessage> {
+        let sender = reply_request_id(&msg).and_then(|request_id| self.senders.remove(request_id));
+        let sender = match sender {
+            Some(sender) => sender,
+            None => return Some(msg),
+        };
+
+        let reply = match msg {
+            ResponseMessage::Error(err) => Err(remote_error(err)),
+            ResponseMessage::RoomCreated(created_room) => Ok(Reply::RoomCreated(created_room)),
+            _ => Ok(Reply::Acknowledged),
+        };
+        // The request is abandoned when its future is dropped
+        let _ = sender.send(reply);
+
+        None
+    }
+
+    /// Fail all pending requests, as their replies will never be received.
+    pub fn fail_all(&mut self) {
+        for (_, sender) in self.senders.drain() {
+            let _ = sender.send(Err(Error::new(
+                ErrorKind::SendMessageError,
+                "Connection is lost before the reply is received",
+            )));
+        }
+    }
+}
+
+fn reply_request_id(msg: &ResponseMessage) -> Option<&str> {
+    match msg {
+        ResponseMessage::Acknowledged(ack) => Some(&ack.request_id),
+        ResponseMessage::RoomCreated(created_room) => created_room.request_id.as_deref(),
+        ResponseMessage::Error(err) => err.request_id.as_deref(),
+        _ => None,
+    }
+}
+
+fn remote_error(err: RequestError) -> Error {
+    let kind = ErrorKind::from_code(&err.code).unwrap_or(ErrorKind::UnknownError);
+
+    Error::new(kind, err.message)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::common::message::response::Acknowledgement;
+    use crate::poker::model::RoomOptions;
+
+    mod register {
+        use super::*;
+
+        #[test]
+        fn should_allocate_unique_request_ids() {
+            let mut pending = PendingRequests::default();
+
+            let (first_id, _) = pending.register();
+            let (second_id, _) = pending.register();
+
+            assert_ne!(first_id, second_id);
+            assert_eq!(pending.senders.len(), 2);
+        }
+    }
+
+    mod resolve {
+        use super::*;
+
+        #[test]
+        fn should_resolve_request_with_acknowledgement() {
+            let mut pending = PendingRequests::default();
+            let (request_id, reply) = pending.register();
+
+            let unresolved = pending.resolve(ResponseMessage::Acknowledged(Acknowledgement {
+                request_id,
+            }));
+
+            assert!(unresolved.is_none());
+            assert!(pending.senders.is_empty());
+            match reply.wait().unwrap() {
+                Reply::Acknowledged => (),
+                _ => panic!("Unexpected reply"),
+            }
+        }
+
+        #[test]
+        fn should_resolve_create_room_request_with_created_room() {
+            let mut pending = PendingRequests::default();
+            let (request_id, reply) = pending.register();
+
+            pending.resolve(ResponseMessage::RoomCreated(CreatedRoom {
+                uuid: String::from("room-uuid"),
+                join_code: String::from("ABCD2345"),
+                private: false,
+                card_set: vec![],
+                cards: vec![],
+                options: RoomOptions::default(),
+                request_id: Some(request_id),
+            }));
+
+            match reply.wait().unwrap() {
+                Reply::RoomCreated(created_room) => assert_eq!(created_room.uuid, "room-uuid"),
+                _ => panic!("Unexpected reply"),
+            }
+        }
+
+        #[test]
+        fn should_fail_request_with_kind_of_error_code() {
+            let mut pending = PendingRequests::default();
+            let (request_id, reply) = pending.register();
+
+            pending.resolve(ResponseMessage::Error(RequestError::new(
+                &Error::new(ErrorKind::RoomNotFound, "Room not found"),
+                Some(request_id),
+            )));
+
+            let err = reply.wait().unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::RoomNotFound);
+            assert_eq!(err.message(), "Room not found");
+        }
+
+        #[test]
+        fn should_return_message_which_is_not_a_reply() {
+            let mut pending = PendingRequests::default();
+            let _reply = pending.register();
+
+            let unresolved = pending.resolve(ResponseMessage::UserJoined(String::from("uuid")));
+
+            match unresolved {
+                Some(ResponseMessage::UserJoined(_)) => (),
+                _ => panic!("Unexpected message"),
+            }
+            assert_eq!(pending.senders.len(), 1);
+        }
+    }
+
+    mod fail_all {
+        use super::*;
+
+        #[test]
+        fn should_fail_all_pending_requests() {
+            let mut pending = PendingRequests::default();
+            let (_, reply) = pending.register();
+
+            pending.fail_all();
+
+            assert!(pending.senders.is_empty());
+            assert_eq!(
+                reply.wait().unwrap_err().kind(),
+                ErrorKind::SendMessageError
+            );
+        }
+    }
+}
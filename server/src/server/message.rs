@@ -6,6 +6,7 @@ use crate::client::channel::ClientChannel;
 use crate::client::ClientId;
 use crate::common::error::Result as CommonResult;
 use crate::common::message::request::CreateRoomParams;
+use crate::common::message::response::CreatedRoom;
 use crate::common::model::Uuid;
 use crate::poker::Room;
 use crate::user::info::SharedUserInfo;
@@ -13,8 +14,10 @@ use crate::user::info::SharedUserInfo;
 use crate::client::store::ClientStore;
 use crate::poker::model::RoomORM;
 
+/// Connect the websocket client to the server. Return the client Id with
+/// the token to resume the session with.
 #[derive(Message)]
-#[rtype(usize)]
+#[rtype(result = "ConnectedClient")]
 pub struct ConnectMessage<T>
 where
     T: ClientChannel,
@@ -23,8 +26,47 @@ where
     pub channel: T,
 }
 
+pub struct ConnectedClient {
+    pub client_id: ClientId,
+    pub resume_token: String,
+}
+
+/// Remove the disconnected websocket client from the server. The client in a
+/// room is kept for the resume grace window instead, so that it can resume
+/// the session with the same seat in the room.
 #[derive(Message)]
-#[rtype(result = "CommonResult<Addr<Room<R, S, T>>>")]
+pub struct DisconnectMessage<R, S, T>
+where
+    R: RoomORM + 'static,
+    S: ClientStore<T> + 'static,
+    T: ClientChannel + 'static,
+{
+    pub client_id: ClientId,
+    pub room_addr: Option<Addr<Room<R, S, T>>>,
+}
+
+/// Resume the session of the disconnected client with its resume token. The
+/// resuming client takes over the client Id of the session. Return the
+/// client Id with the address of the room of the session.
+#[derive(Message)]
+#[rtype(result = "CommonResult<(ClientId, Addr<Room<R, S, T>>)>")]
+pub struct ResumeSessionMessage<R, S, T>
+where
+    R: RoomORM + 'static,
+    S: ClientStore<T> + 'static,
+    T: ClientChannel + 'static,
+{
+    pub client_id: ClientId,
+    pub resume_token: String,
+    pub room_orm_type: PhantomData<R>,
+    pub client_store_type: PhantomData<S>,
+    pub client_channel_type: PhantomData<T>,
+}
+
+/// Create room for the websocket client. Return the room address with the
+/// details of the created room, which are to be replied to the client.
+#[derive(Message)]
+#[rtype(result = "CommonResult<(Addr<Room<R, S, T>>, CreatedRoom)>")]
 pub struct CreateRoomMessage<R, S, T>
 where
     R: RoomORM + 'static,
@@ -38,6 +80,39 @@ where
     pub client_channel_type: PhantomData<T>,
 }
 
+/// Create room on behalf of HTTP request. The owner takes over the room by
+/// joining it from a websocket client.
+#[derive(Message)]
+#[rtype(result = "CommonResult<CreatedRoom>")]
+pub struct CreateRoomForUserMessage {
+    pub owner_uuid: Uuid,
+    pub params: CreateRoomParams,
+}
+
+/// Close room on behalf of HTTP request and stop tracking it.
+#[derive(Message)]
+#[rtype(result = "CommonResult<()>")]
+pub struct CloseRoomMessage {
+    pub user_uuid: Uuid,
+    pub room_uuid: Uuid,
+}
+
+/// Find room by Uuid on behalf of HTTP request, which has no websocket
+/// client.
+#[derive(Message)]
+#[rtype(result = "CommonResult<Addr<Room<R, S, T>>>")]
+pub struct LookupRoomMessage<R, S, T>
+where
+    R: RoomORM + 'static,
+    S: ClientStore<T> + 'static,
+    T: ClientChannel + 'static,
+{
+    pub room_uuid: Uuid,
+    pub room_orm_type: PhantomData<R>,
+    pub client_store_type: PhantomData<S>,
+    pub client_channel_type: PhantomData<T>,
+}
+
 #[derive(Message)]
 #[rtype(result = "CommonResult<Addr<Room<R, S, T>>>")]
 pub struct FindRoomMessage<R, S, T>
@@ -47,7 +122,8 @@ where
     T: ClientChannel + 'static,
 {
     pub client_id: ClientId,
-    pub room_uuid: Uuid,
+    pub room_uuid: Option<Uuid>,
+    pub join_code: Option<String>,
     pub room_orm_type: PhantomData<R>,
     pub client_store_type: PhantomData<S>,
     pub client_channel_type: PhantomData<T>,
@@ -1,58 +1,91 @@
 use std::collections::HashMap;
 use std::marker::PhantomData;
+use std::time::Duration;
 
 use actix::prelude::*;
-use log::error;
+use log::{error, info};
+use rand::distributions::Alphanumeric;
 use rand::prelude::*;
 
 use crate::client::channel::ClientChannel;
 use crate::client::store::{ClientStore, SharedClientStore};
 use crate::client::{Client, ClientId};
-use crate::common::error::{Error, ErrorKind, ErrorKindExt, Result as CommonResult};
+use crate::common::error::{
+    flatten_mailbox_result, ContextExt, Error, ErrorKind, ErrorKindExt, Result as CommonResult,
+};
 use crate::common::message::request::CreateRoomParams;
+use crate::common::message::response::CreatedRoom;
 use crate::common::model::Uuid;
-use crate::poker::model::RoomORM;
+use crate::poker::deck;
+use crate::poker::model::{DeckORM, RoomORM};
+use crate::poker::room::message::{
+    CloseRoomMessage as CloseRoomRoomMessage, DetachClientMessage, LeaveRoomMessage,
+};
 use crate::poker::room::NewRoomParams;
 use crate::poker::Room;
 use crate::user::model::UserORM;
 
-use message::{ConnectMessage, CreateRoomMessage, FindRoomMessage};
+use message::{
+    CloseRoomMessage, ConnectMessage, ConnectedClient, CreateRoomForUserMessage, CreateRoomMessage,
+    DisconnectMessage, FindRoomMessage, LookupRoomMessage, ResumeSessionMessage,
+};
 
 pub mod message;
 
-pub struct Server<U, R, S, T>
+type RoomAddr<R, S, T> = Addr<Room<R, S, T>>;
+
+pub const DEFAULT_RESUME_GRACE: Duration = Duration::from_secs(60);
+
+const RESUME_TOKEN_LEN: usize = 32;
+const JOIN_CODE_LEN: usize = 8;
+/// Letters and digits which are not easily mistaken for one another.
+const JOIN_CODE_CHARSET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+pub struct Server<U, R, D, S, T>
 where
     U: UserORM + 'static,
     R: RoomORM + Clone + 'static,
+    D: DeckORM + 'static,
     S: ClientStore<T> + 'static,
     T: ClientChannel + 'static,
 {
     user_model: U,
     room_model: R,
+    deck_model: D,
     client_store: SharedClientStore<S, T>,
     rooms: HashMap<Uuid, Addr<Room<R, S, T>>>,
+    /// Room Uuids by the join codes of the rooms.
+    join_codes: HashMap<String, Uuid>,
+    /// Client Ids by the tokens to resume their sessions with.
+    resume_tokens: HashMap<String, ClientId>,
+    /// Clients which have disconnected from a room, with the handles of the
+    /// timers removing them once the resume grace window is over.
+    detached_clients: HashMap<ClientId, (RoomAddr<R, S, T>, SpawnHandle)>,
+    resume_grace: Duration,
     rng: ThreadRng,
     client_store_channel_type: PhantomData<T>,
 }
 
-impl<U, R, S, T> Actor for Server<U, R, S, T>
+impl<U, R, D, S, T> Actor for Server<U, R, D, S, T>
 where
     U: UserORM,
     R: RoomORM + Clone,
+    D: DeckORM,
     S: ClientStore<T>,
     T: ClientChannel,
 {
     type Context = Context<Self>;
 }
 
-impl<U, R, S, T> Handler<ConnectMessage<T>> for Server<U, R, S, T>
+impl<U, R, D, S, T> Handler<ConnectMessage<T>> for Server<U, R, D, S, T>
 where
     U: UserORM,
     R: RoomORM + Clone,
+    D: DeckORM,
     S: ClientStore<T>,
     T: ClientChannel,
 {
-    type Result = usize;
+    type Result = MessageResult<ConnectMessage<T>>;
 
     fn handle(&mut self, msg: ConnectMessage<T>, _: &mut Context<Self>) -> Self::Result {
         let client_id = self.generate_client_id();
@@ -62,23 +95,136 @@ where
         };
         self.client_store.get_writable().insert(client_id, client);
 
-        client_id
+        let resume_token = self.generate_resume_token();
+        self.resume_tokens.insert(resume_token.clone(), client_id);
+
+        MessageResult(ConnectedClient {
+            client_id,
+            resume_token,
+        })
     }
 }
 
-impl<U, R, S, T> Handler<CreateRoomMessage<R, S, T>> for Server<U, R, S, T>
+impl<U, R, D, S, T> Handler<DisconnectMessage<R, S, T>> for Server<U, R, D, S, T>
 where
     U: UserORM,
     R: RoomORM + Clone,
+    D: DeckORM,
     S: ClientStore<T>,
     T: ClientChannel,
 {
-    type Result = CommonResult<Addr<Room<R, S, T>>>;
+    type Result = ();
+
+    fn handle(&mut self, msg: DisconnectMessage<R, S, T>, ctx: &mut Context<Self>) -> Self::Result {
+        let client_id = msg.client_id;
+        let room_addr = match msg.room_addr {
+            Some(room_addr) => room_addr,
+            None => {
+                self.remove_client(client_id);
+                return;
+            }
+        };
+
+        room_addr.do_send(DetachClientMessage { client_id });
+        let expiry = ctx.run_later(self.resume_grace, move |server, _ctx| {
+            server.expire_detached_client(client_id)
+        });
+        self.detached_clients.insert(client_id, (room_addr, expiry));
+    }
+}
+
+impl<U, R, D, S, T> Handler<ResumeSessionMessage<R, S, T>> for Server<U, R, D, S, T>
+where
+    U: UserORM,
+    R: RoomORM + Clone,
+    D: DeckORM,
+    S: ClientStore<T>,
+    T: ClientChannel,
+{
+    type Result = CommonResult<(ClientId, RoomAddr<R, S, T>)>;
+
+    fn handle(
+        &mut self,
+        msg: ResumeSessionMessage<R, S, T>,
+        ctx: &mut Context<Self>,
+    ) -> Self::Result {
+        let detached_client_id = self
+            .resume_tokens
+            .get(&msg.resume_token)
+            .cloned()
+            .filter(|client_id| self.detached_clients.contains_key(client_id))
+            .context(|| {
+                (
+                    ErrorKind::SessionNotFound,
+                    "Session is not disconnected or its resume grace window is over",
+                )
+            })?;
+
+        {
+            let mut client_store = self.client_store.get_writable();
+            let user_uuid = |client: &Client<T>| client.user_info.get_readable().uuid.clone();
+            let resuming_uuid = client_store
+                .get(&msg.client_id)
+                .map(user_uuid)
+                .kind(|| ErrorKind::MissingClientError)?;
+            if client_store.get(&detached_client_id).map(user_uuid) != Some(resuming_uuid) {
+                return Err(Error::new(
+                    ErrorKind::ForbiddenError,
+                    "Session belongs to another user",
+                ));
+            }
+
+            let resuming_client = client_store
+                .remove(&msg.client_id)
+                .kind(|| ErrorKind::MissingClientError)?;
+            if let Some(detached_client) = client_store.get_mut(&detached_client_id) {
+                detached_client.channel = resuming_client.channel;
+            }
+        }
+
+        // The token is used up, and the resuming connection's own token is
+        // carried over to resume the session next time.
+        self.resume_tokens
+            .retain(|_, client_id| *client_id != detached_client_id);
+        for client_id in self.resume_tokens.values_mut() {
+            if *client_id == msg.client_id {
+                *client_id = detached_client_id;
+            }
+        }
 
-    fn handle(&mut self, msg: CreateRoomMessage<R, S, T>, ctx: &mut Context<Self>) -> Self::Result {
+        let (room_addr, expiry) = self
+            .detached_clients
+            .remove(&detached_client_id)
+            .kind(|| ErrorKind::SessionNotFound)?;
+        ctx.cancel_future(expiry);
+
+        info!(
+            "Websocket client {} resumed session of client {}",
+            msg.client_id, detached_client_id
+        );
+
+        Ok((detached_client_id, room_addr))
+    }
+}
+
+impl<U, R, D, S, T> Handler<CreateRoomMessage<R, S, T>> for Server<U, R, D, S, T>
+where
+    U: UserORM,
+    R: RoomORM + Clone,
+    D: DeckORM,
+    S: ClientStore<T>,
+    T: ClientChannel,
+{
+    type Result = CommonResult<(RoomAddr<R, S, T>, CreatedRoom)>;
+
+    fn handle(
+        &mut self,
+        msg: CreateRoomMessage<R, S, T>,
+        _ctx: &mut Context<Self>,
+    ) -> Self::Result {
         // TODO: Extract method to check for client existence
-        let channel = match self.client_store.get_readable().get(&msg.client_id) {
-            Some(channel) => channel,
+        let owner_uuid = match self.client_store.get_readable().get(&msg.client_id) {
+            Some(client) => client.user_info.get_readable().uuid.clone(),
             None => {
                 error!(
                     "Received request from deleted websocket client {}",
@@ -88,14 +234,60 @@ where
             }
         };
 
-        self.create_room(msg.params, msg.client_id)
+        self.create_room(msg.params, Some(msg.client_id), owner_uuid)
+    }
+}
+
+impl<U, R, D, S, T> Handler<CreateRoomForUserMessage> for Server<U, R, D, S, T>
+where
+    U: UserORM,
+    R: RoomORM + Clone,
+    D: DeckORM,
+    S: ClientStore<T>,
+    T: ClientChannel,
+{
+    type Result = CommonResult<CreatedRoom>;
+
+    fn handle(&mut self, msg: CreateRoomForUserMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        self.create_room(msg.params, None, msg.owner_uuid)
+            .map(|(_, created_room)| created_room)
+    }
+}
+
+impl<U, R, D, S, T> Handler<CloseRoomMessage> for Server<U, R, D, S, T>
+where
+    U: UserORM,
+    R: RoomORM + Clone,
+    D: DeckORM,
+    S: ClientStore<T>,
+    T: ClientChannel,
+{
+    type Result = ResponseActFuture<Self, (), Error>;
+
+    fn handle(&mut self, msg: CloseRoomMessage, _ctx: &mut Context<Self>) -> Self::Result {
+        let room_addr = match self.find_room(msg.room_uuid.clone()) {
+            Ok(room_addr) => room_addr,
+            Err(err) => return Box::new(fut::err(err)),
+        };
+
+        let room_uuid = msg.room_uuid;
+        Box::new(
+            room_addr
+                .send(CloseRoomRoomMessage {
+                    user_uuid: msg.user_uuid,
+                })
+                .then(flatten_mailbox_result)
+                .into_actor(self)
+                .map(move |_, server, _ctx| server.remove_room(&room_uuid)),
+        )
     }
 }
 
-impl<U, R, S, T> Handler<FindRoomMessage<R, S, T>> for Server<U, R, S, T>
+impl<U, R, D, S, T> Handler<FindRoomMessage<R, S, T>> for Server<U, R, D, S, T>
 where
     U: UserORM,
     R: RoomORM + Clone,
+    D: DeckORM,
     S: ClientStore<T>,
     T: ClientChannel,
 {
@@ -114,23 +306,61 @@ where
             }
         };
 
+        match (msg.room_uuid, msg.join_code) {
+            (Some(room_uuid), None) => self.find_room(room_uuid),
+            (None, Some(join_code)) => self.find_room_by_join_code(&join_code),
+            _ => Err(Error::new(
+                ErrorKind::InvalidParams,
+                "Either room_uuid or join_code has to be provided",
+            )),
+        }
+    }
+}
+
+impl<U, R, D, S, T> Handler<LookupRoomMessage<R, S, T>> for Server<U, R, D, S, T>
+where
+    U: UserORM,
+    R: RoomORM + Clone,
+    D: DeckORM,
+    S: ClientStore<T>,
+    T: ClientChannel,
+{
+    type Result = CommonResult<Addr<Room<R, S, T>>>;
+
+    fn handle(
+        &mut self,
+        msg: LookupRoomMessage<R, S, T>,
+        _ctx: &mut Context<Self>,
+    ) -> Self::Result {
         self.find_room(msg.room_uuid)
     }
 }
 
-impl<U, R, S, T> Server<U, R, S, T>
+impl<U, R, D, S, T> Server<U, R, D, S, T>
 where
     U: UserORM,
     R: RoomORM + Clone,
+    D: DeckORM,
     S: ClientStore<T>,
     T: ClientChannel,
 {
-    pub fn new(user_model: U, room_model: R, client_store: S) -> Server<U, R, S, T> {
+    pub fn new(
+        user_model: U,
+        room_model: R,
+        deck_model: D,
+        client_store: S,
+        resume_grace: Duration,
+    ) -> Server<U, R, D, S, T> {
         Server {
             user_model,
             room_model,
+            deck_model,
             client_store: SharedClientStore::new(client_store),
             rooms: HashMap::new(),
+            join_codes: HashMap::new(),
+            resume_tokens: HashMap::new(),
+            detached_clients: HashMap::new(),
+            resume_grace,
             rng: rand::thread_rng(),
             client_store_channel_type: PhantomData,
         }
@@ -150,6 +380,31 @@ where
         !self.client_store.get_readable().contains_key(&client_id)
     }
 
+    fn generate_resume_token(&mut self) -> String {
+        loop {
+            let resume_token: String = (&mut self.rng)
+                .sample_iter(Alphanumeric)
+                .take(RESUME_TOKEN_LEN)
+                .collect();
+
+            if !self.resume_tokens.contains_key(&resume_token) {
+                break resume_token;
+            }
+        }
+    }
+
+    fn generate_join_code(&mut self) -> String {
+        loop {
+            let join_code: String = (0..JOIN_CODE_LEN)
+                .map(|_| char::from(*JOIN_CODE_CHARSET.choose(&mut self.rng).unwrap()))
+                .collect();
+
+            if !self.join_codes.contains_key(&join_code) {
+                break join_code;
+            }
+        }
+    }
+
     // fn find_addr_by_client_id(&self, client_id: ClientId) -> Option<Addr<Session<U, R, S, T>>> {
     //     self.client_store
     //         .get(&client_id)
@@ -157,38 +412,126 @@ where
     // }
 }
 
-impl<U, R, S, T> Server<U, R, S, T>
+impl<U, R, D, S, T> Server<U, R, D, S, T>
 where
     U: UserORM,
     R: RoomORM + Clone,
+    D: DeckORM,
     S: ClientStore<T>,
     T: ClientChannel,
 {
-    /// Create room actor and return created room Uuid.
+    /// Create room actor and return its address with the details of the
+    /// created room.
     fn create_room(
         &mut self,
         params: CreateRoomParams,
-        owner_client_id: ClientId,
-    ) -> CommonResult<Addr<Room<R, S, T>>> {
+        owner_client_id: Option<ClientId>,
+        owner_uuid: Uuid,
+    ) -> CommonResult<(RoomAddr<R, S, T>, CreatedRoom)> {
+        let card_set = match params.deck_uuid {
+            Some(deck_uuid) => {
+                if params.card_set.is_some() || params.deck_preset.is_some() {
+                    return Err(Error::new(
+                        ErrorKind::InvalidParams,
+                        "Only one of card_set, deck_preset and deck_uuid can be provided",
+                    ));
+                }
+                deck::find_accessible_deck(&self.deck_model, &deck_uuid, &owner_uuid)?.card_set
+            }
+            None => deck::resolve_card_set(params.card_set, params.deck_preset)?,
+        };
         let params: NewRoomParams = NewRoomParams {
             passphrase: params.passphrase,
-            card_set: params.card_set,
+            card_set,
+            options: params.options,
             owner_client_id,
+            owner_uuid,
+            join_code: self.generate_join_code(),
         };
         let mut room = Room::new(params, self.room_model.clone(), self.client_store.clone());
 
         let room_uuid = room.create()?;
+        let created_room = room.created_room()?;
         let room_addr = room.start();
 
-        self.rooms.insert(room_uuid, room_addr.clone());
+        self.rooms.insert(room_uuid.clone(), room_addr.clone());
+        self.join_codes
+            .insert(created_room.join_code.clone(), room_uuid);
 
-        Ok(room_addr)
+        Ok((room_addr, created_room))
     }
 
+    /// Find the room actor, or load the room from its record when the room
+    /// is not running, such as after the server restarts.
     fn find_room(&mut self, room_uuid: Uuid) -> CommonResult<Addr<Room<R, S, T>>> {
-        self.rooms
-            .get(&room_uuid)
-            .map(|addr| addr.clone())
-            .kind(|| ErrorKind::RoomNotFound)
+        if let Some(room_addr) = self.rooms.get(&room_uuid) {
+            return Ok(room_addr.clone());
+        }
+
+        let room_record = self
+            .room_model
+            .find_by_uuid(&room_uuid)?
+            .filter(|room_record| room_record.closed_at.is_none())
+            .kind(|| ErrorKind::RoomNotFound)?;
+        let join_code = room_record.join_code.clone();
+        let room = Room::load(
+            room_record,
+            self.room_model.clone(),
+            self.client_store.clone(),
+        )?;
+        let room_addr = room.start();
+
+        self.rooms.insert(room_uuid.clone(), room_addr.clone());
+        if let Some(join_code) = join_code {
+            self.join_codes.insert(join_code, room_uuid);
+        }
+
+        Ok(room_addr)
+    }
+
+    /// Find room by join code, which is case-insensitive.
+    fn find_room_by_join_code(&mut self, join_code: &str) -> CommonResult<Addr<Room<R, S, T>>> {
+        let room_uuid = self
+            .join_codes
+            .get(&join_code.trim().to_uppercase())
+            .cloned()
+            .kind(|| ErrorKind::RoomNotFound)?;
+
+        self.find_room(room_uuid)
+    }
+
+    /// Remove the client which has not resumed its session within the grace
+    /// window, and let it leave its room.
+    fn expire_detached_client(&mut self, client_id: ClientId) {
+        let (room_addr, _expiry) = match self.detached_clients.remove(&client_id) {
+            Some(detached_client) => detached_client,
+            None => return,
+        };
+
+        let player_uuid = self
+            .client_store
+            .get_readable()
+            .get(&client_id)
+            .map(|client| client.user_info.get_readable().uuid.clone());
+        if let Some(player_uuid) = player_uuid {
+            room_addr.do_send(LeaveRoomMessage {
+                client_id,
+                player_uuid,
+            });
+        }
+
+        self.remove_client(client_id);
+    }
+
+    fn remove_client(&mut self, client_id: ClientId) {
+        self.client_store.get_writable().remove(&client_id);
+        self.resume_tokens
+            .retain(|_, token_client_id| *token_client_id != client_id);
+    }
+
+    fn remove_room(&mut self, room_uuid: &str) {
+        self.rooms.remove(room_uuid);
+        self.join_codes
+            .retain(|_, join_code_room_uuid| join_code_room_uuid != room_uuid);
     }
 }
@@ -1,19 +1,22 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 pub mod oauth;
 pub mod provider;
 pub mod provider_service;
+pub mod token;
 
 pub use provider_service::ProviderService;
+pub use token::{AuthTokenService, AuthenticatedUser};
 
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(untagged)]
 pub enum Params {
     OAuth(OAuthParams),
     Guest,
 }
 
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize, JsonSchema)]
 pub struct OAuthParams {
     pub auth_code: String,
 }
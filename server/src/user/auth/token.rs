@@ -0,0 +1,237 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use actix_web::dev::Payload;
+use actix_web::error::ErrorUnauthorized;
+use actix_web::http::header;
+use actix_web::{Error as ActixWebError, FromRequest, HttpRequest};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::common::error::{Error, ErrorKind, Result as CommonResult};
+use crate::common::model::Uuid;
+
+pub const DEFAULT_TOKEN_TTL: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+const BEARER_PREFIX: &str = "Bearer ";
+
+/// Issues and verifies signed bearer tokens in the form of
+/// `{user_uuid}.{issued_at}.{signature}`, where the signature is the
+/// HMAC-SHA256 of the rest of the token.
+#[derive(Clone)]
+pub struct AuthTokenService {
+    secret: String,
+    ttl: Duration,
+}
+
+impl AuthTokenService {
+    pub fn new(secret: String, ttl: Duration) -> Self {
+        AuthTokenService { secret, ttl }
+    }
+
+    pub fn issue(&self, user_uuid: &str) -> String {
+        self.issue_at(user_uuid, SystemTime::now())
+    }
+
+    /// Verify the token and return the user Uuid it is issued to.
+    pub fn verify(&self, token: &str) -> CommonResult<Uuid> {
+        self.verify_at(token, SystemTime::now())
+    }
+
+    fn issue_at(&self, user_uuid: &str, issued_at: SystemTime) -> String {
+        let issued_at = issued_at
+            .duration_since(UNIX_EPOCH)
+            .expect("System time is before Unix epoch")
+            .as_secs();
+        let payload = format!("{}.{}", user_uuid, issued_at);
+        let signature = self.sign(&payload);
+
+        format!("{}.{}", payload, signature)
+    }
+
+    fn verify_at(&self, token: &str, now: SystemTime) -> CommonResult<Uuid> {
+        let mut parts = token.rsplitn(2, '.');
+        let signature = parts.next().unwrap_or("");
+        let payload = parts.next().ok_or_else(invalid_token_error)?;
+        if !constant_time_eq(signature.as_bytes(), self.sign(payload).as_bytes()) {
+            return Err(invalid_token_error());
+        }
+
+        let mut parts = payload.rsplitn(2, '.');
+        let issued_at: u64 = parts
+            .next()
+            .and_then(|issued_at| issued_at.parse().ok())
+            .ok_or_else(invalid_token_error)?;
+        let user_uuid = parts.next().ok_or_else(invalid_token_error)?;
+
+        let expires_at = UNIX_EPOCH + Duration::from_secs(issued_at) + self.ttl;
+        if expires_at < now {
+            return Err(Error::new(
+                ErrorKind::UnauthorizedError,
+                "Token has expired",
+            ));
+        }
+
+        Ok(String::from(user_uuid))
+    }
+
+    fn sign(&self, payload: &str) -> String {
+        let mut mac = Hmac::<Sha256>::new_varkey(self.secret.as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.input(payload.as_bytes());
+
+        hex::encode(mac.result().code())
+    }
+}
+
+fn invalid_token_error() -> Error {
+    Error::new(ErrorKind::UnauthorizedError, "Invalid token")
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b.iter()).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Extractor of the user authenticated by the bearer token in Authorization
+/// header. Requires AuthTokenService to be registered as app data.
+pub struct AuthenticatedUser {
+    pub uuid: Uuid,
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = ActixWebError;
+    type Future = Result<Self, Self::Error>;
+    type Config = ();
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let token_service = req
+            .app_data::<AuthTokenService>()
+            .expect("AuthTokenService is not registered as app data");
+
+        let token = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .filter(|value| value.starts_with(BEARER_PREFIX))
+            .map(|value| &value[BEARER_PREFIX.len()..])
+            .ok_or_else(|| ErrorUnauthorized("Missing bearer token"))?;
+
+        token_service
+            .verify(token)
+            .map(|uuid| AuthenticatedUser { uuid })
+            .map_err(|err| ErrorUnauthorized(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::test::TestRequest;
+
+    const USER_UUID: &str = "8a0e6f54-1a2b-4c3d-9e8f-0123456789ab";
+
+    #[test]
+    fn verify_should_return_user_uuid_of_issued_token() {
+        let service = make_token_service();
+
+        let token = service.issue(USER_UUID);
+
+        assert_eq!(service.verify(&token).unwrap(), USER_UUID);
+    }
+
+    #[test]
+    fn verify_should_return_unauthorized_error_when_signature_is_tampered() {
+        let service = make_token_service();
+        let token = service.issue(USER_UUID);
+
+        let tampered_token = token.replacen(USER_UUID, "another-user", 1);
+
+        let err = service.verify(&tampered_token).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnauthorizedError);
+    }
+
+    #[test]
+    fn verify_should_return_unauthorized_error_when_signed_with_another_secret() {
+        let service = make_token_service();
+        let another_service = AuthTokenService::new(String::from("another"), DEFAULT_TOKEN_TTL);
+
+        let token = another_service.issue(USER_UUID);
+
+        let err = service.verify(&token).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnauthorizedError);
+    }
+
+    #[test]
+    fn sign_should_return_hmac_sha256_of_payload() {
+        let service = AuthTokenService::new(String::from("key"), DEFAULT_TOKEN_TTL);
+
+        assert_eq!(
+            service.sign("The quick brown fox jumps over the lazy dog"),
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+
+    #[test]
+    fn verify_should_return_unauthorized_error_when_token_is_malformed() {
+        let service = make_token_service();
+
+        for token in ["", "token", "a.b", "a.b.c"].iter() {
+            let err = service.verify(token).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::UnauthorizedError);
+        }
+    }
+
+    #[test]
+    fn verify_should_return_unauthorized_error_when_token_has_expired() {
+        let service = AuthTokenService::new(String::from("secret"), Duration::from_secs(60));
+        let issued_at = SystemTime::now() - Duration::from_secs(61);
+
+        let token = service.issue_at(USER_UUID, issued_at);
+
+        let err = service.verify(&token).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnauthorizedError);
+    }
+
+    mod authenticated_user {
+        use super::*;
+
+        #[test]
+        fn should_extract_user_uuid_from_bearer_token() {
+            let service = make_token_service();
+            let token = service.issue(USER_UUID);
+            let req = TestRequest::default()
+                .data(service)
+                .header(header::AUTHORIZATION, format!("Bearer {}", token))
+                .to_http_request();
+
+            let user = AuthenticatedUser::extract(&req).unwrap();
+
+            assert_eq!(user.uuid, USER_UUID);
+        }
+
+        #[test]
+        fn should_return_error_when_authorization_header_is_missing() {
+            let req = TestRequest::default()
+                .data(make_token_service())
+                .to_http_request();
+
+            assert!(AuthenticatedUser::extract(&req).is_err());
+        }
+
+        #[test]
+        fn should_return_error_when_authorization_is_not_bearer_token() {
+            let service = make_token_service();
+            let token = service.issue(USER_UUID);
+            let req = TestRequest::default()
+                .data(service)
+                .header(header::AUTHORIZATION, format!("Basic {}", token))
+                .to_http_request();
+
+            assert!(AuthenticatedUser::extract(&req).is_err());
+        }
+    }
+
+    fn make_token_service() -> AuthTokenService {
+        AuthTokenService::new(String::from("secret"), DEFAULT_TOKEN_TTL)
+    }
+}
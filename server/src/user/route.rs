@@ -1,5 +1,6 @@
 use actix_web::{error::BlockingError, web, Error as ActixWebError, HttpRequest, HttpResponse};
 use futures::Future;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::common::error::{Error, ErrorKind};
@@ -7,7 +8,8 @@ use crate::common::error::{Error, ErrorKind};
 use super::auth;
 use super::auth::provider::AuthenticateResult;
 use super::auth::provider_service::ProviderService;
-use super::model::{NewUserRecordParams, UserORM};
+use super::auth::AuthTokenService;
+use super::model::{NewUserRecordParams, UserORM, UserRecord};
 
 use oauth2::basic::BasicClient;
 // Alternatively, this can be oauth2::curl::http_client or a custom.
@@ -60,6 +62,7 @@ pub fn login_user<U>(
     req: web::Json<LoginUserReq>,
     user: web::Data<U>,
     auth_provider_service: web::Data<ProviderService>,
+    auth_token_service: web::Data<AuthTokenService>,
 ) -> impl Future<Item = HttpResponse, Error = ActixWebError>
 where
     U: UserORM + Send + Sync + 'static,
@@ -98,8 +101,14 @@ where
             }
         }
     })
-    .then(|user_record_result| match user_record_result {
-        Ok(user_record) => Ok(HttpResponse::Ok().json(user_record)),
+    .then(move |user_record_result| match user_record_result {
+        Ok(user_record) => {
+            let token = auth_token_service.issue(&user_record.uuid);
+            Ok(HttpResponse::Ok().json(LoginUserRes {
+                user: user_record,
+                token,
+            }))
+        }
         Err(err) => {
             println!("{}", err);
             match err {
@@ -184,10 +193,18 @@ where
     // })
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, JsonSchema)]
 pub struct LoginUserReq {
-    provider_id: String,
-    params: auth::Params,
+    pub provider_id: String,
+    pub params: auth::Params,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct LoginUserRes {
+    #[serde(flatten)]
+    pub user: UserRecord,
+    /// Bearer token for authenticating subsequent requests.
+    pub token: String,
 }
 
 #[cfg(test)]
@@ -202,6 +219,7 @@ mod test {
 
     use crate::common::error::{Error, ErrorKind};
     use crate::user::auth::provider::{MockProvider, Provider};
+    use crate::user::auth::token::DEFAULT_TOKEN_TTL;
     use crate::user::model::{MockUserORM, UserRecord};
 
     mod login_user_req {}
@@ -238,9 +256,13 @@ mod test {
                 let provider_service_data =
                     make_provider_service_web_data(vec![guest_provider_service]);
 
-                let resp =
-                    test::block_on(login_user(req, web::Data::new(user), provider_service_data))
-                        .unwrap();
+                let resp = test::block_on(login_user(
+                    req,
+                    web::Data::new(user),
+                    provider_service_data,
+                    make_auth_token_service_web_data(),
+                ))
+                .unwrap();
                 assert_eq!(resp.status(), http::StatusCode::OK);
             }
 
@@ -266,9 +288,13 @@ mod test {
                 let provider_service_data =
                     make_provider_service_web_data(vec![oauth_provider_service]);
 
-                let resp =
-                    test::block_on(login_user(req, web::Data::new(user), provider_service_data))
-                        .unwrap();
+                let resp = test::block_on(login_user(
+                    req,
+                    web::Data::new(user),
+                    provider_service_data,
+                    make_auth_token_service_web_data(),
+                ))
+                .unwrap();
                 assert_eq!(resp.status(), http::StatusCode::OK);
             }
 
@@ -293,6 +319,7 @@ mod test {
                     App::new()
                         .data(user)
                         .register_data(provider_service_data)
+                        .register_data(make_auth_token_service_web_data())
                         .route("/", web::post().to_async(login_user::<MockUserORM>)),
                 );
                 let req = TestRequest::post()
@@ -333,9 +360,13 @@ mod test {
                 let provider_service_data =
                     make_provider_service_web_data(vec![oauth_provider_service]);
 
-                let resp =
-                    test::block_on(login_user(req, web::Data::new(user), provider_service_data))
-                        .unwrap();
+                let resp = test::block_on(login_user(
+                    req,
+                    web::Data::new(user),
+                    provider_service_data,
+                    make_auth_token_service_web_data(),
+                ))
+                .unwrap();
 
                 assert_eq!(resp.status(), http::StatusCode::OK);
             }
@@ -366,6 +397,7 @@ mod test {
                     App::new()
                         .data(user)
                         .register_data(provider_service_data)
+                        .register_data(make_auth_token_service_web_data())
                         .route("/", web::post().to_async(login_user::<MockUserORM>)),
                 );
                 let req = TestRequest::post()
@@ -377,9 +409,56 @@ mod test {
 
                 assert_eq!(user_record_resp, user_record.clone());
             }
+
+            #[test]
+            fn should_return_token_issued_to_user_in_response() {
+                let mut user = MockUserORM::new();
+                let email = String::from("calvinlauco@gmail.com");
+                let now = SystemTime::now();
+                let user_record = UserRecord {
+                    uuid: Uuid::new_v4().to_string(),
+                    email: Some(email.clone()),
+                    name: None,
+                    created_at: now,
+                    last_updated_at: now,
+                };
+                user.expect_find_by_email()
+                    .return_const(Ok(Some(user_record.clone())));
+
+                let oauth_provider_service = make_successful_oauth_provider_service(email);
+                let provider_service_data =
+                    make_provider_service_web_data(vec![oauth_provider_service]);
+                let auth_token_service_data = make_auth_token_service_web_data();
+
+                let mut app = test::init_service(
+                    App::new()
+                        .data(user)
+                        .register_data(provider_service_data)
+                        .register_data(auth_token_service_data.clone())
+                        .route("/", web::post().to_async(login_user::<MockUserORM>)),
+                );
+                let req = TestRequest::post()
+                    .uri("/")
+                    .set_json(&make_oauth_login_user_req())
+                    .to_request();
+
+                let resp: LoginUserRes = test::read_response_json(&mut app, req);
+
+                assert_eq!(
+                    auth_token_service_data.verify(&resp.token).unwrap(),
+                    user_record.uuid
+                );
+            }
         }
     }
 
+    fn make_auth_token_service_web_data() -> web::Data<AuthTokenService> {
+        web::Data::new(AuthTokenService::new(
+            String::from("secret"),
+            DEFAULT_TOKEN_TTL,
+        ))
+    }
+
     fn make_guest_login_request() -> web::Json<LoginUserReq> {
         web::Json(LoginUserReq {
             provider_id: String::from("Guest"),
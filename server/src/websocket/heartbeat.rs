@@ -0,0 +1,91 @@
+use std::time::{Duration, Instant};
+
+use crate::common::error::{Error, ErrorKind, Result as CommonResult};
+
+pub const DEFAULT_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+pub const DEFAULT_CLIENT_TIMEOUT: Duration = Duration::from_secs(45);
+
+/// The server pings every client on the interval. A client which sends
+/// nothing, including pongs, within the timeout is considered gone, and its
+/// connection is closed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeartbeatConfig {
+    pub interval: Duration,
+    pub client_timeout: Duration,
+}
+
+impl HeartbeatConfig {
+    pub fn new(interval: Duration, client_timeout: Duration) -> CommonResult<Self> {
+        if interval == Duration::from_secs(0) {
+            return Err(Error::new(
+                ErrorKind::InvalidParams,
+                "Heartbeat interval must be positive",
+            ));
+        }
+        if client_timeout <= interval {
+            return Err(Error::new(
+                ErrorKind::InvalidParams,
+                "Client timeout must be longer than heartbeat interval",
+            ));
+        }
+
+        Ok(HeartbeatConfig {
+            interval,
+            client_timeout,
+        })
+    }
+
+    pub fn is_timed_out(&self, last_heartbeat: Instant, now: Instant) -> bool {
+        now.duration_since(last_heartbeat) > self.client_timeout
+    }
+}
+
+impl Default for HeartbeatConfig {
+    fn default() -> Self {
+        HeartbeatConfig {
+            interval: DEFAULT_HEARTBEAT_INTERVAL,
+            client_timeout: DEFAULT_CLIENT_TIMEOUT,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    mod new {
+        use super::*;
+
+        #[test]
+        fn should_return_invalid_params_error_when_timeout_is_not_longer_than_interval() {
+            let config_result =
+                HeartbeatConfig::new(Duration::from_secs(10), Duration::from_secs(10));
+
+            assert_eq!(config_result.unwrap_err().kind(), ErrorKind::InvalidParams);
+        }
+
+        #[test]
+        fn should_return_invalid_params_error_when_interval_is_zero() {
+            let config_result =
+                HeartbeatConfig::new(Duration::from_secs(0), Duration::from_secs(10));
+
+            assert_eq!(config_result.unwrap_err().kind(), ErrorKind::InvalidParams);
+        }
+    }
+
+    mod is_timed_out {
+        use super::*;
+
+        #[test]
+        fn should_time_out_client_quiet_for_longer_than_timeout() {
+            let config = HeartbeatConfig::default();
+            let last_heartbeat = Instant::now();
+
+            assert!(!config.is_timed_out(last_heartbeat, last_heartbeat + DEFAULT_CLIENT_TIMEOUT));
+            assert!(config.is_timed_out(
+                last_heartbeat,
+                last_heartbeat + DEFAULT_CLIENT_TIMEOUT + Duration::from_secs(1)
+            ));
+        }
+    }
+}
@@ -1 +1 @@
-use actix::prelude::*;
\ No newline at end of file
+use actix::prelude::*;
@@ -1,5 +1,7 @@
 mod session;
 
+pub mod heartbeat;
+pub mod protocol;
 pub mod route;
 
 pub use session::Session;
@@ -0,0 +1,446 @@
+use actix_web_actors::ws::{CloseCode, CloseReason};
+use serde_json::{self, Value};
+
+use crate::common::error::{Error, ErrorKind, Result as CommonResult};
+use crate::common::message::request::RequestFrame;
+use crate::common::message::response::ResponseFrame;
+use crate::common::message::{RequestMessage, ResponseMessage};
+
+/// Close code sent to the client when none of the protocols it offers is
+/// supported.
+pub const UNSUPPORTED_VERSION_CLOSE_CODE: u16 = 4001;
+
+const MESSAGE_PACK_SUFFIX: &str = ".msgpack";
+
+/// Version of the websocket protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ProtocolVersion {
+    /// Protocol without request IDs, acknowledgements, errors and session
+    /// resume. It is used when the client does not offer any protocol.
+    V1,
+    V2,
+}
+
+impl ProtocolVersion {
+    pub const SUPPORTED: [ProtocolVersion; 2] = [ProtocolVersion::V2, ProtocolVersion::V1];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ProtocolVersion::V1 => "scrum-poker.v1",
+            ProtocolVersion::V2 => "scrum-poker.v2",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        ProtocolVersion::SUPPORTED
+            .iter()
+            .cloned()
+            .find(|version| version.name() == name)
+    }
+}
+
+/// Encoding of the messages. JSON messages are sent in text frames, while
+/// MessagePack messages are sent in binary frames. Both encodings carry
+/// messages of the same shape.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Json,
+    MessagePack,
+}
+
+/// Websocket protocol negotiated with the `Sec-WebSocket-Protocol` header
+/// during the handshake, such as `scrum-poker.v2` or
+/// `scrum-poker.v2.msgpack`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Protocol {
+    pub version: ProtocolVersion,
+    pub encoding: Encoding,
+}
+
+impl Protocol {
+    pub fn name(self) -> String {
+        match self.encoding {
+            Encoding::Json => String::from(self.version.name()),
+            Encoding::MessagePack => format!("{}{}", self.version.name(), MESSAGE_PACK_SUFFIX),
+        }
+    }
+
+    /// MessagePack is only available since V2.
+    pub fn from_name(name: &str) -> Option<Self> {
+        if let Some(version_name) = name.strip_suffix(MESSAGE_PACK_SUFFIX) {
+            let version = ProtocolVersion::from_name(version_name)?;
+            if version < ProtocolVersion::V2 {
+                return None;
+            }
+            Some(Protocol {
+                version,
+                encoding: Encoding::MessagePack,
+            })
+        } else {
+            ProtocolVersion::from_name(name).map(|version| Protocol {
+                version,
+                encoding: Encoding::Json,
+            })
+        }
+    }
+
+    /// Choose the protocol with the latest version from the comma-separated
+    /// protocols offered by the client. Among protocols of the same version,
+    /// the one offered first is chosen.
+    pub fn negotiate(offered: Option<&str>) -> CommonResult<Self> {
+        let offered = match offered {
+            Some(offered) if !offered.trim().is_empty() => offered,
+            _ => {
+                return Ok(Protocol {
+                    version: ProtocolVersion::V1,
+                    encoding: Encoding::Json,
+                })
+            }
+        };
+
+        offered
+            .split(',')
+            .filter_map(|name| Protocol::from_name(name.trim()))
+            .fold(None, |chosen: Option<Protocol>, protocol| match chosen {
+                Some(chosen) if chosen.version >= protocol.version => Some(chosen),
+                _ => Some(protocol),
+            })
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::UnsupportedProtocolVersion,
+                    format!(
+                        "Unsupported protocol version {}. Supported versions are {}",
+                        offered,
+                        supported_names()
+                    ),
+                )
+            })
+    }
+
+    pub fn decode(self, frame: &DataFrame) -> CommonResult<RequestFrame> {
+        let value = self.decode_value(frame)?;
+        let frame_result = match self.version {
+            ProtocolVersion::V1 => {
+                serde_json::from_value::<RequestMessage>(value).map(|req| RequestFrame {
+                    request_id: None,
+                    req,
+                })
+            }
+            ProtocolVersion::V2 => serde_json::from_value(value),
+        };
+
+        frame_result.map_err(|err| Error::new(ErrorKind::DeserializationError, err.to_string()))
+    }
+
+    /// Find the ID of the request which cannot be decoded, so that the error
+    /// can still be matched to the request.
+    pub fn find_request_id(self, frame: &DataFrame) -> Option<String> {
+        match self.version {
+            ProtocolVersion::V1 => None,
+            ProtocolVersion::V2 => self
+                .decode_value(frame)
+                .ok()?
+                .get("request_id")
+                .and_then(Value::as_str)
+                .map(String::from),
+        }
+    }
+
+    fn decode_value(self, frame: &DataFrame) -> CommonResult<Value> {
+        match (self.encoding, frame) {
+            (Encoding::Json, DataFrame::Text(text)) => serde_json::from_str(text)
+                .map_err(|err| Error::new(ErrorKind::DeserializationError, err.to_string())),
+            (Encoding::MessagePack, DataFrame::Binary(bytes)) => rmp_serde::from_slice(bytes)
+                .map_err(|err| Error::new(ErrorKind::DeserializationError, err.to_string())),
+            (Encoding::Json, DataFrame::Binary(_)) => Err(Error::new(
+                ErrorKind::BadRequest,
+                "Binary message is not supported with JSON encoding",
+            )),
+            (Encoding::MessagePack, DataFrame::Text(_)) => Err(Error::new(
+                ErrorKind::BadRequest,
+                "Text message is not supported with MessagePack encoding",
+            )),
+        }
+    }
+
+    /// Serialize the frame for the client. Messages which do not exist in
+    /// the version are not sent, and sequence numbers are only sent since V2.
+    pub fn encode(self, frame: &ResponseFrame) -> Option<DataFrame> {
+        match (self.version, &frame.msg) {
+            (ProtocolVersion::V1, ResponseMessage::Error(_))
+            | (ProtocolVersion::V1, ResponseMessage::Acknowledged(_))
+            | (ProtocolVersion::V1, ResponseMessage::SessionStarted(_)) => return None,
+            _ => (),
+        };
+
+        // Messages are converted to JSON value first, so that enums are
+        // encoded by their names in MessagePack, the same as in JSON, instead
+        // of their positions.
+        let value = match self.version {
+            ProtocolVersion::V1 => serde_json::to_value(&frame.msg),
+            ProtocolVersion::V2 => serde_json::to_value(frame),
+        }
+        .expect("Error when serializing message");
+
+        let frame = match self.encoding {
+            Encoding::Json => DataFrame::Text(value.to_string()),
+            Encoding::MessagePack => DataFrame::Binary(
+                rmp_serde::to_vec_named(&value).expect("Error when serializing message"),
+            ),
+        };
+
+        Some(frame)
+    }
+}
+
+/// Data frame of the websocket connection.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DataFrame {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+fn supported_names() -> String {
+    let names: Vec<String> = ProtocolVersion::SUPPORTED
+        .iter()
+        .flat_map(|version| {
+            vec![
+                Protocol {
+                    version: *version,
+                    encoding: Encoding::Json,
+                },
+                Protocol {
+                    version: *version,
+                    encoding: Encoding::MessagePack,
+                },
+            ]
+        })
+        .filter(|protocol| Protocol::from_name(&protocol.name()).is_some())
+        .map(Protocol::name)
+        .collect();
+
+    names.join(", ")
+}
+
+pub fn unsupported_version_close_reason(err: &Error) -> CloseReason {
+    CloseReason {
+        code: CloseCode::Other(UNSUPPORTED_VERSION_CLOSE_CODE),
+        description: Some(String::from(err.message())),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::common::message::response::{Acknowledgement, RequestError, StartedSession};
+
+    const V1: Protocol = Protocol {
+        version: ProtocolVersion::V1,
+        encoding: Encoding::Json,
+    };
+    const V2: Protocol = Protocol {
+        version: ProtocolVersion::V2,
+        encoding: Encoding::Json,
+    };
+    const V2_MESSAGE_PACK: Protocol = Protocol {
+        version: ProtocolVersion::V2,
+        encoding: Encoding::MessagePack,
+    };
+
+    mod negotiate {
+        use super::*;
+
+        #[test]
+        fn should_use_v1_json_when_client_offers_no_protocol() {
+            assert_eq!(Protocol::negotiate(None).unwrap(), V1);
+            assert_eq!(Protocol::negotiate(Some(" ")).unwrap(), V1);
+        }
+
+        #[test]
+        fn should_choose_latest_supported_version() {
+            let protocol =
+                Protocol::negotiate(Some("scrum-poker.v1, scrum-poker.v3,scrum-poker.v2")).unwrap();
+
+            assert_eq!(protocol, V2);
+        }
+
+        #[test]
+        fn should_choose_encoding_offered_first_for_same_version() {
+            let protocol =
+                Protocol::negotiate(Some("scrum-poker.v2.msgpack, scrum-poker.v2")).unwrap();
+
+            assert_eq!(protocol, V2_MESSAGE_PACK);
+            assert_eq!(protocol.name(), "scrum-poker.v2.msgpack");
+        }
+
+        #[test]
+        fn should_not_support_message_pack_in_v1() {
+            let err = Protocol::negotiate(Some("scrum-poker.v1.msgpack")).unwrap_err();
+
+            assert_eq!(err.kind(), ErrorKind::UnsupportedProtocolVersion);
+        }
+
+        #[test]
+        fn should_return_unsupported_protocol_version_error_when_no_protocol_is_supported() {
+            let err = Protocol::negotiate(Some("scrum-poker.v3")).unwrap_err();
+
+            assert_eq!(err.kind(), ErrorKind::UnsupportedProtocolVersion);
+            assert!(err
+                .message()
+                .contains("scrum-poker.v2, scrum-poker.v2.msgpack, scrum-poker.v1"));
+        }
+    }
+
+    mod decode {
+        use super::*;
+
+        #[test]
+        fn should_ignore_request_id_in_v1() {
+            let frame = V1
+                .decode(&DataFrame::Text(String::from(
+                    r#"{"type": "RevealCards", "request_id": "1"}"#,
+                )))
+                .unwrap();
+
+            assert_eq!(frame.request_id, None);
+        }
+
+        #[test]
+        fn should_decode_request_id_in_v2() {
+            let frame = V2
+                .decode(&DataFrame::Text(String::from(
+                    r#"{"type": "RevealCards", "request_id": "1"}"#,
+                )))
+                .unwrap();
+
+            assert_eq!(frame.request_id, Some(String::from("1")));
+        }
+
+        #[test]
+        fn should_decode_message_pack_request() {
+            let bytes = rmp_serde::to_vec_named(&serde_json::json!({
+                "type": "PlayCard",
+                "request_id": "1",
+                "card": "3",
+            }))
+            .unwrap();
+
+            let frame = V2_MESSAGE_PACK.decode(&DataFrame::Binary(bytes)).unwrap();
+
+            assert_eq!(frame.request_id, Some(String::from("1")));
+            match frame.req {
+                RequestMessage::PlayCard(params) => assert_eq!(params.card, "3"),
+                req => panic!("Unexpected request {:?}", req),
+            }
+        }
+
+        #[test]
+        fn should_return_bad_request_error_when_frame_does_not_match_encoding() {
+            let text_err = V2_MESSAGE_PACK
+                .decode(&DataFrame::Text(String::from(r#"{"type": "RevealCards"}"#)))
+                .unwrap_err();
+            let binary_err = V2.decode(&DataFrame::Binary(vec![0x80])).unwrap_err();
+
+            assert_eq!(text_err.kind(), ErrorKind::BadRequest);
+            assert_eq!(binary_err.kind(), ErrorKind::BadRequest);
+        }
+
+        #[test]
+        fn should_find_request_id_of_invalid_message_pack_request() {
+            let bytes = rmp_serde::to_vec_named(&serde_json::json!({
+                "type": "Unknown",
+                "request_id": "1",
+            }))
+            .unwrap();
+            let frame = DataFrame::Binary(bytes);
+
+            let err = V2_MESSAGE_PACK.decode(&frame).unwrap_err();
+
+            assert_eq!(err.kind(), ErrorKind::DeserializationError);
+            assert_eq!(
+                V2_MESSAGE_PACK.find_request_id(&frame),
+                Some(String::from("1"))
+            );
+        }
+    }
+
+    mod encode {
+        use super::*;
+
+        #[test]
+        fn should_not_send_errors_and_acknowledgements_in_v1() {
+            let error = ResponseMessage::Error(RequestError::new(
+                &Error::from(ErrorKind::ForbiddenError),
+                None,
+            ));
+            let acknowledged = ResponseMessage::Acknowledged(Acknowledgement {
+                request_id: String::from("1"),
+            });
+
+            assert!(V1.encode(&error.into()).is_none());
+            assert!(V1.encode(&acknowledged.clone().into()).is_none());
+            assert!(V2.encode(&acknowledged.into()).is_some());
+        }
+
+        #[test]
+        fn should_not_send_resume_token_in_v1() {
+            let started = ResponseMessage::SessionStarted(StartedSession {
+                resume_token: String::from("token"),
+            });
+
+            assert!(V1.encode(&started.clone().into()).is_none());
+            assert!(V2.encode(&started.into()).is_some());
+        }
+
+        #[test]
+        fn should_encode_same_room_events_in_all_versions() {
+            let frame =
+                ResponseFrame::from(ResponseMessage::UserJoined(String::from("player-uuid")));
+
+            assert_eq!(
+                V1.encode(&frame),
+                Some(DataFrame::Text(String::from(
+                    r#"{"UserJoined":"player-uuid"}"#
+                )))
+            );
+            assert_eq!(V2.encode(&frame), V1.encode(&frame));
+        }
+
+        #[test]
+        fn should_send_sequence_number_since_v2() {
+            let frame =
+                ResponseFrame::event(3, ResponseMessage::UserLeft(String::from("player-uuid")));
+
+            assert_eq!(
+                V1.encode(&frame),
+                Some(DataFrame::Text(String::from(
+                    r#"{"UserLeft":"player-uuid"}"#
+                )))
+            );
+            assert_eq!(
+                V2.encode(&frame),
+                Some(DataFrame::Text(String::from(
+                    r#"{"UserLeft":"player-uuid","seq":3}"#
+                )))
+            );
+        }
+
+        #[test]
+        fn should_encode_message_pack_response_with_same_shape_as_json() {
+            let msg = ResponseMessage::Error(RequestError::new(
+                &Error::new(ErrorKind::RoomNotFound, "Room not found"),
+                Some(String::from("1")),
+            ));
+
+            let bytes = match V2_MESSAGE_PACK.encode(&msg.clone().into()) {
+                Some(DataFrame::Binary(bytes)) => bytes,
+                frame => panic!("Unexpected frame {:?}", frame),
+            };
+            let value: Value = rmp_serde::from_slice(&bytes).unwrap();
+
+            assert_eq!(value, serde_json::to_value(&msg).unwrap());
+            assert_eq!(value["Error"]["code"], "room_not_found");
+        }
+    }
+}
@@ -1,33 +1,137 @@
 use actix::prelude::*;
+use actix_web::http::header;
 use actix_web::{web, Error as AWError, HttpRequest, HttpResponse};
 use actix_web_actors::ws;
+use log::warn;
 
+use crate::common::error::Error;
+use crate::user::auth::AuthenticatedUser;
 use crate::user::info::UserInfo;
 use crate::user::model::UserModel;
 use crate::AppServer;
 
+use super::heartbeat::HeartbeatConfig;
+use super::protocol::{unsupported_version_close_reason, Protocol};
 use super::Session;
 
+/// Connections are refused with 401 before the upgrade, unless they carry a
+/// valid bearer token. The user is identified by the token.
 pub fn websocket_route(
+    user: AuthenticatedUser,
     req: HttpRequest,
     stream: web::Payload,
     user_model: web::Data<UserModel>,
     server: web::Data<Addr<AppServer>>,
+    heartbeat: web::Data<HeartbeatConfig>,
 ) -> std::result::Result<HttpResponse, AWError> {
-    // TODO: Authenticate user
-    let user_uuid = String::from("0123-4567-8901-2345");
-    let user_name = String::from("Calvin Lau");
+    let offered_protocols = req
+        .headers()
+        .get(header::SEC_WEBSOCKET_PROTOCOL)
+        .and_then(|offered_protocols| offered_protocols.to_str().ok());
+    let protocol = match Protocol::negotiate(offered_protocols) {
+        Ok(protocol) => protocol,
+        Err(err) => {
+            warn!("Rejecting websocket connection: {}", err);
+            let mut res = ws::handshake(&req)?;
+            return Ok(res.streaming(ws::WebsocketContext::create(
+                RejectedSession { err },
+                stream,
+            )));
+        }
+    };
+
+    // TODO: Look up user name
     let user_info = UserInfo {
-        uuid: user_uuid,
-        name: user_name,
+        uuid: user.uuid,
+        name: String::new(),
     };
-    ws::start(
-        Session::new(
-            server.get_ref().clone(),
-            user_model.get_ref().clone(),
-            user_info,
-        ),
-        &req,
-        stream,
-    )
+    let session = Session::new(
+        server.get_ref().clone(),
+        user_model.get_ref().clone(),
+        user_info,
+        protocol,
+        *heartbeat.get_ref(),
+    );
+
+    let mut res = ws::handshake(&req)?;
+    // Clients which offer no version are not told about the version, as they
+    // fail the connection when the header is not expected.
+    if offered_protocols.is_some() {
+        res.header(header::SEC_WEBSOCKET_PROTOCOL, protocol.name().as_str());
+    }
+    Ok(res.streaming(ws::WebsocketContext::create(session, stream)))
+}
+
+/// Connection which is closed as soon as it is established, so that the
+/// client receives the reason in the close frame.
+struct RejectedSession {
+    err: Error,
+}
+
+impl Actor for RejectedSession {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        ctx.close(Some(unsupported_version_close_reason(&self.err)));
+        ctx.stop();
+    }
+}
+
+impl StreamHandler<ws::Message, ws::ProtocolError> for RejectedSession {
+    fn handle(&mut self, _msg: ws::Message, _ctx: &mut Self::Context) {}
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use actix_web::http::StatusCode;
+    use actix_web::test::{self, TestRequest};
+    use actix_web::App;
+
+    use crate::user::auth::token::DEFAULT_TOKEN_TTL;
+    use crate::user::auth::AuthTokenService;
+
+    const USER_UUID: &str = "8a0e6f54-1a2b-4c3d-9e8f-0123456789ab";
+
+    #[test]
+    fn should_return_unauthorized_when_token_is_missing() {
+        let req = make_handshake_req();
+
+        assert_eq!(call_websocket_route(req), StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn should_return_unauthorized_when_token_is_forged() {
+        let forged_token =
+            AuthTokenService::new(String::from("forged"), DEFAULT_TOKEN_TTL).issue(USER_UUID);
+        let req =
+            make_handshake_req().header(header::AUTHORIZATION, format!("Bearer {}", forged_token));
+
+        assert_eq!(call_websocket_route(req), StatusCode::UNAUTHORIZED);
+    }
+
+    fn make_handshake_req() -> TestRequest {
+        TestRequest::get()
+            .uri("/ws/")
+            .header(header::UPGRADE, "websocket")
+            .header(header::CONNECTION, "upgrade")
+            .header(header::SEC_WEBSOCKET_VERSION, "13")
+            .header(header::SEC_WEBSOCKET_KEY, "dGhlIHNhbXBsZSBub25jZQ==")
+    }
+
+    /// Only the token service is registered, as the connection is refused
+    /// before the rest of the app data is needed.
+    fn call_websocket_route(req: TestRequest) -> StatusCode {
+        let mut app = test::init_service(
+            App::new()
+                .data(AuthTokenService::new(
+                    String::from("secret"),
+                    DEFAULT_TOKEN_TTL,
+                ))
+                .service(web::resource("/ws/").to(websocket_route)),
+        );
+
+        test::call_service(&mut app, req.to_request()).status()
+    }
 }
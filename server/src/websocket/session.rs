@@ -1,22 +1,32 @@
 use std::marker::PhantomData;
+use std::time::Instant;
 
 use actix::prelude::*;
 use actix_web_actors::ws;
 use log::{debug, info, warn};
-use serde_json;
 
 use crate::client::channel::DefaultClientChannel;
 use crate::client::store::DefaultClientStore;
 use crate::client::{ClientId, DEFAULT_CLIENT_ID};
-use crate::common::message::request::{CreateRoomParams, JoinRoomParams};
+use crate::common::error::{flatten_mailbox_result, Error, ErrorKind};
+use crate::common::message::request::{
+    CreateRoomParams, JoinRoomParams, RequestFrame, ResumeSessionParams,
+};
+use crate::common::message::response::{
+    Acknowledgement, RequestError, ResponseFrame, StartedSession,
+};
 use crate::common::message::{RequestMessage, ResponseMessage};
 use crate::poker::model::RoomModel;
+use crate::poker::room::message::{ClientRequestMessage, ResumeClientMessage};
 use crate::server::message::{
     ConnectMessage as ConnectServerMessage, CreateRoomMessage as CreateRoomServerMessage,
-    FindRoomMessage as FindRoomServerMessage,
+    DisconnectMessage as DisconnectServerMessage, FindRoomMessage as FindRoomServerMessage,
+    ResumeSessionMessage as ResumeSessionServerMessage,
 };
 use crate::user::info::{SharedUserInfo, UserInfo};
 use crate::user::model::UserORM;
+use crate::websocket::heartbeat::HeartbeatConfig;
+use crate::websocket::protocol::{DataFrame, Protocol};
 use crate::AppRoom;
 use crate::AppServer;
 
@@ -30,6 +40,16 @@ type AppFindRoomServerMessage = FindRoomServerMessage<
     DefaultClientStore<DefaultClientChannel>,
     DefaultClientChannel,
 >;
+type AppDisconnectServerMessage = DisconnectServerMessage<
+    RoomModel,
+    DefaultClientStore<DefaultClientChannel>,
+    DefaultClientChannel,
+>;
+type AppResumeSessionServerMessage = ResumeSessionServerMessage<
+    RoomModel,
+    DefaultClientStore<DefaultClientChannel>,
+    DefaultClientChannel,
+>;
 
 pub struct Session<U>
 where
@@ -40,6 +60,9 @@ where
     server_addr: Addr<AppServer>,
     room_addr: Option<Addr<AppRoom>>,
     user_info: SharedUserInfo,
+    protocol: Protocol,
+    heartbeat: HeartbeatConfig,
+    last_heartbeat: Instant,
 }
 
 impl<U> Actor for Session<U>
@@ -51,6 +74,8 @@ where
     fn started(&mut self, ctx: &mut Self::Context) {
         info!("New websocket connection established");
 
+        self.start_heartbeat(ctx);
+
         let addr = ctx.address();
         self.server_addr
             .send(ConnectServerMessage {
@@ -58,25 +83,56 @@ where
                 channel: DefaultClientChannel::new(addr.recipient()),
             })
             .into_actor(self)
-            .then(|client_id_result, actor, ctx| {
-                match client_id_result {
-                    Ok(client_id) => actor.client_id = client_id,
+            .then(|connected_result, actor, ctx| {
+                match connected_result {
+                    Ok(connected) => {
+                        actor.client_id = connected.client_id;
+                        actor.send(
+                            ResponseMessage::SessionStarted(StartedSession {
+                                resume_token: connected.resume_token,
+                            }),
+                            ctx,
+                        );
+                    }
                     _ => ctx.stop(),
                 };
                 fut::ok(())
             })
             .wait(ctx);
     }
+
+    /// The client keeps its seat in the room for a while, so that it can
+    /// resume the session from a new connection.
+    fn stopped(&mut self, _ctx: &mut Self::Context) {
+        info!("Websocket client {} disconnected", self.client_id);
+
+        self.server_addr.do_send(AppDisconnectServerMessage {
+            client_id: self.client_id,
+            room_addr: self.room_addr.take(),
+        });
+    }
 }
 
-impl<U> Handler<ResponseMessage> for Session<U>
+impl<U> Handler<ResponseFrame> for Session<U>
 where
     U: UserORM,
 {
     type Result = ();
 
-    fn handle(&mut self, msg: ResponseMessage, ctx: &mut Self::Context) {
-        ctx.text(serde_json::to_string(&msg).expect("Error when serializing message"));
+    fn handle(&mut self, frame: ResponseFrame, ctx: &mut Self::Context) {
+        match frame.msg {
+            ResponseMessage::Kicked(_) => {
+                info!("Websocket client {} is kicked from room", self.client_id);
+                self.room_addr = None;
+            }
+            ResponseMessage::RoomClosed(_) => {
+                info!("Room of websocket client {} is closed", self.client_id);
+                self.room_addr = None;
+            }
+            _ => (),
+        }
+
+        self.send_frame(&frame, ctx);
     }
 }
 
@@ -90,32 +146,21 @@ where
             msg, self.client_id
         );
 
+        self.last_heartbeat = Instant::now();
+
         match msg {
-            ws::Message::Text(msg_text) => {
-                match serde_json::from_str::<RequestMessage>(&msg_text) {
-                    Ok(req) => self.handle_request_message(req, ctx),
-                    Err(_) => {
-                        warn!(
-                            "Unrecognized text {} from websocket client {}",
-                            &msg_text, self.client_id
-                        );
-                    }
-                };
-            }
-            ws::Message::Binary(_) => {
-                warn!("Unexpected Binary from websocket client {}", self.client_id)
+            ws::Message::Text(msg_text) => self.handle_data_frame(DataFrame::Text(msg_text), ctx),
+            ws::Message::Binary(msg_bytes) => {
+                self.handle_data_frame(DataFrame::Binary(msg_bytes.to_vec()), ctx)
             }
+            ws::Message::Ping(msg) => ctx.pong(&msg),
+            ws::Message::Pong(_) => (),
             ws::Message::Close(_) => {
                 info!("Closing websocket client {}", self.client_id);
                 ctx.stop();
             }
             ws::Message::Nop => (),
-            _ => (),
         }
-
-        // TODO: Removed debug message
-        ctx.address()
-            .do_send(ResponseMessage::RoomClosed(String::from("Haha")));
     }
 }
 
@@ -123,26 +168,78 @@ impl<U> Session<U>
 where
     U: UserORM,
 {
-    pub fn new(server_addr: Addr<AppServer>, user_model: U, user_info: UserInfo) -> Self {
+    pub fn new(
+        server_addr: Addr<AppServer>,
+        user_model: U,
+        user_info: UserInfo,
+        protocol: Protocol,
+        heartbeat: HeartbeatConfig,
+    ) -> Self {
         Self {
             user_model,
             client_id: DEFAULT_CLIENT_ID,
             server_addr,
             room_addr: None,
             user_info: SharedUserInfo::new(user_info),
+            protocol,
+            heartbeat,
+            last_heartbeat: Instant::now(),
         }
     }
 
-    fn handle_request_message(&self, req: RequestMessage, ctx: &mut ws::WebsocketContext<Self>) {
-        match req {
-            RequestMessage::CreateRoom(message) => self.handle_create_room(message, ctx),
-            RequestMessage::JoinRoom(message) => self.handle_join(message, ctx),
-            // TODO:
-            _ => unreachable!(),
+    /// Ping the client on the interval, and close the connection when the
+    /// client has been quiet for too long. The client then leaves its room
+    /// and disconnects from the server as it does when it closes the
+    /// connection itself.
+    fn start_heartbeat(&self, ctx: &mut ws::WebsocketContext<Self>) {
+        ctx.run_interval(self.heartbeat.interval, |actor, ctx| {
+            if actor
+                .heartbeat
+                .is_timed_out(actor.last_heartbeat, Instant::now())
+            {
+                warn!(
+                    "Websocket client {} timed out without heartbeat",
+                    actor.client_id
+                );
+                ctx.stop();
+                return;
+            }
+
+            ctx.ping("");
+        });
+    }
+
+    fn handle_data_frame(&self, frame: DataFrame, ctx: &mut ws::WebsocketContext<Self>) {
+        match self.protocol.decode(&frame) {
+            Ok(req_frame) => self.handle_request_message(req_frame, ctx),
+            Err(err) => {
+                warn!(
+                    "Unrecognized message {:?} from websocket client {}",
+                    frame, self.client_id
+                );
+                self.handle_error(err, self.protocol.find_request_id(&frame), ctx);
+            }
         }
     }
 
-    fn handle_create_room(&self, params: CreateRoomParams, ctx: &mut ws::WebsocketContext<Self>) {
+    fn handle_request_message(&self, frame: RequestFrame, ctx: &mut ws::WebsocketContext<Self>) {
+        let request_id = frame.request_id;
+        match frame.req {
+            RequestMessage::CreateRoom(params) => self.handle_create_room(params, request_id, ctx),
+            RequestMessage::JoinRoom(params) => self.handle_join(params, request_id, ctx),
+            RequestMessage::ResumeSession(params) => {
+                self.handle_resume_session(params, request_id, ctx)
+            }
+            req => self.forward_to_room(req, request_id, ctx),
+        }
+    }
+
+    fn handle_create_room(
+        &self,
+        params: CreateRoomParams,
+        request_id: Option<String>,
+        ctx: &mut ws::WebsocketContext<Self>,
+    ) {
         info!(
             "Receiver create room request from websocket client {}",
             self.client_id
@@ -159,20 +256,26 @@ where
                 client_channel_type: PhantomData,
             })
             .into_actor(self)
-            .then(|handler_result, actor, ctx| {
-                match handler_result {
-                    Ok(room_addr_result) => match room_addr_result {
-                        Ok(room_addr) => actor.room_addr = Some(room_addr),
-                        _ => ctx.stop(),
-                    },
-                    _ => ctx.stop(),
+            .then(move |handler_result, actor, ctx| {
+                match flatten_mailbox_result(handler_result) {
+                    Ok((room_addr, mut created_room)) => {
+                        actor.room_addr = Some(room_addr);
+                        created_room.request_id = request_id;
+                        actor.send(ResponseMessage::RoomCreated(created_room), ctx);
+                    }
+                    Err(err) => actor.handle_error(err, request_id, ctx),
                 };
                 fut::ok(())
             })
             .wait(ctx);
     }
 
-    fn handle_join(&self, params: JoinRoomParams, ctx: &mut ws::WebsocketContext<Self>) {
+    fn handle_join(
+        &self,
+        params: JoinRoomParams,
+        request_id: Option<String>,
+        ctx: &mut ws::WebsocketContext<Self>,
+    ) {
         info!(
             "Receiver join room request from websocket client {}",
             self.client_id
@@ -183,26 +286,396 @@ where
         self.server_addr
             .send(AppFindRoomServerMessage {
                 client_id: self.client_id,
-                room_uuid: params.room_uuid,
+                room_uuid: params.room_uuid.clone(),
+                join_code: params.join_code.clone(),
                 room_orm_type: PhantomData,
                 client_store_type: PhantomData,
                 client_channel_type: PhantomData,
             })
             .into_actor(self)
-            .then(|handler_result, actor, ctx| {
-                match handler_result {
-                    Ok(room_addr_result) => match room_addr_result {
-                        Ok(room_addr) => {
-                            // room_addr.do_send(message);
-
-                            actor.room_addr = Some(room_addr);
-                        }
-                        _ => ctx.stop(),
-                    },
-                    _ => ctx.stop(),
+            .then(move |handler_result, actor, ctx| {
+                match flatten_mailbox_result(handler_result) {
+                    Ok(room_addr) => actor.join_room(room_addr, params, request_id, ctx),
+                    Err(err) => actor.handle_error(err, request_id, ctx),
+                };
+                fut::ok(())
+            })
+            .wait(ctx);
+    }
+
+    fn join_room(
+        &self,
+        room_addr: Addr<AppRoom>,
+        params: JoinRoomParams,
+        request_id: Option<String>,
+        ctx: &mut ws::WebsocketContext<Self>,
+    ) {
+        room_addr
+            .send(ClientRequestMessage {
+                client_id: self.client_id,
+                req: RequestMessage::JoinRoom(params),
+            })
+            .into_actor(self)
+            .then(move |handler_result, actor, ctx| {
+                match flatten_mailbox_result(handler_result) {
+                    Ok(_) => {
+                        actor.room_addr = Some(room_addr);
+                        actor.acknowledge(request_id, ctx);
+                    }
+                    Err(err) => actor.handle_error(err, request_id, ctx),
+                };
+                fut::ok(())
+            })
+            .wait(ctx);
+    }
+
+    fn handle_resume_session(
+        &self,
+        params: ResumeSessionParams,
+        request_id: Option<String>,
+        ctx: &mut ws::WebsocketContext<Self>,
+    ) {
+        info!(
+            "Receiver resume session request from websocket client {}",
+            self.client_id
+        );
+
+        if self.room_addr.is_some() {
+            self.handle_error(
+                Error::new(
+                    ErrorKind::AlreadyJoinedError,
+                    "Websocket client is in a room",
+                ),
+                request_id,
+                ctx,
+            );
+            return;
+        }
+
+        let last_seq = params.last_seq;
+        self.server_addr
+            .send(AppResumeSessionServerMessage {
+                client_id: self.client_id,
+                resume_token: params.resume_token,
+                room_orm_type: PhantomData,
+                client_store_type: PhantomData,
+                client_channel_type: PhantomData,
+            })
+            .into_actor(self)
+            .then(move |handler_result, actor, ctx| {
+                match flatten_mailbox_result(handler_result) {
+                    Ok((client_id, room_addr)) => {
+                        actor.client_id = client_id;
+                        actor.resume_room(room_addr, last_seq, request_id, ctx);
+                    }
+                    Err(err) => actor.handle_error(err, request_id, ctx),
                 };
                 fut::ok(())
             })
             .wait(ctx);
     }
+
+    fn resume_room(
+        &self,
+        room_addr: Addr<AppRoom>,
+        last_seq: Option<u64>,
+        request_id: Option<String>,
+        ctx: &mut ws::WebsocketContext<Self>,
+    ) {
+        room_addr
+            .send(ResumeClientMessage {
+                client_id: self.client_id,
+                last_seq,
+            })
+            .into_actor(self)
+            .then(move |handler_result, actor, ctx| {
+                match flatten_mailbox_result(handler_result) {
+                    Ok(_) => {
+                        actor.room_addr = Some(room_addr);
+                        actor.acknowledge(request_id, ctx);
+                    }
+                    Err(err) => actor.handle_error(err, request_id, ctx),
+                };
+                fut::ok(())
+            })
+            .wait(ctx);
+    }
+
+    fn forward_to_room(
+        &self,
+        req: RequestMessage,
+        request_id: Option<String>,
+        ctx: &mut ws::WebsocketContext<Self>,
+    ) {
+        let room_addr = match self.room_addr {
+            Some(ref room_addr) => room_addr,
+            None => {
+                warn!(
+                    "Received room request from websocket client {} which is not in a room",
+                    self.client_id
+                );
+                self.handle_error(
+                    Error::new(ErrorKind::BadRequest, "Websocket client is not in a room"),
+                    request_id,
+                    ctx,
+                );
+                return;
+            }
+        };
+
+        room_addr
+            .send(ClientRequestMessage {
+                client_id: self.client_id,
+                req,
+            })
+            .into_actor(self)
+            .then(move |handler_result, actor, ctx| {
+                match flatten_mailbox_result(handler_result) {
+                    Ok(_) => actor.acknowledge(request_id, ctx),
+                    Err(err) => actor.handle_error(err, request_id, ctx),
+                };
+                fut::ok(())
+            })
+            .wait(ctx);
+    }
+
+    /// Acknowledge the request which is handled successfully. Requests
+    /// without ID are not acknowledged, as the client cannot tell them apart.
+    fn acknowledge(&self, request_id: Option<String>, ctx: &mut ws::WebsocketContext<Self>) {
+        if let Some(request_id) = request_id {
+            self.send(
+                ResponseMessage::Acknowledged(Acknowledgement { request_id }),
+                ctx,
+            );
+        }
+    }
+
+    /// Report the error to the client. The connection is closed when the
+    /// error cannot be recovered from, such as when the server has lost track
+    /// of the client.
+    fn handle_error(
+        &self,
+        err: Error,
+        request_id: Option<String>,
+        ctx: &mut ws::WebsocketContext<Self>,
+    ) {
+        warn!(
+            "Error when handling request from websocket client {}: {}",
+            self.client_id, err
+        );
+
+        self.send(
+            ResponseMessage::Error(RequestError::new(&err, request_id)),
+            ctx,
+        );
+
+        match err.kind() {
+            ErrorKind::MissingClientError | ErrorKind::ActixRuntimeError => ctx.stop(),
+            _ => (),
+        }
+    }
+
+    fn send(&self, msg: ResponseMessage, ctx: &mut ws::WebsocketContext<Self>) {
+        self.send_frame(&ResponseFrame::from(msg), ctx);
+    }
+
+    fn send_frame(&self, frame: &ResponseFrame, ctx: &mut ws::WebsocketContext<Self>) {
+        match self.protocol.encode(frame) {
+            Some(DataFrame::Text(msg_text)) => ctx.text(msg_text),
+            Some(DataFrame::Binary(msg_bytes)) => ctx.binary(msg_bytes),
+            None => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use std::time::Duration;
+
+    use actix_web::error::PayloadError;
+    use actix_web::web::Bytes;
+    use diesel::r2d2::ConnectionManager;
+    use diesel::PgConnection;
+    use futures::sync::oneshot;
+    use futures::{future, stream, Async, Future, Poll, Stream};
+    use tokio_timer::{Delay, Timeout};
+
+    use crate::client::store::{ClientStore, SharedClientStore};
+    use crate::client::Client;
+    use crate::common::model::ConnectionPool;
+    use crate::poker::model::{DeckModel, RoomOptions};
+    use crate::poker::room::NewRoomParams;
+    use crate::user::model::UserModel;
+    use crate::websocket::protocol::{Encoding, ProtocolVersion};
+
+    const PLAYER_UUID: &str = "0123-4567-8901-2345";
+    /// Connection of the same player from another device, which keeps the
+    /// player in the room, so that the session joins it as a returning
+    /// player.
+    const OTHER_CLIENT_ID: ClientId = 1;
+    const RESUME_GRACE: Duration = Duration::from_millis(200);
+
+    mod start_heartbeat {
+        use super::*;
+
+        #[test]
+        fn should_stop_timed_out_session_and_leave_room_after_resume_grace() {
+            let heartbeat =
+                HeartbeatConfig::new(Duration::from_millis(50), Duration::from_millis(100))
+                    .unwrap();
+            let room_client_store = SharedClientStore::new(DefaultClientStore::default());
+            let mut sys = System::new("test");
+
+            let started_at = Instant::now();
+            let (session_addr, room_addr, session_stopped) = sys
+                .block_on(future::lazy(|| {
+                    future::ok::<_, ()>(start_session_in_room(heartbeat, room_client_store.clone()))
+                }))
+                .unwrap();
+
+            let client_id = sys.block_on(session_addr.send(GetClientId)).unwrap();
+            room_client_store.get_writable().insert(
+                client_id,
+                Client {
+                    user_info: SharedUserInfo::new(make_user_info()),
+                    channel: DefaultClientChannel::new(session_addr.recipient()),
+                },
+            );
+            sys.block_on(room_addr.send(ClientRequestMessage {
+                client_id,
+                req: RequestMessage::JoinRoom(JoinRoomParams {
+                    room_uuid: None,
+                    join_code: None,
+                    passphrase: None,
+                    spectator: false,
+                }),
+            }))
+            .unwrap()
+            .expect("Session should join the room");
+
+            let stopped_at = sys
+                .block_on(Timeout::new(session_stopped, Duration::from_secs(5)))
+                .expect("Session should be stopped by heartbeat");
+            assert!(stopped_at.duration_since(started_at) > heartbeat.client_timeout);
+
+            sys.block_on(room_addr.send(ResumeClientMessage {
+                client_id,
+                last_seq: Some(0),
+            }))
+            .unwrap()
+            .expect("Seat should be kept within the resume grace window");
+
+            sys.block_on(Delay::new(Instant::now() + RESUME_GRACE * 2))
+                .unwrap();
+            let resumed_result = sys
+                .block_on(room_addr.send(ResumeClientMessage {
+                    client_id,
+                    last_seq: Some(0),
+                }))
+                .unwrap();
+            assert_eq!(
+                resumed_result.unwrap_err().kind(),
+                ErrorKind::PlayerNotFound
+            );
+        }
+    }
+
+    struct GetClientId;
+
+    impl Message for GetClientId {
+        type Result = ClientId;
+    }
+
+    impl<U> Handler<GetClientId> for Session<U>
+    where
+        U: UserORM,
+    {
+        type Result = MessageResult<GetClientId>;
+
+        fn handle(&mut self, _msg: GetClientId, _ctx: &mut Self::Context) -> Self::Result {
+            MessageResult(self.client_id)
+        }
+    }
+
+    /// Start the server, a room and a session in the room, whose client never
+    /// sends anything. Return the addresses of the session and the room, with
+    /// the time when the session is stopped.
+    fn start_session_in_room(
+        heartbeat: HeartbeatConfig,
+        room_client_store: SharedClientStore<
+            DefaultClientStore<DefaultClientChannel>,
+            DefaultClientChannel,
+        >,
+    ) -> (
+        Addr<Session<UserModel>>,
+        Addr<AppRoom>,
+        oneshot::Receiver<Instant>,
+    ) {
+        let pool = make_unconnected_pool();
+        let server_addr = AppServer::new(
+            UserModel::new(pool.clone()),
+            RoomModel::new(pool.clone()),
+            DeckModel::new(pool.clone()),
+            DefaultClientStore::default(),
+            RESUME_GRACE,
+        )
+        .start();
+        let room_addr = AppRoom::new(
+            make_new_room_params(),
+            RoomModel::new(pool.clone()),
+            room_client_store,
+        )
+        .start();
+
+        let mut session = Session::new(
+            server_addr,
+            UserModel::new(pool),
+            make_user_info(),
+            Protocol {
+                version: ProtocolVersion::V2,
+                encoding: Encoding::Json,
+            },
+            heartbeat,
+        );
+        session.room_addr = Some(room_addr.clone());
+
+        let silent_client =
+            stream::poll_fn(|| -> Poll<Option<Bytes>, PayloadError> { Ok(Async::NotReady) });
+        let (session_addr, frames) = ws::WebsocketContext::create_with_addr(session, silent_client);
+        let (stopped_sender, session_stopped) = oneshot::channel();
+        Arbiter::spawn(frames.for_each(|_| Ok(())).then(move |_| {
+            stopped_sender.send(Instant::now()).unwrap();
+            Ok(())
+        }));
+
+        (session_addr, room_addr, session_stopped)
+    }
+
+    /// Pool which never connects to the database, for the flows which do not
+    /// touch it.
+    fn make_unconnected_pool() -> ConnectionPool {
+        r2d2::Pool::builder()
+            .min_idle(Some(0))
+            .build_unchecked(ConnectionManager::<PgConnection>::new(""))
+    }
+
+    fn make_new_room_params() -> NewRoomParams {
+        NewRoomParams {
+            passphrase: None,
+            card_set: vec![String::from("1"), String::from("2")],
+            options: RoomOptions::default(),
+            owner_client_id: Some(OTHER_CLIENT_ID),
+            owner_uuid: String::from(PLAYER_UUID),
+            join_code: String::from("ABCD2345"),
+        }
+    }
+
+    fn make_user_info() -> UserInfo {
+        UserInfo {
+            uuid: String::from(PLAYER_UUID),
+            name: String::from("Player"),
+        }
+    }
 }
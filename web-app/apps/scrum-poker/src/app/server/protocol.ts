@@ -0,0 +1,264 @@
+// Generated from the message types of the server. Do not edit.
+// Run `UPDATE_GENERATED_FILES=1 cargo test generated_files` in `server/` to update.
+
+/** Reply to a request which is handled successfully but has no other direct reply. */
+export interface Acknowledgement {
+    request_id: string;
+}
+
+/** Card of a room's card set with the metadata inferred from its label and its position in the card set. */
+export interface Card {
+    kind: CardKind;
+    label: string;
+    ordinal: number;
+    value?: number | null;
+}
+
+export interface CardCount {
+    count: number;
+    label: string;
+}
+
+export type CardKind =
+    | "Unknown" | "Break" | "Infinity"
+    | "Number";
+
+/** Statistics of the cards played in a game. */
+export interface CardStats {
+    /** Average of numeric cards. Absent when no numeric card is played. */
+    average?: number | null;
+    /** All players, except those taking a break, played the same estimate. */
+    consensus: boolean;
+    /** Number of times each card is played, sorted by ordinal. */
+    distribution: CardCount[];
+    max?: string | null;
+    /** Median of numeric cards. Absent when no numeric card is played. */
+    median?: number | null;
+    /** Lowest and highest estimate cards by ordinal, so they are available for non-numeric decks too. */
+    min?: string | null;
+}
+
+export interface CountdownTick {
+    secs_left: number;
+}
+
+export interface CreatedRoom {
+    card_set: string[];
+    cards: Card[];
+    /** Short code to share with players instead of the room Uuid. */
+    join_code: string;
+    options: RoomOptions;
+    private: boolean;
+    /** ID of the request which created the room, when it is given by the client. */
+    request_id?: string | null;
+    uuid: string;
+}
+
+export type DeckPreset = "Fibonacci" | "ModifiedFibonacci" | "PowersOfTwo" | "TShirt" | "Hours";
+
+export interface FinalizedGame {
+    estimate: string;
+    game_uuid: string;
+    reason?: string | null;
+}
+
+export interface GameSnapshot {
+    attempt: number;
+    countdown_secs?: number | null;
+    description?: string | null;
+    link?: string | null;
+    /** Card played by the player who resumes the session. */
+    own_card?: string | null;
+    /** Uuids of players who have played in the current round. */
+    played: string[];
+    /** Present once the cards of the current round are revealed. */
+    revealed_cards?: RevealedCards | null;
+    title: string;
+    uuid: string;
+}
+
+export interface KickedFromRoom {
+    banned: boolean;
+    room_uuid: string;
+}
+
+export interface LoginUserReq {
+    params: Params;
+    provider_id: string;
+}
+
+export interface OAuthParams {
+    auth_code: string;
+}
+
+export type Params =
+    | OAuthParams
+    | null;
+
+export interface PlayedHand {
+    card: string;
+    player_uuid: string;
+}
+
+/** Error when handling a request. Details of server errors are not exposed to the client. */
+export interface RequestError {
+    /** Stable code derived from the error kind, for clients to handle the error with. */
+    code: string;
+    message: string;
+    /** ID of the request which caused the error, when it is given by the client. */
+    request_id?: string | null;
+}
+
+/** Request of websocket client. The ID given by the client is echoed in the reply to the request, so that the client can match them. */
+export type RequestFrame = { request_id?: string | null } & (
+    | { card_set?: string[] | null; deck_preset?: DeckPreset | null; deck_uuid?: string | null; options?: RoomOptions; passphrase?: string | null; type: "CreateRoom" }
+    | { join_code?: string | null; passphrase?: string | null; room_uuid?: string | null; spectator?: boolean; type: "JoinRoom" }
+    | { player_uuid: string; type: "KickPlayer" }
+    | { player_uuid: string; type: "BanPlayer" }
+    | { card_set?: string[] | null; deck_preset?: DeckPreset | null; options?: RoomOptions | null; passphrase?: string | null; type: "UpdateRoomConfig" }
+    | { countdown_secs?: number | null; description?: string | null; title: string; type: "StartGame" }
+    | { card: string; type: "PlayCard" }
+    | { type: "RevealCards" }
+    | { countdown_secs?: number | null; type: "ResetGame" }
+    | { estimate: string; reason?: string | null; type: "FinalizeGame" }
+    | { description?: string | null; link?: string | null; title: string; type: "AddStory" }
+    | { story_uuids: string[]; type: "ReorderBacklog" }
+    | { story_uuid: string; type: "RemoveStory" }
+    | { type: "SkipStory" }
+    | { countdown_secs?: number | null; type: "NextStory" }
+    | { last_seq?: number | null; resume_token: string; type: "ResumeSession" }
+);
+
+export type RequestMessage =
+    | { card_set?: string[] | null; deck_preset?: DeckPreset | null; deck_uuid?: string | null; options?: RoomOptions; passphrase?: string | null; type: "CreateRoom" }
+    | { join_code?: string | null; passphrase?: string | null; room_uuid?: string | null; spectator?: boolean; type: "JoinRoom" }
+    | { player_uuid: string; type: "KickPlayer" }
+    | { player_uuid: string; type: "BanPlayer" }
+    | { card_set?: string[] | null; deck_preset?: DeckPreset | null; options?: RoomOptions | null; passphrase?: string | null; type: "UpdateRoomConfig" }
+    | { countdown_secs?: number | null; description?: string | null; title: string; type: "StartGame" }
+    | { card: string; type: "PlayCard" }
+    | { type: "RevealCards" }
+    | { countdown_secs?: number | null; type: "ResetGame" }
+    | { estimate: string; reason?: string | null; type: "FinalizeGame" }
+    | { description?: string | null; link?: string | null; title: string; type: "AddStory" }
+    | { story_uuids: string[]; type: "ReorderBacklog" }
+    | { story_uuid: string; type: "RemoveStory" }
+    | { type: "SkipStory" }
+    | { countdown_secs?: number | null; type: "NextStory" }
+    | { last_seq?: number | null; resume_token: string; type: "ResumeSession" };
+
+export interface ResetRound {
+    attempt: number;
+    countdown_secs?: number | null;
+}
+
+/** Response to websocket client. Events of a room are numbered by the sequence of the room, so that the client can tell which events it has missed and have them replayed when it resumes its session. */
+export type ResponseFrame = { seq?: number | null } & (
+    | { Error: RequestError }
+    | { Acknowledged: Acknowledgement }
+    | { RoomCreated: CreatedRoom }
+    | { RoomClosed: string }
+    | { UserJoined: string }
+    | { UserLeft: string }
+    | { Kicked: KickedFromRoom }
+    | { TopicUpdated: string }
+    | { ConfigUpdated: RoomConfig }
+    | { GameStarted: StartedGame }
+    | { CardPlayed: string }
+    | { CardPlayFailed: string }
+    | { CardsRevealed: RevealedCards }
+    | { CountdownTicked: CountdownTick }
+    | { GameReset: ResetRound }
+    | { GameFinalized: FinalizedGame }
+    | { BacklogUpdated: Story[] }
+    | { GameEnded: string }
+    | { SessionStarted: StartedSession }
+    | { SessionResumed: RoomSnapshot }
+);
+
+export type ResponseMessage =
+    | { Error: RequestError }
+    | { Acknowledged: Acknowledgement }
+    | { RoomCreated: CreatedRoom }
+    | { RoomClosed: string }
+    | { UserJoined: string }
+    | { UserLeft: string }
+    | { Kicked: KickedFromRoom }
+    | { TopicUpdated: string }
+    | { ConfigUpdated: RoomConfig }
+    | { GameStarted: StartedGame }
+    | { CardPlayed: string }
+    | { CardPlayFailed: string }
+    | { CardsRevealed: RevealedCards }
+    | { CountdownTicked: CountdownTick }
+    | { GameReset: ResetRound }
+    | { GameFinalized: FinalizedGame }
+    | { BacklogUpdated: Story[] }
+    | { GameEnded: string }
+    | { SessionStarted: StartedSession }
+    | { SessionResumed: RoomSnapshot };
+
+/** Cards played in the game, sorted by their ordinals in the card set. */
+export interface RevealedCards {
+    /** Uuids of players in the room who did not play a card. */
+    abstained: string[];
+    attempt: number;
+    cards: string[];
+    /** Empty in anonymous mode, so that cards cannot be mapped to players. */
+    hands: PlayedHand[];
+    stats: CardStats;
+}
+
+export interface RoomConfig {
+    card_set: string[];
+    cards: Card[];
+    options: RoomOptions;
+    private: boolean;
+}
+
+/** Room-level settings which can be changed by the room owner mid-session. Persisted as JSON so that new options do not require a schema change. */
+export interface RoomOptions {
+    /** Reveal and persist the played cards without who played them. */
+    anonymous?: boolean;
+    /** Reveal the cards as soon as every player, except spectators, has played. */
+    auto_reveal?: boolean;
+    /** Reject new players from joining the room. */
+    locked?: boolean;
+}
+
+/** State of the room for the player who resumes the session, in place of the messages missed while disconnected which are no longer buffered. */
+export interface RoomSnapshot {
+    backlog: Story[];
+    config: RoomConfig;
+    game?: GameSnapshot | null;
+    /** Uuids of players in the room, including spectators. */
+    players: string[];
+    /** Sequence number of the latest room event reflected in the snapshot. */
+    seq: number;
+    spectators: string[];
+    uuid: string;
+}
+
+export interface StartedGame {
+    countdown_secs?: number | null;
+    description?: string | null;
+    link?: string | null;
+    title: string;
+}
+
+/** Sent once the connection is established. The token resumes the session when the connection drops, and is replaced by the token of the resuming connection once used. */
+export interface StartedSession {
+    resume_token: string;
+}
+
+/** Story waiting in the backlog of a room to be estimated. */
+export interface Story {
+    description?: string | null;
+    external_id?: string | null;
+    /** Link to the story in external issue tracker. */
+    link?: string | null;
+    /** Estimate suggested before the game is played. */
+    pre_estimate?: string | null;
+    title: string;
+    uuid: string;
+}